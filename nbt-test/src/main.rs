@@ -3,6 +3,10 @@ fn main() {
     // sleep 1s
     println!("============ small test ============");
     big_read_test();
+    println!("============ write test ============");
+    write_test();
+    println!("============ array read test ============");
+    array_read_test();
     println!("============ cli test ============");
     cli_read_test();
 }
@@ -246,6 +250,98 @@ fn read_test(in_data: Vec<u8>) {
     test_fastnbt(data);
 }
 
+/// 模拟一个区块: 大量的数组
+fn gen_chunk() -> shen_nbt5::NbtValue {
+    use shen_nbt5::{NbtList, NbtValue};
+    let sections = (0..24)
+        .map(|y| {
+            vec![
+                ("Y".to_string(), NbtValue::Byte(y as i8)),
+                (
+                    "BlockStates".to_string(),
+                    NbtValue::LongArray((0..1024).map(|i| i * 0x0101_0101 + y).collect()),
+                ),
+                (
+                    "Biomes".to_string(),
+                    NbtValue::IntArray((0..1024).map(|i| i as i32 - y as i32).collect()),
+                ),
+                (
+                    "SkyLight".to_string(),
+                    NbtValue::ByteArray((0..2048).map(|i| i as i8).collect()),
+                ),
+                (
+                    "BlockLight".to_string(),
+                    NbtValue::ByteArray((0..2048).map(|i| (i * 3) as i8).collect()),
+                ),
+            ]
+            .into()
+        })
+        .collect();
+    NbtValue::Compound(
+        vec![
+            ("DataVersion".to_string(), NbtValue::Int(3700)),
+            ("sections".to_string(), NbtValue::List(NbtList::Compound(sections))),
+        ]
+        .into(),
+    )
+}
+
+fn write_test_with<W: shen_nbt5::nbt_version::NbtWriteTrait>(
+    value: &shen_nbt5::NbtValue,
+    name: &str,
+) {
+    const TIMES: usize = 1000;
+    let len = value.to_binary::<W>().unwrap().len() * TIMES;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    test_lib!(
+        {
+            for _ in 0..TIMES {
+                let _buff = std::hint::black_box(value.to_binary::<W>().unwrap());
+            }
+        },
+        name,
+        len
+    );
+}
+
+fn write_test() {
+    use shen_nbt5::nbt_version::{BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2};
+    let value = gen_chunk();
+    write_test_with::<Java>(&value, "nbt v5 write Java");
+    write_test_with::<JavaNetAfter1_20_2>(&value, "nbt v5 write JavaNetAfter1_20_2");
+    write_test_with::<BedrockDisk>(&value, "nbt v5 write BedrockDisk");
+    write_test_with::<BedrockNetVarInt>(&value, "nbt v5 write BedrockNetVarInt");
+}
+
+fn array_read_test_with<F>(value: &shen_nbt5::NbtValue, name: &str)
+where
+    F: shen_nbt5::nbt_version::NbtWriteTrait + shen_nbt5::nbt_version::NbtReadTrait,
+{
+    const TIMES: usize = 1000;
+    let mut data = value.to_binary::<F>().unwrap();
+    let len = data.len() * TIMES;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    test_lib!(
+        {
+            for _ in 0..TIMES {
+                let _value = std::hint::black_box(
+                    shen_nbt5::NbtValue::from_binary::<F>(data.as_mut_slice()).unwrap(),
+                );
+            }
+        },
+        name,
+        len
+    );
+}
+
+fn array_read_test() {
+    use shen_nbt5::nbt_version::{BedrockDisk, BedrockNetVarInt, Java};
+    let value = gen_chunk();
+    array_read_test_with::<Java>(&value, "nbt v5 read Java");
+    array_read_test_with::<BedrockDisk>(&value, "nbt v5 read BedrockDisk");
+    array_read_test_with::<BedrockNetVarInt>(&value, "nbt v5 read BedrockNetVarInt");
+}
+
 fn cli_read_test() {
     let mut args = std::env::args();
     // 如果有, 取出
@@ -0,0 +1,321 @@
+//! `shen-nbt5` 的 `#[derive(ToNbt, FromNbt)]`
+//!
+//! 不要直接依赖这个 crate, 开启 `shen-nbt5` 的 `derive` feature 就行
+//!
+//! 支持的属性见 `shen_nbt5::convert`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr,
+    Path, PathArguments, Type,
+};
+
+#[proc_macro_derive(ToNbt, attributes(nbt))]
+pub fn derive_to_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_nbt(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(FromNbt, attributes(nbt))]
+pub fn derive_from_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_nbt(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// 字段缺少时的处理
+enum Missing {
+    /// 报错
+    Required,
+    /// `Default::default()`
+    Trait,
+    /// 调用一个函数
+    Path(Path),
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    key: String,
+    default: Missing,
+    skip: bool,
+    flatten: bool,
+    /// 自定义转换的模块, 里面有 `to_nbt` 和 `from_nbt`
+    with: Option<Path>,
+    /// `Option<T>` 的 `T`
+    option: Option<Type>,
+}
+
+fn expand_to_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in input.generics.type_params() {
+        let ident = &param.ident;
+        generics.make_where_clause().predicates.push(parse_quote!(#ident: __nbt::ToNbt));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let writes = fields.iter().filter(|f| !f.skip).map(|field| {
+        let ident = &field.ident;
+        let key = &field.key;
+        if field.flatten {
+            return quote! {
+                __nbt::flatten_into(&mut __compound, __nbt::ToNbt::to_nbt(&self.#ident)?, #key)?;
+            };
+        }
+        let to = match &field.with {
+            Some(with) => quote!(#with::to_nbt),
+            None => quote!(__nbt::ToNbt::to_nbt),
+        };
+        match field.option {
+            Some(_) => quote! {
+                if let __nbt::Option::Some(value) = &self.#ident {
+                    __compound.push(#key, #to(value).map_err(|e| e.in_field(#key))?);
+                }
+            },
+            None => quote! {
+                __compound.push(#key, #to(&self.#ident).map_err(|e| e.in_field(#key))?);
+            },
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            use ::shen_nbt5::convert::__private as __nbt;
+
+            impl #impl_generics __nbt::ToNbt for #name #ty_generics #where_clause {
+                fn to_nbt(&self) -> __nbt::NbtResult<__nbt::NbtValue> {
+                    let mut __compound = __nbt::NbtCompound::new();
+                    #(#writes)*
+                    __nbt::Result::Ok(__nbt::NbtValue::Compound(__compound))
+                }
+            }
+        };
+    })
+}
+
+fn expand_from_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in input.generics.type_params() {
+        let ident = &param.ident;
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#ident: __nbt::FromNbt));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let local = |field: &Field| {
+        let name = field.ident.to_string();
+        Ident::new(
+            &format!("__field_{}", name.trim_start_matches("r#")),
+            proc_macro2::Span::call_site(),
+        )
+    };
+    let read = |field: &Field| {
+        let var = local(field);
+        let key = &field.key;
+        let ty = &field.ty;
+        if field.skip {
+            return quote!(let #var = __nbt::Default::default(););
+        }
+        if field.flatten {
+            return quote!(let #var = <#ty as __nbt::FromNbt>::from_compound(__compound)?;);
+        }
+        let from = match (&field.with, &field.option) {
+            (Some(with), _) => quote!(#with::from_nbt),
+            (None, Some(inner)) => quote!(<#inner as __nbt::FromNbt>::from_nbt),
+            (None, None) => quote!(<#ty as __nbt::FromNbt>::from_nbt),
+        };
+        let missing = match (&field.default, &field.option) {
+            (Missing::Trait, _) => quote!(__nbt::Default::default()),
+            (Missing::Path(path), _) => quote!(#path()),
+            (Missing::Required, Some(_)) => quote!(__nbt::Option::None),
+            (Missing::Required, None) => quote!(return __nbt::Result::Err(__nbt::missing(#key))),
+        };
+        let found = match field.option {
+            Some(_) => quote!(__nbt::Option::Some(#from(value).map_err(|e| e.in_field(#key))?)),
+            None => quote!(#from(value).map_err(|e| e.in_field(#key))?),
+        };
+        quote! {
+            let #var = match __compound.remove(#key) {
+                __nbt::Option::Some(value) => #found,
+                __nbt::Option::None => #missing,
+            };
+        }
+    };
+    // flatten 的字段最后读, 拿到的是别的字段用剩下的 key
+    let reads = fields.iter().filter(|f| !f.flatten).chain(fields.iter().filter(|f| f.flatten));
+    let reads = reads.map(read);
+    let inits = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let var = local(field);
+        quote!(#ident: #var)
+    });
+
+    Ok(quote! {
+        const _: () = {
+            use ::shen_nbt5::convert::__private as __nbt;
+
+            impl #impl_generics __nbt::FromNbt for #name #ty_generics #where_clause {
+                fn from_nbt(value: __nbt::NbtValue) -> __nbt::NbtResult<Self> {
+                    match value {
+                        __nbt::NbtValue::Compound(mut compound) => Self::from_compound(&mut compound),
+                        other => __nbt::Result::Err(__nbt::NbtError::IncorrectType(10, other.tag())),
+                    }
+                }
+
+                fn from_compound(__compound: &mut __nbt::NbtCompound) -> __nbt::NbtResult<Self> {
+                    #(#reads)*
+                    __nbt::Result::Ok(Self { #(#inits),* })
+                }
+            }
+        };
+    })
+}
+
+fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "只支持有名字段的结构体")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "只支持有名字段的结构体")),
+    };
+
+    let mut rename_all = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("nbt")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                let rule: LitStr = meta.value()?.parse()?;
+                if !RENAME_RULES.contains(&rule.value().as_str()) {
+                    return Err(meta.error(format!("未知的 rename_all: {}", rule.value())));
+                }
+                rename_all = Some(rule.value());
+                Ok(())
+            } else {
+                Err(meta.error("未知的属性"))
+            }
+        })?;
+    }
+
+    fields
+        .iter()
+        .map(|f| {
+            let ident = f.ident.clone().expect("有名字段");
+            let name = ident.to_string().trim_start_matches("r#").to_string();
+            let mut field = Field {
+                key: match &rename_all {
+                    Some(rule) => rename(&name, rule),
+                    None => name,
+                },
+                ident,
+                ty: f.ty.clone(),
+                default: Missing::Required,
+                skip: false,
+                flatten: false,
+                with: None,
+                option: option_inner(&f.ty),
+            };
+            for attr in f.attrs.iter().filter(|a| a.path().is_ident("nbt")) {
+                attr.parse_nested_meta(|meta| {
+                    let set_with = |field: &mut Field, path: Path| {
+                        if field.with.is_some() {
+                            return Err(meta.error("只能指定一种转换方式"));
+                        }
+                        field.with = Some(path);
+                        Ok(())
+                    };
+                    if meta.path.is_ident("rename") {
+                        field.key = meta.value()?.parse::<LitStr>()?.value();
+                    } else if meta.path.is_ident("default") {
+                        field.default = match meta.value() {
+                            Ok(value) => Missing::Path(value.parse::<LitStr>()?.parse()?),
+                            Err(_) => Missing::Trait,
+                        };
+                    } else if meta.path.is_ident("skip") {
+                        field.skip = true;
+                    } else if meta.path.is_ident("flatten") {
+                        field.flatten = true;
+                    } else if meta.path.is_ident("with") {
+                        set_with(&mut field, meta.value()?.parse::<LitStr>()?.parse()?)?;
+                    } else if meta.path.is_ident("byte_array") {
+                        set_with(&mut field, parse_quote!(::shen_nbt5::convert::byte_array))?;
+                    } else if meta.path.is_ident("int_array") {
+                        set_with(&mut field, parse_quote!(::shen_nbt5::convert::int_array))?;
+                    } else if meta.path.is_ident("long_array") {
+                        set_with(&mut field, parse_quote!(::shen_nbt5::convert::long_array))?;
+                    } else {
+                        return Err(meta.error("未知的属性"));
+                    }
+                    Ok(())
+                })?;
+            }
+            if field.flatten
+                && (field.with.is_some() || !matches!(field.default, Missing::Required))
+            {
+                return Err(syn::Error::new_spanned(
+                    &f.ty,
+                    "flatten 不能和 default / with / 数组类型一起用",
+                ));
+            }
+            Ok(field)
+        })
+        .collect()
+}
+
+/// `Option<T>` 里的 `T`
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner.clone()),
+        _ => None,
+    }
+}
+
+const RENAME_RULES: [&str; 7] = [
+    "camelCase",
+    "PascalCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "lowercase",
+    "UPPERCASE",
+];
+
+/// 按 `rename_all` 的规则转换字段名 (字段名是 snake_case)
+fn rename(name: &str, rule: &str) -> String {
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new(),
+        }
+    };
+    match rule {
+        "camelCase" => {
+            let pascal = rename(name, "PascalCase");
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(c) => c.to_lowercase().chain(chars).collect(),
+                None => pascal,
+            }
+        }
+        "PascalCase" => name.split('_').map(capitalize).collect(),
+        "SCREAMING_SNAKE_CASE" | "UPPERCASE" => name.to_uppercase(),
+        "kebab-case" => name.replace('_', "-"),
+        "lowercase" => name.to_lowercase(),
+        _ => name.to_string(),
+    }
+}
@@ -0,0 +1,201 @@
+//! 规范化写入 和 内容哈希
+//!
+//! 同一份数据, 不管 key 的顺序, 不管从什么格式读进来
+//! 规范化之后写出来的字节都一样, 哈希也一样
+//!
+//! 规范化规则:
+//! - Compound 按 key 的字节序排序
+//! - 所有的 NaN 都换成 `f32::NAN` / `f64::NAN`
+//! - 空 List 的元素类型统一为 End(0)
+
+use crate::{nbt_version, NbtCompound, NbtList, NbtResult, NbtValue};
+
+/// FNV-1a 64 位
+///
+/// 不用 std 的 `DefaultHasher`, 因为他不保证跨版本稳定
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self { Self(Self::OFFSET) }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    #[inline]
+    fn write_len(&mut self, len: usize) { self.write(&(len as u32).to_be_bytes()); }
+
+    #[inline]
+    fn write_str(&mut self, data: &str) {
+        self.write_len(data.len());
+        self.write(data.as_bytes());
+    }
+
+    fn write_i8s(&mut self, data: &[i8]) {
+        self.write_len(data.len());
+        data.iter().for_each(|n| self.write(&[*n as u8]));
+    }
+
+    fn write_i32s(&mut self, data: &[i32]) {
+        self.write_len(data.len());
+        data.iter().for_each(|n| self.write(&n.to_be_bytes()));
+    }
+
+    fn write_i64s(&mut self, data: &[i64]) {
+        self.write_len(data.len());
+        data.iter().for_each(|n| self.write(&n.to_be_bytes()));
+    }
+
+    fn write_compound(&mut self, data: &NbtCompound) {
+        // 不复制数据, 只排序引用
+        let mut entries = data.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.write_len(entries.len());
+        for (key, value) in entries {
+            self.write_str(key);
+            value.hash_content(self);
+        }
+    }
+}
+
+#[inline]
+fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::NAN
+    } else {
+        value
+    }
+}
+
+#[inline]
+fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else {
+        value
+    }
+}
+
+impl NbtValue {
+    /// 原地规范化
+    ///
+    /// 排序所有 Compound 的 key, 统一 NaN, 空 List 的元素类型改为 End
+    pub fn canonicalize(&mut self) {
+        match self {
+            NbtValue::Float(x) => *x = canonical_f32(*x),
+            NbtValue::Double(x) => *x = canonical_f64(*x),
+            NbtValue::List(x) => x.canonicalize(),
+            NbtValue::Compound(x) => canonicalize_compound(x),
+            _ => (),
+        }
+    }
+
+    /// 返回规范化之后的副本
+    pub fn to_canonical(&self) -> NbtValue {
+        let mut value = self.clone();
+        value.canonicalize();
+        value
+    }
+
+    /// 规范化写入
+    ///
+    /// 逻辑上相同的数据写出来的字节完全一致
+    pub fn to_canonical_binary<W>(&self) -> NbtResult<Vec<u8>>
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        self.to_canonical().to_binary::<W>()
+    }
+
+    /// 稳定的内容哈希
+    ///
+    /// 与 key 顺序, 来源格式, 根节点名字都无关
+    ///
+    /// 跨平台, 跨版本稳定, 可以存下来当缓存的 key
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        self.hash_content(&mut hasher);
+        hasher.0
+    }
+
+    fn hash_content(&self, hasher: &mut ContentHasher) {
+        hasher.write(&[self.tag()]);
+        match self {
+            NbtValue::Byte(x) => hasher.write(&[*x as u8]),
+            NbtValue::Short(x) => hasher.write(&x.to_be_bytes()),
+            NbtValue::Int(x) => hasher.write(&x.to_be_bytes()),
+            NbtValue::Long(x) => hasher.write(&x.to_be_bytes()),
+            NbtValue::Float(x) => hasher.write(&canonical_f32(*x).to_be_bytes()),
+            NbtValue::Double(x) => hasher.write(&canonical_f64(*x).to_be_bytes()),
+            NbtValue::ByteArray(x) => hasher.write_i8s(x),
+            NbtValue::IntArray(x) => hasher.write_i32s(x),
+            NbtValue::LongArray(x) => hasher.write_i64s(x),
+            NbtValue::String(x) => hasher.write_str(x),
+            NbtValue::List(x) => x.hash_content(hasher),
+            NbtValue::Compound(x) => hasher.write_compound(x),
+        }
+    }
+}
+
+fn canonicalize_compound(data: &mut NbtCompound) {
+    // 稳定排序, 重复的 key 保持原来的顺序
+    data.sort_keys();
+    data.values_mut().for_each(NbtValue::canonicalize);
+}
+
+impl NbtList {
+    /// 原地规范化, 空 List 统一为 `End`
+    pub fn canonicalize(&mut self) {
+        if self.is_empty() {
+            *self = NbtList::End;
+            return;
+        }
+        match self {
+            NbtList::Float(x) => x.iter_mut().for_each(|v| *v = canonical_f32(*v)),
+            NbtList::Double(x) => x.iter_mut().for_each(|v| *v = canonical_f64(*v)),
+            NbtList::List(x) => x.iter_mut().for_each(NbtList::canonicalize),
+            NbtList::Compound(x) => x.iter_mut().for_each(canonicalize_compound),
+            _ => (),
+        }
+    }
+
+    /// 和逐个元素写成 `NbtValue` 之后哈希的结果一致
+    fn hash_content(&self, hasher: &mut ContentHasher) {
+        // 空 List 的元素类型统一为 End
+        let tag = if self.is_empty() {
+            0
+        } else {
+            self.element_tag()
+        };
+        hasher.write(&[tag]);
+        hasher.write_len(self.len());
+        for index in 0..self.len() {
+            hasher.write(&[tag]);
+            match self {
+                NbtList::End => (),
+                NbtList::Byte(x) => hasher.write(&[x[index] as u8]),
+                NbtList::Short(x) => hasher.write(&x[index].to_be_bytes()),
+                NbtList::Int(x) => hasher.write(&x[index].to_be_bytes()),
+                NbtList::Long(x) => hasher.write(&x[index].to_be_bytes()),
+                NbtList::Float(x) => hasher.write(&canonical_f32(x[index]).to_be_bytes()),
+                NbtList::Double(x) => hasher.write(&canonical_f64(x[index]).to_be_bytes()),
+                NbtList::ByteArray(x) => hasher.write_i8s(&x[index]),
+                NbtList::String(x) => hasher.write_str(&x[index]),
+                NbtList::List(x) => {
+                    // 和 NbtValue::List 一样, 元素本身的 tag 已经写过了
+                    x[index].hash_content(hasher)
+                }
+                NbtList::Compound(x) => hasher.write_compound(&x[index]),
+                NbtList::IntArray(x) => hasher.write_i32s(&x[index]),
+                NbtList::LongArray(x) => hasher.write_i64s(&x[index]),
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! NbtCompound
+//!
+//! 保持插入顺序的 key-value 表
+//!
+//! 默认只是一个 `Vec`, 需要的时候可以建立哈希索引, 大 Compound 查找变成 O(1)
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::NbtValue;
+
+/// 10: Compound
+///
+/// 保持插入顺序, 从数据里读出来的重复 key 也会原样保留
+///
+/// 查找时总是返回第一个匹配的 key
+#[derive(Clone, Default)]
+pub struct NbtCompound {
+    entries: Vec<(String, NbtValue)>,
+    /// 可选的哈希索引: key -> 第一次出现的下标
+    index: Option<HashMap<String, usize>>,
+}
+
+impl NbtCompound {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            index: None,
+        }
+    }
+
+    /// 建立哈希索引
+    ///
+    /// 之后的 `get` / `insert` / `contains_key` 都是 O(1)
+    pub fn build_index(&mut self) {
+        let mut index = HashMap::with_capacity(self.entries.len());
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            index.entry(key.clone()).or_insert(i);
+        }
+        self.index = Some(index);
+    }
+
+    /// 建立哈希索引, 链式调用版本
+    pub fn with_index(mut self) -> Self {
+        self.build_index();
+        self
+    }
+
+    /// 丢掉哈希索引
+    pub fn drop_index(&mut self) { self.index = None; }
+
+    #[inline]
+    pub fn has_index(&self) -> bool { self.index.is_some() }
+
+    /// 改变了下标之后重建索引
+    #[inline]
+    fn reindex(&mut self) {
+        if self.index.is_some() {
+            self.build_index();
+        }
+    }
+
+    #[inline]
+    fn position(&self, key: &str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(key).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn get(&self, key: &str) -> Option<&NbtValue> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut NbtValue> {
+        self.position(key).map(|i| &mut self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool { self.position(key).is_some() }
+
+    /// 插入一个值
+    ///
+    /// key 已经存在时替换掉原来的值 (位置不变), 并返回旧值
+    pub fn insert(&mut self, key: impl Into<String>, value: NbtValue) -> Option<NbtValue> {
+        let key = key.into();
+        if let Some(i) = self.position(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        if let Some(index) = &mut self.index {
+            index.insert(key.clone(), self.entries.len());
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    /// 直接加到末尾, 不检查 key 是否重复
+    ///
+    /// 读取数据的时候用, 保证重复的 key 也能原样写回去
+    pub fn push(&mut self, key: impl Into<String>, value: NbtValue) {
+        let key = key.into();
+        if let Some(index) = &mut self.index {
+            index.entry(key.clone()).or_insert(self.entries.len());
+        }
+        self.entries.push((key, value));
+    }
+
+    /// 删除一个值, 后面的值保持原来的顺序
+    pub fn remove(&mut self, key: &str) -> Option<NbtValue> {
+        let i = self.position(key)?;
+        let (_, value) = self.entries.remove(i);
+        self.reindex();
+        Some(value)
+    }
+
+    /// 只保留 `f` 返回 `true` 的值
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &mut NbtValue) -> bool) {
+        self.entries.retain_mut(|(k, v)| f(k, v));
+        self.reindex();
+    }
+
+    /// 按 key 的字节序稳定排序
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.reindex();
+    }
+
+    /// 所有的 key-value, 按插入顺序
+    #[inline]
+    pub fn entries(&self) -> &[(String, NbtValue)] { &self.entries }
+
+    #[inline]
+    pub fn into_entries(self) -> Vec<(String, NbtValue)> { self.entries }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, (String, NbtValue)> { self.entries.iter() }
+
+    /// 可以改值, 不能改 key
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut NbtValue)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> { self.entries.iter().map(|(k, _)| k) }
+
+    pub fn values(&self) -> impl Iterator<Item = &NbtValue> { self.entries.iter().map(|(_, v)| v) }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut NbtValue> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+}
+
+/// 只比较内容和顺序, 不管有没有索引
+impl PartialEq for NbtCompound {
+    fn eq(&self, other: &Self) -> bool { self.entries == other.entries }
+}
+
+impl fmt::Debug for NbtCompound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.entries.iter().map(|(k, v)| (k, v))).finish()
+    }
+}
+
+/// 原样保留, 包括重复的 key
+impl From<Vec<(String, NbtValue)>> for NbtCompound {
+    fn from(entries: Vec<(String, NbtValue)>) -> Self {
+        Self {
+            entries,
+            index: None,
+        }
+    }
+}
+
+impl From<NbtCompound> for Vec<(String, NbtValue)> {
+    fn from(value: NbtCompound) -> Self { value.entries }
+}
+
+impl<K: Into<String>> FromIterator<(K, NbtValue)> for NbtCompound {
+    fn from_iter<T: IntoIterator<Item = (K, NbtValue)>>(iter: T) -> Self {
+        let mut compound = NbtCompound::new();
+        compound.extend(iter);
+        compound
+    }
+}
+
+/// 和 `insert` 一样, 重复的 key 会替换掉原来的值
+impl<K: Into<String>> Extend<(K, NbtValue)> for NbtCompound {
+    fn extend<T: IntoIterator<Item = (K, NbtValue)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for NbtCompound {
+    type Item = (String, NbtValue);
+    type IntoIter = std::vec::IntoIter<(String, NbtValue)>;
+
+    fn into_iter(self) -> Self::IntoIter { self.entries.into_iter() }
+}
+
+impl<'a> IntoIterator for &'a NbtCompound {
+    type Item = &'a (String, NbtValue);
+    type IntoIter = std::slice::Iter<'a, (String, NbtValue)>;
+
+    fn into_iter(self) -> Self::IntoIter { self.entries.iter() }
+}
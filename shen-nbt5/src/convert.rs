@@ -0,0 +1,751 @@
+//! 不经过 serde, 直接在 Rust 类型和 `NbtValue` 之间转换
+//!
+//! 开启 `derive` feature 之后可以用 `#[derive(ToNbt, FromNbt)]`:
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use shen_nbt5::{FromNbt, NbtCompound, NbtValue, ToNbt};
+//!
+//! #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+//! #[nbt(rename_all = "PascalCase")]
+//! struct Entity {
+//!     #[nbt(rename = "id")]
+//!     id: String,
+//!     #[nbt(rename = "UUID", int_array)]
+//!     uuid: Vec<i32>,
+//!     custom_name: Option<String>,
+//!     #[nbt(default)]
+//!     invulnerable: bool,
+//!     /// 其他没有用到的 key
+//!     #[nbt(flatten)]
+//!     extra: NbtCompound,
+//! }
+//!
+//! let value = NbtValue::from_snbt("{id: pig, UUID: [I; 1, 2, 3, 4], Air: 300s}").unwrap();
+//! let entity = Entity::from_nbt(value.clone()).unwrap();
+//! assert_eq!(entity.uuid, vec![1, 2, 3, 4]);
+//! assert_eq!(entity.extra.get("Air"), Some(&NbtValue::Short(300)));
+//! assert_eq!(
+//!     entity.to_nbt().unwrap(),
+//!     NbtValue::from_snbt("{id: pig, UUID: [I; 1, 2, 3, 4], Invulnerable: 0b, Air: 300s}").unwrap()
+//! );
+//! # }
+//! ```
+//!
+//! 结构体的属性:
+//! - `#[nbt(rename_all = "...")]`: `camelCase` / `PascalCase` / `snake_case` /
+//!   `SCREAMING_SNAKE_CASE` / `kebab-case` / `lowercase` / `UPPERCASE`
+//!
+//! 字段的属性:
+//! - `#[nbt(rename = "...")]`: 换一个 key
+//! - `#[nbt(default)]` / `#[nbt(default = "path")]`: 没有这个 key 的时候用默认值
+//! - `#[nbt(skip)]`: 不读也不写, 读的时候用 `Default`
+//! - `#[nbt(flatten)]`: 把字段的内容展开到外层, `NbtCompound` / `HashMap` 的话就是收集剩下所有的 key,
+//!   这种字段要放在最后
+//! - `#[nbt(byte_array)]` / `#[nbt(int_array)]` / `#[nbt(long_array)]`: 写成数组而不是 List
+//! - `#[nbt(with = "path")]`: 用 `path::to_nbt` / `path::from_nbt` 转换, 比如 [`uuid`]
+//!
+//! `Option<T>` 字段为 `None` 的时候不写, 没有这个 key 的时候是 `None`
+//!
+//! 另外还有标准库的 `From` / `TryFrom`, 构造字面量的话用 [`nbt!`](crate::nbt):
+//! - 数字 / `bool` / `String` / `&str` 对应同类型的值, 无符号数按补码存
+//! - `Vec<i8>` / `Vec<i32>` / `Vec<i64>` (还有对应的无符号数) 对应数组, 其他 `Vec` 对应 List,
+//!   `Vec<NbtValue>` 可能类型不一致, 只有 `TryFrom`
+//! - `HashMap` / `BTreeMap` 对应 Compound
+//! - `TryFrom<NbtValue>` 的错误和 [`FromNbt`] 一样
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtValue};
+
+/// 转换成 `NbtValue`
+pub trait ToNbt {
+    fn to_nbt(&self) -> NbtResult<NbtValue>;
+}
+
+/// 从 `NbtValue` 转换, 类型不对的时候返回 `IncorrectType` (在字段里的话是 `PathTypeMismatch`)
+pub trait FromNbt: Sized {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self>;
+
+    /// `#[nbt(flatten)]` 的时候用: 从外层 Compound 里拿走需要的 key, 剩下的留给后面的字段
+    ///
+    /// 默认把整个 Compound 都拿走
+    fn from_compound(compound: &mut NbtCompound) -> NbtResult<Self> {
+        Self::from_nbt(NbtValue::Compound(std::mem::take(compound)))
+    }
+}
+
+/// 路径里的 key, 有特殊字符的时候加上引号
+pub(crate) fn path_key(key: &str) -> String {
+    if !key.is_empty() && !key.contains(['.', '[', ']', '"', '\\']) {
+        return key.to_string();
+    }
+    format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 把内层的路径接到外层后面
+fn join_path(outer: String, inner: &str) -> String {
+    match inner {
+        "" => outer,
+        _ if inner.starts_with('[') => outer + inner,
+        _ => outer + "." + inner,
+    }
+}
+
+impl NbtError {
+    /// 给类型错误加上所在的 key
+    ///
+    /// `IncorrectType` 变成 `PathTypeMismatch`, 已经有路径的接在 key 后面
+    pub fn in_field(self, key: &str) -> NbtError { self.in_path(path_key(key)) }
+
+    /// 给类型错误加上所在的下标
+    pub fn at_index(self, index: usize) -> NbtError { self.in_path(format!("[{}]", index)) }
+
+    fn in_path(self, outer: String) -> NbtError {
+        match self {
+            NbtError::IncorrectType(expect, got) => NbtError::PathTypeMismatch(outer, expect, got),
+            NbtError::PathTypeMismatch(path, expect, got) => {
+                NbtError::PathTypeMismatch(join_path(outer, &path), expect, got)
+            }
+            NbtError::PathNotFound(path) => NbtError::PathNotFound(join_path(outer, &path)),
+            other => other,
+        }
+    }
+}
+
+macro_rules! scalar {
+    ($($ty:ty => $variant:ident($tag:literal) as $inner:ty),* $(,)?) => {
+        $(
+            impl ToNbt for $ty {
+                fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::$variant(*self as $inner)) }
+            }
+
+            impl FromNbt for $ty {
+                fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+                    match value {
+                        NbtValue::$variant(v) => Ok(v as $ty),
+                        other => Err(NbtError::IncorrectType($tag, other.tag())),
+                    }
+                }
+            }
+
+            impl From<$ty> for NbtValue {
+                fn from(value: $ty) -> Self { NbtValue::$variant(value as $inner) }
+            }
+
+            impl TryFrom<NbtValue> for $ty {
+                type Error = NbtError;
+
+                fn try_from(value: NbtValue) -> NbtResult<Self> { Self::from_nbt(value) }
+            }
+        )*
+    };
+}
+
+// 无符号数按补码存, 和 serde 那边一样
+scalar! {
+    i8 => Byte(1) as i8,
+    u8 => Byte(1) as i8,
+    i16 => Short(2) as i16,
+    u16 => Short(2) as i16,
+    i32 => Int(3) as i32,
+    u32 => Int(3) as i32,
+    i64 => Long(4) as i64,
+    u64 => Long(4) as i64,
+    f32 => Float(5) as f32,
+    f64 => Double(6) as f64,
+}
+
+impl ToNbt for bool {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::Byte(*self as i8)) }
+}
+
+/// 和游戏里的 getBoolean 一样, 任何数字类型非 0 都是 true
+impl FromNbt for bool {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        match value {
+            NbtValue::Byte(v) => Ok(v != 0),
+            NbtValue::Short(v) => Ok(v != 0),
+            NbtValue::Int(v) => Ok(v != 0),
+            NbtValue::Long(v) => Ok(v != 0),
+            NbtValue::Float(v) => Ok(v != 0.0),
+            NbtValue::Double(v) => Ok(v != 0.0),
+            other => Err(NbtError::IncorrectType(1, other.tag())),
+        }
+    }
+}
+
+impl From<bool> for NbtValue {
+    fn from(value: bool) -> Self { NbtValue::Byte(value as i8) }
+}
+
+impl TryFrom<NbtValue> for bool {
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> { Self::from_nbt(value) }
+}
+
+impl ToNbt for str {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::String(self.to_string())) }
+}
+
+impl ToNbt for String {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::String(self.clone())) }
+}
+
+impl FromNbt for String {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        match value {
+            NbtValue::String(v) => Ok(v),
+            other => Err(NbtError::IncorrectType(8, other.tag())),
+        }
+    }
+}
+
+impl ToNbt for NbtValue {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(self.clone()) }
+}
+
+impl FromNbt for NbtValue {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> { Ok(value) }
+}
+
+impl ToNbt for NbtList {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::List(self.clone())) }
+}
+
+impl FromNbt for NbtList {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        match value {
+            NbtValue::List(v) => Ok(v),
+            other => Err(NbtError::IncorrectType(9, other.tag())),
+        }
+    }
+}
+
+impl ToNbt for NbtCompound {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { Ok(NbtValue::Compound(self.clone())) }
+}
+
+impl FromNbt for NbtCompound {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        match value {
+            NbtValue::Compound(v) => Ok(v),
+            other => Err(NbtError::IncorrectType(10, other.tag())),
+        }
+    }
+}
+
+macro_rules! value_from {
+    ($($ty:ty => $variant:ident, $into:ident);* $(;)?) => {
+        $(
+            impl From<$ty> for NbtValue {
+                fn from(value: $ty) -> Self { NbtValue::$variant(value) }
+            }
+
+            impl TryFrom<NbtValue> for $ty {
+                type Error = NbtError;
+
+                fn try_from(value: NbtValue) -> NbtResult<Self> { value.$into() }
+            }
+        )*
+    };
+}
+
+value_from! {
+    String => String, into_string;
+    NbtList => List, into_list;
+    NbtCompound => Compound, into_compound;
+    Vec<i8> => ByteArray, into_i8_array;
+    Vec<i32> => IntArray, into_i32_array;
+    Vec<i64> => LongArray, into_i64_array;
+}
+
+impl From<&str> for NbtValue {
+    fn from(value: &str) -> Self { NbtValue::String(value.to_string()) }
+}
+
+/// 其他类型的 `Vec` 写成 List, 读的时候空的 End List 也可以
+macro_rules! list_from {
+    ($($ty:ty => $variant:ident($tag:literal)),* $(,)?) => {
+        $(
+            impl From<Vec<$ty>> for NbtValue {
+                fn from(value: Vec<$ty>) -> Self { NbtValue::List(NbtList::$variant(value)) }
+            }
+
+            impl TryFrom<NbtValue> for Vec<$ty> {
+                type Error = NbtError;
+
+                fn try_from(value: NbtValue) -> NbtResult<Self> {
+                    match value.into_list()? {
+                        NbtList::$variant(v) => Ok(v),
+                        NbtList::End => Ok(Vec::new()),
+                        other => Err(NbtError::IncorrectType($tag, other.element_tag())),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+list_from! {
+    i16 => Short(2),
+    f32 => Float(5),
+    f64 => Double(6),
+    Vec<i8> => ByteArray(7),
+    String => String(8),
+    NbtList => List(9),
+    NbtCompound => Compound(10),
+    Vec<i32> => IntArray(11),
+    Vec<i64> => LongArray(12),
+}
+
+/// 无符号数的 `Vec` 和有符号的一样, 按补码存
+macro_rules! unsigned_vec_from {
+    ($($ty:ty => $signed:ty),* $(,)?) => {
+        $(
+            impl From<Vec<$ty>> for NbtValue {
+                fn from(value: Vec<$ty>) -> Self {
+                    value.into_iter().map(|v| v as $signed).collect::<Vec<_>>().into()
+                }
+            }
+
+            impl TryFrom<NbtValue> for Vec<$ty> {
+                type Error = NbtError;
+
+                fn try_from(value: NbtValue) -> NbtResult<Self> {
+                    Vec::<$signed>::try_from(value).map(|v| v.into_iter().map(|v| v as $ty).collect())
+                }
+            }
+        )*
+    };
+}
+
+unsigned_vec_from! {
+    u8 => i8,
+    u16 => i16,
+    u32 => i32,
+    u64 => i64,
+}
+
+impl From<Vec<bool>> for NbtValue {
+    fn from(value: Vec<bool>) -> Self {
+        NbtValue::List(NbtList::Byte(value.into_iter().map(|v| v as i8).collect()))
+    }
+}
+
+impl TryFrom<NbtValue> for Vec<bool> {
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> {
+        match value.into_list()? {
+            NbtList::Byte(v) => Ok(v.into_iter().map(|v| v != 0).collect()),
+            NbtList::End => Ok(Vec::new()),
+            other => Err(NbtError::IncorrectType(1, other.element_tag())),
+        }
+    }
+}
+
+impl From<Vec<&str>> for NbtValue {
+    fn from(value: Vec<&str>) -> Self {
+        NbtValue::List(NbtList::String(value.into_iter().map(str::to_string).collect()))
+    }
+}
+
+/// 元素类型不一致时返回 `ListTypeNotSame`
+impl TryFrom<Vec<NbtValue>> for NbtValue {
+    type Error = NbtError;
+
+    fn try_from(value: Vec<NbtValue>) -> NbtResult<Self> {
+        NbtList::try_from(value).map(NbtValue::List)
+    }
+}
+
+impl TryFrom<NbtValue> for Vec<NbtValue> {
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> { Ok(value.into_list()?.into_values()) }
+}
+
+fn compound_from<K: Into<String>, V: Into<NbtValue>>(
+    entries: impl IntoIterator<Item = (K, V)>,
+) -> NbtCompound {
+    entries.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+/// Compound 的 List, 每个元素单独转换, 读的时候空的 End List 也可以
+fn compound_list<T: TryFrom<NbtValue, Error = NbtError>>(value: NbtValue) -> NbtResult<Vec<T>> {
+    match value.into_list()? {
+        NbtList::Compound(v) => v
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| T::try_from(NbtValue::Compound(c)).map_err(|e| e.at_index(i)))
+            .collect(),
+        NbtList::End => Ok(Vec::new()),
+        other => Err(NbtError::IncorrectType(10, other.element_tag())),
+    }
+}
+
+impl<K: Into<String>, V: Into<NbtValue>> From<BTreeMap<K, V>> for NbtValue {
+    fn from(value: BTreeMap<K, V>) -> Self { NbtValue::Compound(compound_from(value)) }
+}
+
+impl<K: Into<String>, V: Into<NbtValue>, S> From<HashMap<K, V, S>> for NbtValue {
+    fn from(value: HashMap<K, V, S>) -> Self { NbtValue::Compound(compound_from(value)) }
+}
+
+impl<K: Into<String>, V: Into<NbtValue>> From<Vec<BTreeMap<K, V>>> for NbtValue {
+    fn from(value: Vec<BTreeMap<K, V>>) -> Self {
+        NbtValue::List(NbtList::Compound(value.into_iter().map(compound_from).collect()))
+    }
+}
+
+impl<K: Into<String>, V: Into<NbtValue>, S> From<Vec<HashMap<K, V, S>>> for NbtValue {
+    fn from(value: Vec<HashMap<K, V, S>>) -> Self {
+        NbtValue::List(NbtList::Compound(value.into_iter().map(compound_from).collect()))
+    }
+}
+
+impl<V: TryFrom<NbtValue, Error = NbtError>> TryFrom<NbtValue> for BTreeMap<String, V> {
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> {
+        value
+            .into_compound()?
+            .into_entries()
+            .into_iter()
+            .map(|(key, value)| V::try_from(value).map_err(|e| e.in_field(&key)).map(|v| (key, v)))
+            .collect()
+    }
+}
+
+impl<V, S> TryFrom<NbtValue> for HashMap<String, V, S>
+where
+    V: TryFrom<NbtValue, Error = NbtError>,
+    S: BuildHasher + Default,
+{
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> {
+        value
+            .into_compound()?
+            .into_entries()
+            .into_iter()
+            .map(|(key, value)| V::try_from(value).map_err(|e| e.in_field(&key)).map(|v| (key, v)))
+            .collect()
+    }
+}
+
+impl<V: TryFrom<NbtValue, Error = NbtError>> TryFrom<NbtValue> for Vec<BTreeMap<String, V>> {
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> { compound_list(value) }
+}
+
+impl<V, S> TryFrom<NbtValue> for Vec<HashMap<String, V, S>>
+where
+    V: TryFrom<NbtValue, Error = NbtError>,
+    S: BuildHasher + Default,
+{
+    type Error = NbtError;
+
+    fn try_from(value: NbtValue) -> NbtResult<Self> { compound_list(value) }
+}
+
+impl<T: ToNbt + ?Sized> ToNbt for &T {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { (**self).to_nbt() }
+}
+
+impl<T: ToNbt + ?Sized> ToNbt for Box<T> {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { (**self).to_nbt() }
+}
+
+impl<T: FromNbt> FromNbt for Box<T> {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> { T::from_nbt(value).map(Box::new) }
+}
+
+/// 写成 List, 元素类型和第一个不一样的时候返回带下标的 `PathTypeMismatch`
+impl<T: ToNbt> ToNbt for [T] {
+    fn to_nbt(&self) -> NbtResult<NbtValue> {
+        let mut list = NbtList::End;
+        for (i, item) in self.iter().enumerate() {
+            let value = item.to_nbt().map_err(|e| e.at_index(i))?;
+            list.push(value).map_err(|e| e.at_index(i))?;
+        }
+        Ok(NbtValue::List(list))
+    }
+}
+
+impl<T: ToNbt> ToNbt for Vec<T> {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { self.as_slice().to_nbt() }
+}
+
+/// 从 List 读, 数组也可以
+impl<T: FromNbt> FromNbt for Vec<T> {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        let values = match value {
+            NbtValue::List(list) => list.into_values(),
+            NbtValue::ByteArray(v) => v.into_iter().map(NbtValue::Byte).collect(),
+            NbtValue::IntArray(v) => v.into_iter().map(NbtValue::Int).collect(),
+            NbtValue::LongArray(v) => v.into_iter().map(NbtValue::Long).collect(),
+            other => return Err(NbtError::IncorrectType(9, other.tag())),
+        };
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| T::from_nbt(v).map_err(|e| e.at_index(i)))
+            .collect()
+    }
+}
+
+/// 按 key 的顺序写成 Compound
+impl<T: ToNbt> ToNbt for BTreeMap<String, T> {
+    fn to_nbt(&self) -> NbtResult<NbtValue> {
+        let mut compound = NbtCompound::with_capacity(self.len());
+        for (key, value) in self {
+            compound.push(key.clone(), value.to_nbt().map_err(|e| e.in_field(key))?);
+        }
+        Ok(NbtValue::Compound(compound))
+    }
+}
+
+impl<T: FromNbt> FromNbt for BTreeMap<String, T> {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        NbtCompound::from_nbt(value)?
+            .into_entries()
+            .into_iter()
+            .map(|(key, value)| T::from_nbt(value).map_err(|e| e.in_field(&key)).map(|v| (key, v)))
+            .collect()
+    }
+}
+
+impl<T: ToNbt, S> ToNbt for HashMap<String, T, S> {
+    fn to_nbt(&self) -> NbtResult<NbtValue> {
+        let mut compound = NbtCompound::with_capacity(self.len());
+        for (key, value) in self {
+            compound.push(key.clone(), value.to_nbt().map_err(|e| e.in_field(key))?);
+        }
+        Ok(NbtValue::Compound(compound))
+    }
+}
+
+impl<T: FromNbt, S: BuildHasher + Default> FromNbt for HashMap<String, T, S> {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        NbtCompound::from_nbt(value)?
+            .into_entries()
+            .into_iter()
+            .map(|(key, value)| T::from_nbt(value).map_err(|e| e.in_field(&key)).map(|v| (key, v)))
+            .collect()
+    }
+}
+
+macro_rules! array_module {
+    ($($module:ident: $ty:ty => $variant:ident, $tag:literal, $snbt:literal);* $(;)?) => {
+        $(
+            #[doc = concat!("`#[nbt(", stringify!($module), ")]`: 写成 `", $snbt, "` 这样的数组")]
+            ///
+            /// 读的时候同类型元素的 List 也可以
+            pub mod $module {
+                use crate::{NbtError, NbtResult, NbtValue};
+
+                pub fn to_nbt(value: &[$ty]) -> NbtResult<NbtValue> {
+                    Ok(NbtValue::$variant(value.to_vec()))
+                }
+
+                pub fn from_nbt(value: NbtValue) -> NbtResult<Vec<$ty>> {
+                    match value {
+                        NbtValue::$variant(v) => Ok(v),
+                        NbtValue::List(list) => list
+                            .into_values()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, v)| <$ty as crate::FromNbt>::from_nbt(v).map_err(|e| e.at_index(i)))
+                            .collect(),
+                        other => Err(NbtError::IncorrectType($tag, other.tag())),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+array_module! {
+    byte_array: i8 => ByteArray, 7, "[B; 1b, 2b]";
+    int_array: i32 => IntArray, 11, "[I; 1, 2]";
+    long_array: i64 => LongArray, 12, "[L; 1L, 2L]";
+}
+
+/// `#[nbt(with = "shen_nbt5::convert::uuid")]`
+///
+/// 游戏里的 UUID: 4 个大端序 Int 组成的 IntArray, 对应 `[u8; 16]`
+pub mod uuid {
+    use crate::{NbtError, NbtResult, NbtValue};
+
+    pub fn to_nbt(value: &[u8; 16]) -> NbtResult<NbtValue> {
+        Ok(NbtValue::IntArray(
+            value
+                .chunks_exact(4)
+                .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+        ))
+    }
+
+    pub fn from_nbt(value: NbtValue) -> NbtResult<[u8; 16]> {
+        match value {
+            NbtValue::IntArray(v) if v.len() == 4 => {
+                let mut bytes = [0u8; 16];
+                for (chunk, int) in bytes.chunks_exact_mut(4).zip(v) {
+                    chunk.copy_from_slice(&int.to_be_bytes());
+                }
+                Ok(bytes)
+            }
+            NbtValue::IntArray(v) => {
+                Err(NbtError::UnknownErr(format!("UUID 应该是 4 个 Int, 实际是 {} 个", v.len())))
+            }
+            other => Err(NbtError::IncorrectType(11, other.tag())),
+        }
+    }
+}
+
+/// derive 和 `nbt!` 展开的代码用到的东西
+#[doc(hidden)]
+pub mod __private {
+    pub use std::default::Default;
+    pub use std::option::Option;
+    pub use std::result::Result;
+
+    pub use crate::{FromNbt, NbtCompound, NbtError, NbtResult, NbtValue, ToNbt};
+
+    /// 把 flatten 字段写出来的 Compound 展开到外层
+    pub fn flatten_into(compound: &mut NbtCompound, value: NbtValue, field: &str) -> NbtResult<()> {
+        match value {
+            NbtValue::Compound(inner) => {
+                for (key, value) in inner.into_entries() {
+                    compound.push(key, value);
+                }
+                Ok(())
+            }
+            other => Err(NbtError::IncorrectType(10, other.tag()).in_field(field)),
+        }
+    }
+
+    /// 缺少必须的字段
+    pub fn missing(field: &str) -> NbtError { NbtError::PathNotFound(super::path_key(field)) }
+
+    /// `nbt!` 里的字面量: Rust 的写法 (`1i32`, `"a\n"`) 和 SNBT 的后缀 (`1b`, `2.5f`) 都可以
+    ///
+    /// 没有后缀的整数是 `int_tag` 类型, 数组里就是数组元素的类型
+    #[track_caller]
+    pub fn literal(text: &str, int_tag: crate::NbtTypeId) -> NbtValue {
+        let value = match text {
+            "true" => Some(NbtValue::Byte(1)),
+            "false" => Some(NbtValue::Byte(0)),
+            _ if text.starts_with(['"', '\'', 'r']) => rust_string(text).map(NbtValue::String),
+            _ => crate::snbt::number(&snbt_number(text), int_tag).ok().flatten(),
+        };
+        value.unwrap_or_else(|| panic!("nbt!: 无效的字面量 {}", text))
+    }
+
+    /// Rust 的数字后缀换成 SNBT 的
+    fn snbt_number(text: &str) -> String {
+        const SUFFIXES: [(&str, &str); 10] = [
+            ("i8", "sb"),
+            ("u8", "ub"),
+            ("i16", "ss"),
+            ("u16", "us"),
+            ("i32", "si"),
+            ("u32", "ui"),
+            ("i64", "sl"),
+            ("u64", "ul"),
+            ("f32", "f"),
+            ("f64", "d"),
+        ];
+        let is_hex = text.trim_start_matches('-').starts_with("0x");
+        for (rust, snbt) in SUFFIXES {
+            if is_hex && rust.starts_with('f') {
+                continue;
+            }
+            if let Some(body) = text.strip_suffix(rust) {
+                return format!("{}{}", body, snbt);
+            }
+        }
+        text.to_string()
+    }
+
+    /// Rust 的字符串 / 字符字面量
+    fn rust_string(text: &str) -> Option<String> {
+        if let Some(raw) = text.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            return raw.get(hashes + 1..raw.len().checked_sub(hashes + 1)?).map(str::to_string);
+        }
+        let body = text.get(1..text.len().checked_sub(1)?)?;
+        let mut out = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next()? {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                '0' => out.push('\0'),
+                c @ ('\\' | '\'' | '"') => out.push(c),
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    out.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+                }
+                'u' => {
+                    let rest = chars.as_str().strip_prefix('{')?;
+                    let (hex, rest) = rest.split_once('}')?;
+                    out.push(char::from_u32(u32::from_str_radix(&hex.replace('_', ""), 16).ok()?)?);
+                    chars = rest.chars();
+                }
+                // 行尾的 `\` 会跳过下一行开头的空白
+                '\n' => chars = chars.as_str().trim_start().chars(),
+                _ => return None,
+            }
+        }
+        Some(out)
+    }
+
+    /// `nbt!` 里的 List, 元素类型不一致的时候 panic
+    #[track_caller]
+    pub fn list(values: Vec<NbtValue>) -> NbtValue {
+        let mut list = crate::NbtList::End;
+        for value in values {
+            let tag = value.tag();
+            if list.push(value).is_err() {
+                panic!("nbt!: {} 类型的值不能放进元素类型为 {} 的 List", tag, list.element_tag());
+            }
+        }
+        NbtValue::List(list)
+    }
+
+    /// `nbt!` 里的 `[B;` `[I;` `[L;`, `tag` 是元素的类型
+    #[track_caller]
+    pub fn array(tag: crate::NbtTypeId, values: Vec<NbtValue>) -> NbtValue {
+        let kind = match tag {
+            1 => 'B',
+            3 => 'I',
+            _ => 'L',
+        };
+        if let Some(value) = values.iter().find(|v| v.tag() != tag) {
+            panic!("nbt!: {} 类型的值不能放进 [{};", value.tag(), kind);
+        }
+        match kind {
+            'B' => {
+                NbtValue::ByteArray(values.into_iter().filter_map(|v| v.into_i8().ok()).collect())
+            }
+            'I' => {
+                NbtValue::IntArray(values.into_iter().filter_map(|v| v.into_i32().ok()).collect())
+            }
+            _ => {
+                NbtValue::LongArray(values.into_iter().filter_map(|v| v.into_i64().ok()).collect())
+            }
+        }
+    }
+}
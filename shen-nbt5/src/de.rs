@@ -0,0 +1,891 @@
+//! serde 反序列化, 直接从二进制读, 不经过 `NbtValue`; [`from_value`] 直接读 `NbtValue`, 不经过二进制
+//!
+//! 类型对应关系和 [`crate::ser`] 一样, 另外:
+//! - `u8` / `u16` / `u32` / `u64` 按补码读对应的有符号类型
+//! - `bool` 可以从任何数字类型读, 非 0 就是 `true`
+//! - 枚举可以是 String (单元变体), 只有一个 key 的 Compound, 或者 Byte / Short / Int (单元变体的序号)
+//! - 结构体里缺少的 `Option` 字段是 `None`
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use shen_nbt5::nbt_version::Java;
+//! use shen_nbt5::{from_bytes, NbtValue};
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Item {
+//!     id: String,
+//!     count: i8,
+//!     tag: Option<String>,
+//! }
+//!
+//! let value = NbtValue::from_snbt("{id: 'minecraft:stone', count: 64b}").unwrap();
+//! let mut bytes = value.to_binary::<Java>().unwrap();
+//! let item: Item = from_bytes::<Java, _>(&mut bytes).unwrap();
+//! assert_eq!(item, Item { id: "minecraft:stone".to_string(), count: 64, tag: None });
+//! ```
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::vec;
+
+use serde::de::value::{SeqDeserializer, StrDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IgnoredAny, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+
+use crate::nbt_version::NbtReadTrait;
+use crate::reader::NbtReader;
+use crate::ser::{LIST_TOKEN, TAG_VARIANTS, VALUE_TOKEN};
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue};
+
+impl de::Error for NbtError {
+    fn custom<T: Display>(msg: T) -> Self { NbtError::UnknownErr(msg.to_string()) }
+}
+
+/// 从二进制反序列化, 根节点名字会被忽略
+pub fn from_bytes<R, T>(data: &mut [u8]) -> NbtResult<T>
+where
+    R: NbtReadTrait,
+    T: DeserializeOwned,
+{
+    let mut reader = NbtReader::new(data);
+    let (tag, _name) = R::read_root_header(&mut reader)?;
+    T::deserialize(Deserializer::<R>::new(&mut reader, tag))
+}
+
+/// 把 `NbtValue` 转成任意 `Deserialize` 的值
+///
+/// 直接读, 不经过二进制, 类型对应关系和 [`from_bytes`] 完全一样
+pub fn from_value<T: DeserializeOwned>(value: NbtValue) -> NbtResult<T> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+/// 读取一个已知类型的值的内容 (不包括 tag 和名字)
+pub struct Deserializer<'a, 'data, R> {
+    reader: &'a mut NbtReader<'data>,
+    tag: NbtTypeId,
+    _format: PhantomData<R>,
+}
+
+impl<'a, 'data, R: NbtReadTrait> Deserializer<'a, 'data, R> {
+    pub fn new(reader: &'a mut NbtReader<'data>, tag: NbtTypeId) -> Self {
+        Self {
+            reader,
+            tag,
+            _format: PhantomData,
+        }
+    }
+}
+
+/// 数组交给 serde 自带的 `SeqDeserializer`, 读完之后检查有没有剩下的
+fn visit_array<'de, V, T>(visitor: V, data: Vec<T>) -> NbtResult<V::Value>
+where
+    V: Visitor<'de>,
+    ArrayElement<T>: IntoDeserializer<'de, NbtError>,
+{
+    let mut seq = SeqDeserializer::new(data.into_iter().map(ArrayElement));
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+impl<'de, R: NbtReadTrait> de::Deserializer<'de> for Deserializer<'_, '_, R> {
+    type Error = NbtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            1 => visitor.visit_i8(self.reader.read_i8()),
+            2 => visitor.visit_i16(R::read_i16(self.reader)?),
+            3 => visitor.visit_i32(R::read_i32(self.reader)?),
+            4 => visitor.visit_i64(R::read_i64(self.reader)?),
+            5 => visitor.visit_f32(R::read_f32(self.reader)?),
+            6 => visitor.visit_f64(R::read_f64(self.reader)?),
+            7 => visit_array(visitor, R::read_i8_array(self.reader)?),
+            8 => visitor.visit_string(R::read_nbt_string(self.reader)?),
+            9 => ListAccess::<R>::read(self.reader)?.deserialize_any(visitor),
+            10 => visitor.visit_map(CompoundAccess::<R> {
+                reader: self.reader,
+                tag: 0,
+                _format: PhantomData,
+            }),
+            11 => visit_array(visitor, R::read_i32_array(self.reader)?),
+            12 => visit_array(visitor, R::read_i64_array(self.reader)?),
+            x => Err(NbtError::UnknownType(x)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        // 和游戏里的 getBoolean 一样, 任何数字类型非 0 都是 true
+        match self.tag {
+            1 => visitor.visit_bool(self.reader.read_i8() != 0),
+            2 => visitor.visit_bool(R::read_i16(self.reader)? != 0),
+            3 => visitor.visit_bool(R::read_i32(self.reader)? != 0),
+            4 => visitor.visit_bool(R::read_i64(self.reader)? != 0),
+            5 => visitor.visit_bool(R::read_f32(self.reader)? != 0.0),
+            6 => visitor.visit_bool(R::read_f64(self.reader)? != 0.0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            1 => visitor.visit_u8(self.reader.read_u8()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            2 => visitor.visit_u16(R::read_i16(self.reader)? as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            3 => visitor.visit_u32(R::read_i32(self.reader)? as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            4 => visitor.visit_u64(R::read_i64(self.reader)? as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.tag {
+            7 => visitor.visit_byte_buf(
+                R::read_i8_array(self.reader)?.into_iter().map(|b| b as u8).collect(),
+            ),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        // 能读到就说明有值, 没有的时候 Compound 里根本不会有这个 key
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        // NbtValue / NbtList 的变体由 tag 决定
+        if name == VALUE_TOKEN {
+            return match self.tag {
+                1..=12 => visitor.visit_enum(TagVariant {
+                    variant: TAG_VARIANTS[self.tag as usize],
+                    content: self,
+                }),
+                x => Err(NbtError::UnknownType(x)),
+            };
+        }
+        if name == LIST_TOKEN && self.tag == 9 {
+            let list = ListAccess::<R>::read(self.reader)?;
+            return visitor.visit_enum(TagVariant {
+                variant: TAG_VARIANTS[list.element_tag as usize],
+                content: list,
+            });
+        }
+        match self.tag {
+            8 => {
+                let variant: StringDeserializer<NbtError> =
+                    R::read_nbt_string(self.reader)?.into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            10 => {
+                let tag = self.reader.read_u8();
+                if tag == 0 {
+                    return Err(NbtError::UnknownErr("枚举的 Compound 不能是空的".to_string()));
+                }
+                let name = R::read_nbt_string(self.reader)?;
+                visitor.visit_enum(VariantCompound::<R> {
+                    reader: self.reader,
+                    tag,
+                    name,
+                    _format: PhantomData,
+                })
+            }
+            // 游戏里有些枚举按序号存, 比如 playerGameType
+            1 => visitor.visit_enum((self.reader.read_u8() as u32).into_deserializer()),
+            2 => visitor.visit_enum((R::read_i16(self.reader)? as u16 as u32).into_deserializer()),
+            3 => visitor.visit_enum((R::read_i32(self.reader)? as u32).into_deserializer()),
+            x => Err(NbtError::IncorrectType(8, x)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// List 的元素
+struct ListAccess<'a, 'data, R> {
+    reader: &'a mut NbtReader<'data>,
+    element_tag: NbtTypeId,
+    remaining: usize,
+    _format: PhantomData<R>,
+}
+
+impl<'a, 'data, R: NbtReadTrait> ListAccess<'a, 'data, R> {
+    /// 读取元素类型和长度
+    fn read(reader: &'a mut NbtReader<'data>) -> NbtResult<Self> {
+        let element_tag = reader.read_u8();
+        let len = R::read_i32(reader)?;
+        if len < 0 {
+            return Err(NbtError::UnknownErr(format!("List 长度不能是负数: {}", len)));
+        }
+        if element_tag > 12 || (element_tag == 0 && len != 0) {
+            return Err(NbtError::UnknownType(element_tag));
+        }
+        Ok(Self {
+            reader,
+            element_tag,
+            remaining: len as usize,
+            _format: PhantomData,
+        })
+    }
+}
+
+impl<'de, R: NbtReadTrait> de::Deserializer<'de> for ListAccess<'_, '_, R> {
+    type Error = NbtError;
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> NbtResult<V::Value> {
+        let value = visitor.visit_seq(&mut self)?;
+        if self.remaining != 0 {
+            return Err(NbtError::UnknownErr(format!("List 还剩 {} 个元素没有读", self.remaining)));
+        }
+        Ok(value)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+impl<'de, R: NbtReadTrait> SeqAccess<'de> for ListAccess<'_, '_, R> {
+    type Error = NbtError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> NbtResult<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(Deserializer::<R>::new(self.reader, self.element_tag))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.remaining) }
+}
+
+/// Compound 的键值对, 一直读到 End
+struct CompoundAccess<'a, 'data, R> {
+    reader: &'a mut NbtReader<'data>,
+    /// 下一个值的类型
+    tag: NbtTypeId,
+    _format: PhantomData<R>,
+}
+
+impl<'de, R: NbtReadTrait> MapAccess<'de> for CompoundAccess<'_, '_, R> {
+    type Error = NbtError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> NbtResult<Option<K::Value>> {
+        self.tag = self.reader.read_u8();
+        if self.tag == 0 {
+            return Ok(None);
+        }
+        let name = R::read_nbt_string(self.reader)?;
+        seed.deserialize(KeyDeserializer(name)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> NbtResult<V::Value> {
+        seed.deserialize(Deserializer::<R>::new(self.reader, self.tag))
+    }
+}
+
+/// `{变体名: 内容}` 形式的枚举
+struct VariantCompound<'a, 'data, R> {
+    reader: &'a mut NbtReader<'data>,
+    tag: NbtTypeId,
+    name: String,
+    _format: PhantomData<R>,
+}
+
+impl<'a, 'data, R: NbtReadTrait> VariantCompound<'a, 'data, R> {
+    /// 读完内容之后还要有一个 End
+    fn content<T>(
+        self,
+        read: impl FnOnce(Deserializer<'_, 'data, R>) -> NbtResult<T>,
+    ) -> NbtResult<T> {
+        let value = read(Deserializer::new(self.reader, self.tag))?;
+        match self.reader.read_u8() {
+            0 => Ok(value),
+            _ => {
+                Err(NbtError::UnknownErr(format!("枚举的 Compound 只能有一个 key: {}", self.name)))
+            }
+        }
+    }
+}
+
+impl<'a, 'data, 'de, R: NbtReadTrait> EnumAccess<'de> for VariantCompound<'a, 'data, R> {
+    type Error = NbtError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> NbtResult<(V::Value, Self)> {
+        let name: StringDeserializer<NbtError> = self.name.clone().into_deserializer();
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, R: NbtReadTrait> VariantAccess<'de> for VariantCompound<'_, '_, R> {
+    type Error = NbtError;
+
+    fn unit_variant(self) -> NbtResult<()> {
+        Err(NbtError::UnknownErr(format!("单元变体应该是 String: {}", self.name)))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> NbtResult<T::Value> {
+        self.content(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> NbtResult<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_tuple(de, len, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_struct(de, "", fields, visitor))
+    }
+}
+
+/// 从 `NbtValue` 读, 和 [`Deserializer`] 的类型对应关系一样
+pub struct ValueDeserializer(pub NbtValue);
+
+impl<'de> IntoDeserializer<'de, NbtError> for NbtValue {
+    type Deserializer = ValueDeserializer;
+
+    fn into_deserializer(self) -> ValueDeserializer { ValueDeserializer(self) }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = NbtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::Byte(v) => visitor.visit_i8(v),
+            NbtValue::Short(v) => visitor.visit_i16(v),
+            NbtValue::Int(v) => visitor.visit_i32(v),
+            NbtValue::Long(v) => visitor.visit_i64(v),
+            NbtValue::Float(v) => visitor.visit_f32(v),
+            NbtValue::Double(v) => visitor.visit_f64(v),
+            NbtValue::ByteArray(v) => visit_array(visitor, v),
+            NbtValue::String(v) => visitor.visit_string(v),
+            NbtValue::List(list) => {
+                let mut seq = SeqDeserializer::new(list.into_values().into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            NbtValue::Compound(compound) => visitor.visit_map(ValueCompoundAccess {
+                entries: compound.into_iter(),
+                value: None,
+            }),
+            NbtValue::IntArray(v) => visit_array(visitor, v),
+            NbtValue::LongArray(v) => visit_array(visitor, v),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        // 和游戏里的 getBoolean 一样, 任何数字类型非 0 都是 true
+        match self.0 {
+            NbtValue::Byte(v) => visitor.visit_bool(v != 0),
+            NbtValue::Short(v) => visitor.visit_bool(v != 0),
+            NbtValue::Int(v) => visitor.visit_bool(v != 0),
+            NbtValue::Long(v) => visitor.visit_bool(v != 0),
+            NbtValue::Float(v) => visitor.visit_bool(v != 0.0),
+            NbtValue::Double(v) => visitor.visit_bool(v != 0.0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::Byte(v) => visitor.visit_u8(v as u8),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::Short(v) => visitor.visit_u16(v as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::Int(v) => visitor.visit_u32(v as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::Long(v) => visitor.visit_u64(v as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self.0 {
+            NbtValue::ByteArray(v) => {
+                visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect())
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        // NbtValue / NbtList 的变体由 tag 决定
+        if name == VALUE_TOKEN {
+            return visitor.visit_enum(TagVariant {
+                variant: TAG_VARIANTS[self.0.tag() as usize],
+                content: self,
+            });
+        }
+        if name == LIST_TOKEN {
+            if let NbtValue::List(list) = &self.0 {
+                return visitor.visit_enum(TagVariant {
+                    variant: TAG_VARIANTS[list.element_tag() as usize],
+                    content: self,
+                });
+            }
+        }
+        match self.0 {
+            NbtValue::String(v) => {
+                let variant: StringDeserializer<NbtError> = v.into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            NbtValue::Compound(compound) => {
+                let mut entries = compound.into_iter();
+                let Some((name, value)) = entries.next() else {
+                    return Err(NbtError::UnknownErr("枚举的 Compound 不能是空的".to_string()));
+                };
+                if entries.next().is_some() {
+                    return Err(NbtError::UnknownErr(format!(
+                        "枚举的 Compound 只能有一个 key: {}",
+                        name
+                    )));
+                }
+                visitor.visit_enum(ValueVariant { name, value })
+            }
+            // 游戏里有些枚举按序号存, 比如 playerGameType
+            NbtValue::Byte(v) => visitor.visit_enum((v as u8 as u32).into_deserializer()),
+            NbtValue::Short(v) => visitor.visit_enum((v as u16 as u32).into_deserializer()),
+            NbtValue::Int(v) => visitor.visit_enum((v as u32).into_deserializer()),
+            x => Err(NbtError::IncorrectType(8, x.tag())),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// Compound 的键值对
+struct ValueCompoundAccess {
+    entries: vec::IntoIter<(String, NbtValue)>,
+    /// `next_key` 之后, 等待读取的值
+    value: Option<NbtValue>,
+}
+
+impl<'de> MapAccess<'de> for ValueCompoundAccess {
+    type Error = NbtError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> NbtResult<Option<K::Value>> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(KeyDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> NbtResult<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| NbtError::UnknownErr("next_value 之前没有 key".to_string()))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.entries.len()) }
+}
+
+/// `{变体名: 内容}` 形式的枚举
+struct ValueVariant {
+    name: String,
+    value: NbtValue,
+}
+
+impl<'de> EnumAccess<'de> for ValueVariant {
+    type Error = NbtError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> NbtResult<(V::Value, Self)> {
+        let name: StringDeserializer<NbtError> = self.name.clone().into_deserializer();
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueVariant {
+    type Error = NbtError;
+
+    fn unit_variant(self) -> NbtResult<()> {
+        Err(NbtError::UnknownErr(format!("单元变体应该是 String: {}", self.name)))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> NbtResult<T::Value> {
+        seed.deserialize(ValueDeserializer(self.value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> NbtResult<V::Value> {
+        de::Deserializer::deserialize_tuple(ValueDeserializer(self.value), len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        de::Deserializer::deserialize_struct(ValueDeserializer(self.value), "", fields, visitor)
+    }
+}
+
+/// `NbtValue` / `NbtList` 的变体, 变体名是从 tag 得到的, 内容是 newtype
+struct TagVariant<D> {
+    variant: &'static str,
+    content: D,
+}
+
+impl<'de, D: de::Deserializer<'de, Error = NbtError>> EnumAccess<'de> for TagVariant<D> {
+    type Error = NbtError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> NbtResult<(V::Value, Self)> {
+        let variant: StrDeserializer<NbtError> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de, D: de::Deserializer<'de, Error = NbtError>> VariantAccess<'de> for TagVariant<D> {
+    type Error = NbtError;
+
+    fn unit_variant(self) -> NbtResult<()> {
+        Err(NbtError::UnknownErr(format!("{} 不是单元变体", self.variant)))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> NbtResult<T::Value> {
+        seed.deserialize(self.content)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> NbtResult<V::Value> {
+        self.content.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        self.content.deserialize_any(visitor)
+    }
+}
+
+/// 数组的元素, 和单独的值一样可以按补码读成无符号整数
+struct ArrayElement<T>(T);
+
+macro_rules! array_element {
+    ($($ty:ty => $visit:ident, $unsigned:ident => $visit_unsigned:ident: $uty:ty, $($other:ident)*);* $(;)?) => {
+        $(
+            impl<'de> IntoDeserializer<'de, NbtError> for ArrayElement<$ty> {
+                type Deserializer = Self;
+
+                fn into_deserializer(self) -> Self { self }
+            }
+
+            impl<'de> de::Deserializer<'de> for ArrayElement<$ty> {
+                type Error = NbtError;
+
+                fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+                    visitor.$visit(self.0)
+                }
+
+                fn $unsigned<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+                    visitor.$visit_unsigned(self.0 as $uty)
+                }
+
+                forward_to_deserialize_any! {
+                    bool i8 i16 i32 i64 i128 u128 f32 f64 char str string bytes byte_buf option
+                    unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+                    identifier ignored_any $($other)*
+                }
+            }
+        )*
+    };
+}
+
+array_element! {
+    i8 => visit_i8, deserialize_u8 => visit_u8: u8, u16 u32 u64;
+    i32 => visit_i32, deserialize_u32 => visit_u32: u32, u8 u16 u64;
+    i64 => visit_i64, deserialize_u64 => visit_u64: u64, u8 u16 u32;
+}
+
+/// Compound 的 key, 要整数的时候从字符串解析
+struct KeyDeserializer(String);
+
+macro_rules! deserialize_key_int {
+    ($($method:ident => $visit:ident: $ty:ty),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+                match self.0.parse::<$ty>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_string(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = NbtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_key_int! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        let variant: StringDeserializer<NbtError> = self.0.into_deserializer();
+        visitor.visit_enum(variant)
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// 变体名或者序号 (就是 tag)
+struct TagName(NbtTypeId);
+
+impl<'de> Deserialize<'de> for TagName {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TagNameVisitor;
+
+        impl Visitor<'_> for TagNameVisitor {
+            type Value = TagName;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("NBT 类型名")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<TagName, E> {
+                match v {
+                    0..=12 => Ok(TagName(v as NbtTypeId)),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<TagName, E> {
+                match TAG_VARIANTS.iter().position(|name| *name == v) {
+                    Some(tag) => Ok(TagName(tag as NbtTypeId)),
+                    None => Err(E::unknown_variant(v, &TAG_VARIANTS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(TagNameVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = NbtValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("NBT 值")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<NbtValue, A::Error> {
+                let (TagName(tag), variant) = data.variant()?;
+                Ok(match tag {
+                    1 => NbtValue::Byte(variant.newtype_variant()?),
+                    2 => NbtValue::Short(variant.newtype_variant()?),
+                    3 => NbtValue::Int(variant.newtype_variant()?),
+                    4 => NbtValue::Long(variant.newtype_variant()?),
+                    5 => NbtValue::Float(variant.newtype_variant()?),
+                    6 => NbtValue::Double(variant.newtype_variant()?),
+                    7 => NbtValue::ByteArray(variant.newtype_variant()?),
+                    8 => NbtValue::String(variant.newtype_variant()?),
+                    9 => NbtValue::List(variant.newtype_variant()?),
+                    10 => NbtValue::Compound(variant.newtype_variant()?),
+                    11 => NbtValue::IntArray(variant.newtype_variant()?),
+                    12 => NbtValue::LongArray(variant.newtype_variant()?),
+                    _ => return Err(de::Error::unknown_variant("End", &TAG_VARIANTS[1..])),
+                })
+            }
+        }
+
+        deserializer.deserialize_enum(VALUE_TOKEN, &TAG_VARIANTS[1..], ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtList {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ListVisitor;
+
+        impl<'de> Visitor<'de> for ListVisitor {
+            type Value = NbtList;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("NBT List")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<NbtList, A::Error> {
+                let (TagName(tag), variant) = data.variant()?;
+                Ok(match tag {
+                    0 => {
+                        let elements: Vec<IgnoredAny> = variant.newtype_variant()?;
+                        if !elements.is_empty() {
+                            return Err(de::Error::invalid_length(elements.len(), &"空 List"));
+                        }
+                        NbtList::End
+                    }
+                    1 => NbtList::Byte(variant.newtype_variant()?),
+                    2 => NbtList::Short(variant.newtype_variant()?),
+                    3 => NbtList::Int(variant.newtype_variant()?),
+                    4 => NbtList::Long(variant.newtype_variant()?),
+                    5 => NbtList::Float(variant.newtype_variant()?),
+                    6 => NbtList::Double(variant.newtype_variant()?),
+                    7 => NbtList::ByteArray(variant.newtype_variant()?),
+                    8 => NbtList::String(variant.newtype_variant()?),
+                    9 => NbtList::List(variant.newtype_variant()?),
+                    10 => NbtList::Compound(variant.newtype_variant()?),
+                    11 => NbtList::IntArray(variant.newtype_variant()?),
+                    _ => NbtList::LongArray(variant.newtype_variant()?),
+                })
+            }
+        }
+
+        deserializer.deserialize_enum(LIST_TOKEN, &TAG_VARIANTS, ListVisitor)
+    }
+}
+
+/// 按顺序读, 重复的 key 也原样保留
+impl<'de> Deserialize<'de> for NbtCompound {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CompoundVisitor;
+
+        impl<'de> Visitor<'de> for CompoundVisitor {
+            type Value = NbtCompound;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("NBT Compound")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<NbtCompound, A::Error> {
+                let mut compound = NbtCompound::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((key, value)) = map.next_entry::<String, NbtValue>()? {
+                    compound.push(key, value);
+                }
+                Ok(compound)
+            }
+        }
+
+        deserializer.deserialize_map(CompoundVisitor)
+    }
+}
@@ -0,0 +1,320 @@
+//! 两个 `NbtValue` 之间的结构化差异
+//!
+//! ```rust
+//! use shen_nbt5::{DiffChange, NbtValue};
+//!
+//! let old = NbtValue::from_snbt("{Health: 20f, Pos: [0d, 64d, 0d], Tags: [a]}").unwrap();
+//! let new = NbtValue::from_snbt("{Health: 19.5f, Pos: [0d, 65d, 0d], Air: 300s}").unwrap();
+//! let diff = old.diff(&new);
+//! assert_eq!(diff.len(), 4);
+//! assert_eq!(diff.changes[1].path, "Pos[1]");
+//! assert!(matches!(diff.changes[3].change, DiffChange::Added(_)));
+//! assert_eq!(
+//!     diff.to_string(),
+//!     "~ Health: 20.0f -> 19.5f\n~ Pos[1]: 64.0d -> 65.0d\n- Tags: [a]\n+ Air: 300s\n"
+//! );
+//! ```
+//!
+//! 路径和 [`NbtValue::get_path`] 的格式一样, List 默认按下标对齐, 也可以用 [`DiffOptions`] 按某个 key 对齐
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::convert::path_key;
+use crate::merge::{element_key, list_options};
+use crate::{NbtCompound, NbtList, NbtValue};
+
+/// List 元素的对齐方式
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ListAlign {
+    /// 同一下标的元素对比
+    #[default]
+    ByIndex,
+    /// 这个 key 的值相同的 Compound 元素对比, 比如物品栏的 `Slot`
+    ///
+    /// 没有这个 key 的元素只和完全相同的元素对上;
+    /// 先从后往前列出删掉的元素 (原来的下标), 再按新的顺序列出其他的:
+    /// 顺序变了的元素先是一个 `Moved` (从删完之后当时的下标移过来), 然后是它里面的变化
+    ByKey(String),
+}
+
+/// 对比选项
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffOptions {
+    /// 浮点数的容差, 差的绝对值不超过它就算相同
+    pub epsilon: Option<f64>,
+    /// 默认的 List 对齐方式
+    pub lists: ListAlign,
+    /// 和 [`MergeOptions::list_keys`](crate::MergeOptions::list_keys) 一样按 List 所在的 key 指定
+    pub list_keys: HashMap<String, ListAlign>,
+}
+
+list_options!(DiffOptions, ListAlign, list_align, "对齐方式");
+
+/// 一处变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffChange {
+    /// 新增的值
+    Added(NbtValue),
+    /// 删掉的值
+    Removed(NbtValue),
+    /// 类型相同, 值变了
+    ///
+    /// 元素类型不同的两个 List 也算这种, 整个 List 一起替换
+    Changed { old: NbtValue, new: NbtValue },
+    /// 类型变了
+    TypeChanged { old: NbtValue, new: NbtValue },
+    /// 按 key 对齐的 List 里, 元素从 `from` 移到这里
+    Moved { from: String },
+}
+
+/// 一处变化和它的路径
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    /// 空路径是根节点
+    pub path: String,
+    pub change: DiffChange,
+}
+
+/// 对比结果
+///
+/// 先是原来就有的 key (按原来的顺序), 然后是新增的 key, List 里删掉的元素从后往前排,
+/// 所以可以直接转换成 [`NbtPatch`](crate::NbtPatch); `Display` 输出每行一处变化的补丁格式
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NbtDiff {
+    pub changes: Vec<DiffEntry>,
+}
+
+impl NbtDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.changes.is_empty() }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.changes.len() }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, DiffEntry> { self.changes.iter() }
+
+    /// 带 ANSI 颜色的补丁格式: 新增绿色, 删除红色, 修改黄色
+    pub fn to_colored_string(&self) -> String {
+        let mut out = String::new();
+        self.write_patch(&mut out, true).expect("写 String 不会失败");
+        out
+    }
+
+    fn write_patch(&self, out: &mut impl fmt::Write, color: bool) -> fmt::Result {
+        for entry in &self.changes {
+            let (sign, code) = match entry.change {
+                DiffChange::Added(_) => ('+', "32"),
+                DiffChange::Removed(_) => ('-', "31"),
+                DiffChange::Changed { .. } | DiffChange::TypeChanged { .. } => ('~', "33"),
+                DiffChange::Moved { .. } => ('>', "36"),
+            };
+            if color {
+                write!(out, "\x1b[{}m", code)?;
+            }
+            let path = if entry.path.is_empty() {
+                "<root>"
+            } else {
+                &entry.path
+            };
+            write!(out, "{} {}: ", sign, path)?;
+            match &entry.change {
+                DiffChange::Added(value) | DiffChange::Removed(value) => write!(out, "{}", value)?,
+                DiffChange::Changed { old, new } => write!(out, "{} -> {}", old, new)?,
+                DiffChange::TypeChanged { old, new } => {
+                    write!(out, "{} -> {} (类型 {} -> {})", old, new, old.tag(), new.tag())?
+                }
+                DiffChange::Moved { from } => write!(out, "<- {}", from)?,
+            }
+            if color {
+                out.write_str("\x1b[0m")?;
+            }
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for NbtDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.write_patch(f, false) }
+}
+
+impl IntoIterator for NbtDiff {
+    type Item = DiffEntry;
+    type IntoIter = std::vec::IntoIter<DiffEntry>;
+
+    fn into_iter(self) -> Self::IntoIter { self.changes.into_iter() }
+}
+
+impl<'a> IntoIterator for &'a NbtDiff {
+    type Item = &'a DiffEntry;
+    type IntoIter = std::slice::Iter<'a, DiffEntry>;
+
+    fn into_iter(self) -> Self::IntoIter { self.changes.iter() }
+}
+
+impl NbtValue {
+    /// 从 `self` 变成 `new` 的所有变化
+    ///
+    /// Compound 和 List 逐层对比, 数组和其他值整体对比
+    pub fn diff(&self, new: &NbtValue) -> NbtDiff { self.diff_with(new, &DiffOptions::default()) }
+
+    pub fn diff_with(&self, new: &NbtValue, options: &DiffOptions) -> NbtDiff {
+        let mut differ = Differ {
+            options,
+            changes: Vec::new(),
+        };
+        differ.value(String::new(), None, self, new);
+        NbtDiff {
+            changes: differ.changes,
+        }
+    }
+}
+
+struct Differ<'o> {
+    options: &'o DiffOptions,
+    changes: Vec<DiffEntry>,
+}
+
+impl Differ<'_> {
+    fn push(&mut self, path: String, change: DiffChange) {
+        self.changes.push(DiffEntry { path, change });
+    }
+
+    /// `key` 是这个值在外层 Compound 里的 key, 用来找 List 的对齐方式
+    fn value(&mut self, path: String, key: Option<&str>, old: &NbtValue, new: &NbtValue) {
+        match (old, new) {
+            (NbtValue::Compound(old), NbtValue::Compound(new)) => self.compound(&path, old, new),
+            (NbtValue::List(old), NbtValue::List(new)) => {
+                self.list(&path, self.options.list_align(key), old, new)
+            }
+            _ if old.tag() != new.tag() => self.push(
+                path,
+                DiffChange::TypeChanged {
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+            ),
+            _ if !self.same(old, new) => self.push(
+                path,
+                DiffChange::Changed {
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+            ),
+            _ => {}
+        }
+    }
+
+    /// 不是 Compound 和 List 的值是否相同
+    fn same(&self, old: &NbtValue, new: &NbtValue) -> bool {
+        let float = |a: f64, b: f64| {
+            a == b
+                || (a.is_nan() && b.is_nan())
+                || self.options.epsilon.is_some_and(|e| (a - b).abs() <= e)
+        };
+        match (old, new) {
+            (NbtValue::Float(a), NbtValue::Float(b)) => float(*a as f64, *b as f64),
+            (NbtValue::Double(a), NbtValue::Double(b)) => float(*a, *b),
+            _ => old == new,
+        }
+    }
+
+    fn compound(&mut self, path: &str, old: &NbtCompound, new: &NbtCompound) {
+        for (key, old_value) in old.iter() {
+            let child = child_path(path, key);
+            match new.get(key) {
+                Some(new_value) => self.value(child, Some(key), old_value, new_value),
+                None => self.push(child, DiffChange::Removed(old_value.clone())),
+            }
+        }
+        for (key, new_value) in new.iter() {
+            if !old.contains_key(key) {
+                self.push(child_path(path, key), DiffChange::Added(new_value.clone()));
+            }
+        }
+    }
+
+    fn list(&mut self, path: &str, align: &ListAlign, old: &NbtList, new: &NbtList) {
+        // 元素类型不同的 List 没法一个个替换元素, 只能整个换掉
+        if !old.is_empty() && !new.is_empty() && old.element_tag() != new.element_tag() {
+            self.push(
+                path.to_string(),
+                DiffChange::Changed {
+                    old: NbtValue::List(old.clone()),
+                    new: NbtValue::List(new.clone()),
+                },
+            );
+            return;
+        }
+        let (old, new) = (old.to_values(), new.to_values());
+        let index = |i: usize| format!("{}[{}]", path, i);
+        match align {
+            ListAlign::ByIndex => {
+                for (i, (o, n)) in old.iter().zip(&new).enumerate() {
+                    self.value(index(i), None, o, n);
+                }
+                // 从后往前删, 这样可以直接当补丁用
+                for i in (new.len()..old.len()).rev() {
+                    self.push(index(i), DiffChange::Removed(old[i].clone()));
+                }
+                for (i, n) in new.iter().enumerate().skip(old.len()) {
+                    self.push(index(i), DiffChange::Added(n.clone()));
+                }
+            }
+            ListAlign::ByKey(key) => {
+                // 新的元素在原来的 List 里对应的下标
+                let mut matched = vec![false; old.len()];
+                let mut pairs = Vec::with_capacity(new.len());
+                for n in &new {
+                    let found = (0..old.len()).find(|&i| {
+                        !matched[i]
+                            && match element_key(n, key) {
+                                Some(id) => element_key(&old[i], key) == Some(id),
+                                None => element_key(&old[i], key).is_none() && old[i] == *n,
+                            }
+                    });
+                    if let Some(i) = found {
+                        matched[i] = true;
+                    }
+                    pairs.push(found);
+                }
+                for (i, o) in old.iter().enumerate().rev() {
+                    if !matched[i] {
+                        self.push(index(i), DiffChange::Removed(o.clone()));
+                    }
+                }
+                // 删完之后的 List 里每个位置是原来的哪个元素, 新增的是 None;
+                // 前 j 个已经和新的 List 一样了
+                let mut current =
+                    (0..old.len()).filter(|&i| matched[i]).map(Some).collect::<Vec<_>>();
+                for (j, (n, found)) in new.iter().zip(pairs).enumerate() {
+                    match found {
+                        Some(i) => {
+                            let pos = (j..current.len())
+                                .find(|&p| current[p] == Some(i))
+                                .expect("对上的元素一定还在后面");
+                            if pos != j {
+                                current[j..=pos].rotate_right(1);
+                                self.push(index(j), DiffChange::Moved { from: index(pos) });
+                            }
+                            self.value(index(j), None, &old[i], n);
+                        }
+                        None => {
+                            current.insert(j, None);
+                            self.push(index(j), DiffChange::Added(n.clone()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 路径后面接一个 key
+fn child_path(path: &str, key: &str) -> String {
+    match path {
+        "" => path_key(key),
+        _ => format!("{}.{}", path, path_key(key)),
+    }
+}
@@ -0,0 +1,81 @@
+//! NbtDocument
+//!
+//! 根节点 + 根节点的名字
+//!
+//! 名字只属于根节点, 嵌套的 Compound 没有名字 (key 在父节点里)
+
+use crate::{nbt_version, NbtReader, NbtResult, NbtValue};
+
+/// 一份完整的 NBT 数据
+///
+/// 根节点能不能有名字由格式决定, 见 `NbtWriteTrait::root_name_rule`
+///
+/// | 格式 | Compound 根节点 | List 根节点 |
+/// |------|-----------------|-------------|
+/// | `Java` | 必须 | 不支持 |
+/// | `JavaNetAfter1_20_2` | 禁止 | 不支持 |
+/// | `BedrockDisk` | 可选 | 禁止 |
+/// | `BedrockNetVarInt` | 可选 | 禁止 |
+///
+/// 读取时有名字的格式总是返回 `Some`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtDocument {
+    /// 根节点的名字
+    pub root_name: Option<String>,
+    /// 根节点, Compound 或者 List (仅基岩版)
+    pub root: NbtValue,
+}
+
+impl NbtDocument {
+    /// 没有名字的根节点
+    ///
+    /// 必须有名字的格式写入时会返回 `RootWithoutName`, 空名字请用 `with_name("", root)`
+    pub fn new(root: NbtValue) -> Self {
+        Self {
+            root_name: None,
+            root,
+        }
+    }
+
+    /// 有名字的根节点
+    pub fn with_name(name: impl Into<String>, root: NbtValue) -> Self {
+        Self {
+            root_name: Some(name.into()),
+            root,
+        }
+    }
+
+    /// 解析 Nbt 数据
+    pub fn from_binary<R>(data: &mut [u8]) -> NbtResult<Self>
+    where
+        R: nbt_version::NbtReadTrait,
+    {
+        R::from_reader(NbtReader::new(data))
+    }
+
+    pub fn write_to<W>(&self, buff: &mut Vec<u8>) -> NbtResult<()>
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        W::write_to(self.root_name.as_deref(), &self.root, buff)
+    }
+
+    pub fn to_binary<W>(&self) -> NbtResult<Vec<u8>>
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        W::to_bytes(self.root_name.as_deref(), &self.root)
+    }
+
+    /// 与 `to_binary::<W>()` 的结果长度一致
+    pub fn encoded_len<W>(&self) -> NbtResult<usize>
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        W::encoded_len(self.root_name.as_deref(), &self.root)
+    }
+}
+
+impl From<NbtValue> for NbtDocument {
+    fn from(root: NbtValue) -> Self { Self::new(root) }
+}
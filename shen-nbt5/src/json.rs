@@ -0,0 +1,283 @@
+//! 和 JSON 互相转换
+//!
+//! 两种格式:
+//! - 普通 JSON ([`NbtValue::to_json`]): 数字就是 JSON 数字, 数组和 List 都是 JSON 数组, 会丢掉类型
+//! - 带类型的 JSON ([`NbtValue::to_typed_json`]): 每个值都是 `{"type": "int", "value": 1}`,
+//!   可以原样转换回来
+//!
+//! 带类型的格式里:
+//! - Long 和 LongArray 的数字是字符串, 超过 2^53 也不会丢精度
+//! - Float / Double 的 NaN 和无穷大是字符串 `"NaN"` `"Infinity"` `"-Infinity"`
+//! - List 多一个 `"element"` 表示元素类型, 空 List 也不会丢
+//! - Compound 的 `value` 是 `[{"name": "a", "type": "int", "value": 1}, ...]`, 保留顺序和重复的 key
+
+use serde_json::{Map, Number, Value};
+
+use crate::{type_id, NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue, TYPE_NAMES};
+
+fn error(pointer: &str, msg: impl Into<String>) -> NbtError {
+    NbtError::InvalidJson(pointer.to_string(), msg.into())
+}
+
+/// JSON Pointer 的下一段
+fn child(pointer: &str, key: &str) -> String {
+    format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// 用最短的十进制表示转成 f64, `0.1f32` 会变成 `0.1` 而不是 `0.10000000149011612`
+///
+/// 转回 f32 的时候还是同一个值
+fn widen(x: f32) -> f64 { x.to_string().parse().unwrap_or(x as f64) }
+
+fn float_to_json(x: f64) -> Value {
+    match Number::from_f64(x) {
+        Some(n) => Value::Number(n),
+        None if x.is_nan() => Value::String("NaN".to_string()),
+        None if x > 0.0 => Value::String("Infinity".to_string()),
+        None => Value::String("-Infinity".to_string()),
+    }
+}
+
+fn to_plain(value: &NbtValue) -> Value {
+    match value {
+        NbtValue::Byte(x) => Value::from(*x),
+        NbtValue::Short(x) => Value::from(*x),
+        NbtValue::Int(x) => Value::from(*x),
+        NbtValue::Long(x) => Value::from(*x),
+        NbtValue::Float(x) => Value::from(widen(*x)),
+        NbtValue::Double(x) => Value::from(*x),
+        NbtValue::ByteArray(x) => Value::from(x.as_slice()),
+        NbtValue::String(x) => Value::from(x.as_str()),
+        NbtValue::List(x) => Value::Array(x.to_values().iter().map(to_plain).collect()),
+        NbtValue::Compound(x) => {
+            Value::Object(x.iter().map(|(k, v)| (k.clone(), to_plain(v))).collect())
+        }
+        NbtValue::IntArray(x) => Value::from(x.as_slice()),
+        NbtValue::LongArray(x) => Value::from(x.as_slice()),
+    }
+}
+
+/// 整数能放进 Int 就是 Int, 不然是 Long, 再大就是 Double
+fn plain_number(n: &Number) -> NbtValue {
+    match n.as_i64() {
+        Some(x) => i32::try_from(x).map_or(NbtValue::Long(x), NbtValue::Int),
+        None => NbtValue::Double(n.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+fn from_plain(value: &Value, pointer: &str) -> NbtResult<NbtValue> {
+    Ok(match value {
+        Value::Null => return Err(error(pointer, "NBT 里没有 null")),
+        Value::Bool(b) => NbtValue::Byte(*b as i8),
+        Value::Number(n) => plain_number(n),
+        Value::String(s) => NbtValue::String(s.clone()),
+        Value::Array(items) => NbtValue::List(plain_list(items, pointer)?),
+        Value::Object(map) => {
+            let mut compound = NbtCompound::with_capacity(map.len());
+            for (key, value) in map {
+                compound.push(key.clone(), from_plain(value, &child(pointer, key))?);
+            }
+            NbtValue::Compound(compound)
+        }
+    })
+}
+
+/// 全是数字的数组统一成一种类型, `[1, 2.5]` 是 Double 的 List
+fn plain_list(items: &[Value], pointer: &str) -> NbtResult<NbtList> {
+    if !items.is_empty() && items.iter().all(Value::is_number) {
+        let widest = items.iter().filter_map(Value::as_number).map(|n| plain_number(n).tag()).max();
+        return Ok(match widest {
+            Some(3) => {
+                NbtList::Int(items.iter().filter_map(|v| Some(v.as_i64()? as i32)).collect())
+            }
+            Some(4) => NbtList::Long(items.iter().filter_map(Value::as_i64).collect()),
+            _ => NbtList::Double(items.iter().filter_map(Value::as_f64).collect()),
+        });
+    }
+    let mut list = NbtList::End;
+    for (i, item) in items.iter().enumerate() {
+        let pointer = child(pointer, &i.to_string());
+        let value = from_plain(item, &pointer)?;
+        let tag = value.tag();
+        list.push(value).map_err(|_| {
+            error(
+                &pointer,
+                format!(
+                    "{} 不能放进 {} 的 List",
+                    TYPE_NAMES[tag as usize],
+                    TYPE_NAMES[list.element_tag() as usize]
+                ),
+            )
+        })?;
+    }
+    Ok(list)
+}
+
+fn to_typed(value: &NbtValue) -> Map<String, Value> {
+    let mut node = Map::new();
+    node.insert("type".to_string(), Value::from(TYPE_NAMES[value.tag() as usize]));
+    let payload = match value {
+        NbtValue::Byte(x) => Value::from(*x),
+        NbtValue::Short(x) => Value::from(*x),
+        NbtValue::Int(x) => Value::from(*x),
+        NbtValue::Long(x) => Value::from(x.to_string()),
+        NbtValue::Float(x) => float_to_json(widen(*x)),
+        NbtValue::Double(x) => float_to_json(*x),
+        NbtValue::ByteArray(x) => Value::from(x.as_slice()),
+        NbtValue::String(x) => Value::from(x.as_str()),
+        NbtValue::List(x) => {
+            node.insert("element".to_string(), Value::from(TYPE_NAMES[x.element_tag() as usize]));
+            Value::Array(x.to_values().iter().map(|v| Value::Object(to_typed(v))).collect())
+        }
+        NbtValue::Compound(x) => Value::Array(
+            x.iter()
+                .map(|(k, v)| {
+                    let mut entry = to_typed(v);
+                    entry.insert("name".to_string(), Value::from(k.as_str()));
+                    Value::Object(entry)
+                })
+                .collect(),
+        ),
+        NbtValue::IntArray(x) => Value::from(x.as_slice()),
+        NbtValue::LongArray(x) => {
+            Value::Array(x.iter().map(|v| Value::from(v.to_string())).collect())
+        }
+    };
+    node.insert("value".to_string(), payload);
+    node
+}
+
+fn typed_int<T: TryFrom<i64>>(value: &Value, pointer: &str, tag: NbtTypeId) -> NbtResult<T> {
+    value
+        .as_i64()
+        .and_then(|x| T::try_from(x).ok())
+        .ok_or_else(|| error(pointer, format!("应该是 {} 范围内的整数", TYPE_NAMES[tag as usize])))
+}
+
+/// 字符串和数字都可以
+fn typed_long(value: &Value, pointer: &str) -> NbtResult<i64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        _ => value.as_i64(),
+    }
+    .ok_or_else(|| error(pointer, "应该是 long 范围内的整数或者字符串"))
+}
+
+fn typed_float(value: &Value, pointer: &str) -> NbtResult<f64> {
+    match value {
+        Value::String(s) if s == "NaN" => Some(f64::NAN),
+        Value::String(s) if s == "Infinity" => Some(f64::INFINITY),
+        Value::String(s) if s == "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => value.as_f64(),
+    }
+    .ok_or_else(|| error(pointer, "应该是数字或者 \"NaN\" \"Infinity\" \"-Infinity\""))
+}
+
+fn typed_array<'v, T>(
+    value: &'v Value,
+    pointer: &str,
+    mut element: impl FnMut(&'v Value, &str) -> NbtResult<T>,
+) -> NbtResult<Vec<T>> {
+    let items = value.as_array().ok_or_else(|| error(pointer, "应该是数组"))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| element(item, &child(pointer, &i.to_string())))
+        .collect()
+}
+
+/// 读 `"type"` 或者 `"element"`
+fn typed_tag(node: &Map<String, Value>, key: &str, pointer: &str) -> NbtResult<NbtTypeId> {
+    let name = node.get(key).and_then(Value::as_str);
+    name.and_then(type_id).ok_or_else(|| {
+        error(&child(pointer, key), format!("未知类型: {:?}", name.unwrap_or_default()))
+    })
+}
+
+fn from_typed(value: &Value, pointer: &str) -> NbtResult<NbtValue> {
+    let node = value
+        .as_object()
+        .ok_or_else(|| error(pointer, "应该是 {\"type\": .., \"value\": ..}"))?;
+    let tag = typed_tag(node, "type", pointer)?;
+    let payload = node.get("value").ok_or_else(|| error(pointer, "缺少 value"))?;
+    let node_pointer = pointer;
+    let pointer = &child(pointer, "value");
+    Ok(match tag {
+        1 => NbtValue::Byte(typed_int(payload, pointer, 1)?),
+        2 => NbtValue::Short(typed_int(payload, pointer, 2)?),
+        3 => NbtValue::Int(typed_int(payload, pointer, 3)?),
+        4 => NbtValue::Long(typed_long(payload, pointer)?),
+        5 => NbtValue::Float(typed_float(payload, pointer)? as f32),
+        6 => NbtValue::Double(typed_float(payload, pointer)?),
+        7 => NbtValue::ByteArray(typed_array(payload, pointer, |v, p| typed_int(v, p, 1))?),
+        8 => NbtValue::String(
+            payload.as_str().ok_or_else(|| error(pointer, "应该是字符串"))?.to_string(),
+        ),
+        9 => {
+            let element = typed_tag(node, "element", node_pointer)?;
+            let mut list = NbtList::empty(element)?;
+            for value in typed_array(payload, pointer, from_typed)? {
+                if value.tag() != element {
+                    return Err(error(
+                        pointer,
+                        format!(
+                            "List 的元素类型是 {}, 里面有 {}",
+                            TYPE_NAMES[element as usize],
+                            TYPE_NAMES[value.tag() as usize]
+                        ),
+                    ));
+                }
+                list.push(value)?;
+            }
+            NbtValue::List(list)
+        }
+        10 => {
+            let entries = typed_array(payload, pointer, |entry, p| {
+                let name = entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| error(p, "缺少 name"))?;
+                Ok((name.to_string(), from_typed(entry, p)?))
+            })?;
+            NbtValue::Compound(entries.into())
+        }
+        11 => NbtValue::IntArray(typed_array(payload, pointer, |v, p| typed_int(v, p, 3))?),
+        12 => NbtValue::LongArray(typed_array(payload, pointer, typed_long)?),
+        _ => return Err(error(&child(node_pointer, "type"), "值不能是 end")),
+    })
+}
+
+impl NbtValue {
+    /// 转成普通的 JSON, 会丢掉类型信息
+    ///
+    /// - 所有数字都是 JSON 数字, NaN 和无穷大会变成 `null`
+    /// - 数组和 List 都是 JSON 数组
+    /// - Compound 的 key 顺序由 `serde_json::Map` 决定, 重复的 key 只留最后一个
+    pub fn to_json(&self) -> Value { to_plain(self) }
+
+    /// 从普通的 JSON 转换, 类型靠猜
+    ///
+    /// - `true` / `false` 是 Byte
+    /// - 整数能放进 Int 就是 Int, 不然是 Long, 有小数的是 Double
+    /// - 全是数字的数组统一成一种类型的 List, 其他数组的元素类型必须一样
+    /// - `null` 会报错
+    pub fn from_json(value: &Value) -> NbtResult<NbtValue> { from_plain(value, "") }
+
+    /// 转成带类型的 JSON, 可以用 [`NbtValue::from_typed_json`] 原样转换回来
+    ///
+    /// ```rust
+    /// use shen_nbt5::NbtValue;
+    ///
+    /// let value = NbtValue::from_snbt("{a: 1b, b: [L; 9007199254740993L]}").unwrap();
+    /// let json = value.to_typed_json();
+    /// assert_eq!(
+    ///     json.to_string(),
+    ///     r#"{"type":"compound","value":[{"name":"a","type":"byte","value":1},{"name":"b","type":"long_array","value":["9007199254740993"]}]}"#
+    /// );
+    /// assert_eq!(NbtValue::from_typed_json(&json), Ok(value));
+    /// ```
+    pub fn to_typed_json(&self) -> Value { Value::Object(to_typed(self)) }
+
+    /// 从带类型的 JSON 转换
+    pub fn from_typed_json(value: &Value) -> NbtResult<NbtValue> { from_typed(value, "") }
+}
@@ -28,20 +28,59 @@
 //! }
 //! ```
 
+pub mod convert;
 pub mod reader;
 pub mod writer;
 
+mod canonical;
+mod compound;
+mod diff;
+mod document;
+#[cfg(feature = "json")]
+mod json;
+mod list;
+mod macros;
+mod merge;
+mod nbt_path;
+mod ord;
+mod patch;
+mod path;
+mod schema;
+mod snbt;
+
+pub use compound::NbtCompound;
+pub use convert::{FromNbt, ToNbt};
+pub use diff::{DiffChange, DiffEntry, DiffOptions, ListAlign, NbtDiff};
+pub use document::NbtDocument;
+pub use list::NbtList;
+pub use merge::{ListMerge, MergeOptions};
+pub use nbt_path::NbtPath;
+pub use ord::{NbtKey, NbtUnorderedKey};
+pub use patch::{NbtPatch, PatchOp};
+pub use path::{NbtMut, NbtRef};
+pub use schema::{NbtSchema, SchemaField, SchemaNumber, SchemaViolation, ViolationKind};
+#[cfg(feature = "derive")]
+pub use shen_nbt5_derive::{FromNbt, ToNbt};
+pub use snbt::SnbtStyle;
+
+#[cfg(feature = "serde")]
+pub mod de;
 #[cfg(feature = "serde")]
 pub mod ser;
 #[cfg(feature = "serde")]
-use serde;
+pub mod serde;
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+pub use de::{from_bytes, from_value};
+#[cfg(feature = "serde")]
+pub use ser::{to_bytes, to_bytes_with_name, to_value};
 
 use reader::NbtReader;
 
 #[cfg(test)]
 mod tests;
+// 让 derive 生成的 `::shen_nbt5::...` 在本 crate 的测试里也能用
+#[cfg(all(test, feature = "derive"))]
+extern crate self as shen_nbt5;
 
 /// 不同版本的 Nbt 数据细节不同
 /// 老要命了
@@ -55,39 +94,142 @@ mod tests;
 /// - `BedrockNetVarInt`
 ///   基岩版 网络 NBT 格式
 pub mod nbt_version {
-    use super::{NbtReader, NbtResult, NbtValue};
+    use super::{
+        NbtCompound, NbtDocument, NbtError, NbtList, NbtReader, NbtResult, NbtTypeId, NbtValue,
+    };
+
+    /// 根节点名字的规则
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RootName {
+        /// 必须有名字, 没有时返回 `RootWithoutName`
+        Required,
+        /// 可以没有, 写入时当作空字符串
+        Optional,
+        /// 不能有名字, 有名字时返回 `RootNameForbidden`
+        Forbidden,
+    }
 
     pub trait NbtWriteTrait {
+        fn write_i16(writer: &mut Vec<u8>, data: i16);
+        /// List/Array 的长度前缀也用这个
+        fn write_i32(writer: &mut Vec<u8>, data: i32);
+        fn write_i64(writer: &mut Vec<u8>, data: i64);
+        fn write_f32(writer: &mut Vec<u8>, data: f32);
+        fn write_f64(writer: &mut Vec<u8>, data: f64);
         fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]);
         fn write_i32_array(writer: &mut Vec<u8>, data: &[i32]);
         fn write_i64_array(writer: &mut Vec<u8>, data: &[i64]);
         fn write_nbt_string(writer: &mut Vec<u8>, data: &str);
-        fn write_list(writer: &mut Vec<u8>, data: &[NbtValue]) -> NbtResult<()>;
-        fn write_compound(
+        fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()>;
+        /// 只写入 Compound 的内容 (包括结尾的 End tag)
+        ///
+        /// 名字由调用者负责写入
+        fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()>;
+
+        /// 这个格式的根节点名字规则
+        ///
+        /// 根节点类型不对时返回 `WrongRootType`
+        fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName>;
+
+        /// 按照 `root_name_rule` 检查根节点的名字
+        ///
+        /// 返回实际要写入的名字, `None` 表示不写名字
+        fn check_root_name(tag: NbtTypeId, name: Option<&str>) -> NbtResult<Option<&str>> {
+            match (Self::root_name_rule(tag)?, name) {
+                (RootName::Required, None) => Err(NbtError::RootWithoutName),
+                (RootName::Forbidden, Some(name)) => {
+                    Err(NbtError::RootNameForbidden(name.to_string()))
+                }
+                (RootName::Forbidden, None) => Ok(None),
+                (_, name) => Ok(Some(name.unwrap_or(""))),
+            }
+        }
+
+        /// `NbtValue` 上不带名字的方法实际写入的名字
+        ///
+        /// 必须有名字的格式写空字符串, 其他格式和不给名字一样
+        fn unnamed_root_name(tag: NbtTypeId) -> NbtResult<Option<&'static str>> {
+            match Self::root_name_rule(tag)? {
+                RootName::Required => Ok(Some("")),
+                _ => Ok(None),
+            }
+        }
+
+        /// 写入根节点的 tag 和名字
+        fn write_root_header(
             writer: &mut Vec<u8>,
-            name: Option<&String>,
-            data: &[(String, NbtValue)],
-        ) -> NbtResult<()>;
+            tag: NbtTypeId,
+            name: Option<&str>,
+        ) -> NbtResult<()> {
+            let name = Self::check_root_name(tag, name)?;
+            writer.push(tag);
+            if let Some(name) = name {
+                Self::write_nbt_string(writer, name);
+            }
+            Ok(())
+        }
+
+        /// 写入整个根节点
+        fn write_to(name: Option<&str>, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
+            Self::write_root_header(buff, value.tag(), name)?;
+            match value {
+                NbtValue::Compound(data) => Self::write_compound(buff, data),
+                NbtValue::List(data) => Self::write_list(buff, data),
+                x => Err(NbtError::WrongRootType(x.tag())),
+            }
+        }
 
-        fn write_to(value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()>;
-        fn write_to_with_name(name: &str, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()>;
+        /// 一个 Int 编码后的长度
+        ///
+        /// List/Array 的长度前缀也用这个
+        fn i32_len(value: i32) -> usize;
+        /// 一个 Long 编码后的长度
+        fn i64_len(value: i64) -> usize;
+        /// 一个字符串编码后的长度 (包括长度前缀)
+        fn nbt_string_len(data: &str) -> usize;
+        /// 整个根节点编码后的长度
+        ///
+        /// 与 `write_to` 写出的长度完全一致
+        fn encoded_len(name: Option<&str>, value: &NbtValue) -> NbtResult<usize>
+        where
+            Self: Sized,
+        {
+            // tag + 名字 + 数据
+            let name = Self::check_root_name(value.tag(), name)?;
+            Ok(1 + name.map(Self::nbt_string_len).unwrap_or(0) + value.payload_len::<Self>())
+        }
 
-        fn to_bytes(value: &NbtValue) -> NbtResult<Vec<u8>> {
-            let mut buff = Vec::new();
-            Self::write_to(value, &mut buff)?;
+        fn to_bytes(name: Option<&str>, value: &NbtValue) -> NbtResult<Vec<u8>>
+        where
+            Self: Sized,
+        {
+            // 先算好长度, 只分配一次
+            let mut buff = Vec::with_capacity(Self::encoded_len(name, value)?);
+            Self::write_to(name, value, &mut buff)?;
             Ok(buff)
         }
     }
 
     pub trait NbtReadTrait {
+        fn read_i16(reader: &mut NbtReader) -> NbtResult<i16>;
+        /// List/Array 的长度前缀也用这个
+        fn read_i32(reader: &mut NbtReader) -> NbtResult<i32>;
+        fn read_i64(reader: &mut NbtReader) -> NbtResult<i64>;
+        fn read_f32(reader: &mut NbtReader) -> NbtResult<f32>;
+        fn read_f64(reader: &mut NbtReader) -> NbtResult<f64>;
         fn read_i8_array(reader: &mut NbtReader) -> NbtResult<Vec<i8>>;
         fn read_i32_array(reader: &mut NbtReader) -> NbtResult<Vec<i32>>;
         fn read_i64_array(reader: &mut NbtReader) -> NbtResult<Vec<i64>>;
         fn read_nbt_string(reader: &mut NbtReader) -> NbtResult<String>;
-        fn read_list(reader: &mut NbtReader) -> NbtResult<Vec<NbtValue>>;
-        fn read_compound(reader: &mut NbtReader) -> NbtResult<Vec<(String, NbtValue)>>;
+        fn read_list(reader: &mut NbtReader) -> NbtResult<NbtList>;
+        fn read_compound(reader: &mut NbtReader) -> NbtResult<NbtCompound>;
 
-        fn from_reader(reader: NbtReader) -> NbtResult<NbtValue>;
+        fn from_reader(reader: NbtReader) -> NbtResult<NbtDocument>;
+
+        /// 读取根节点的 tag 和名字, 之后就是根节点的内容
+        ///
+        /// 根节点类型不对时返回 `WrongRootType`
+        fn read_root_header(reader: &mut NbtReader) -> NbtResult<(NbtTypeId, Option<String>)>;
     }
     /// Java 版 绝大部分的 NBT 格式
     ///
@@ -123,6 +265,29 @@ pub mod nbt_version {
 
 pub type NbtTypeId = u8;
 
+/// 小写的类型名, 下标就是类型 id
+///
+/// 带类型的 JSON 和 [`NbtSchema`] 的 `type` 用
+pub(crate) const TYPE_NAMES: [&str; 13] = [
+    "end",
+    "byte",
+    "short",
+    "int",
+    "long",
+    "float",
+    "double",
+    "byte_array",
+    "string",
+    "list",
+    "compound",
+    "int_array",
+    "long_array",
+];
+
+pub(crate) fn type_id(name: &str) -> Option<NbtTypeId> {
+    TYPE_NAMES.iter().position(|n| *n == name).map(|i| i as NbtTypeId)
+}
+
 /// 把 u8 转换成对应的 Nbt 类型名称
 pub trait NbtTypeConversion {
     /// 把 u8 转换成对应的 Nbt 类型名称
@@ -163,6 +328,8 @@ pub enum NbtError {
     WrongRootType(NbtTypeId),
     /// 根节点无名称
     RootWithoutName,
+    /// 这个格式的根节点不能有名称
+    RootNameForbidden(String),
     /// 未知类型
     UnknownType(NbtTypeId),
     /// 名称读取错误
@@ -183,6 +350,42 @@ pub enum NbtError {
     ListTypeNotSame(Vec<NbtTypeId>),
     /// 错误类型
     IncorrectType(NbtTypeId, NbtTypeId),
+    /// 路径格式错误
+    InvalidPath(String),
+    /// 路径不存在
+    ///
+    /// 到找不到的那一段为止的路径
+    PathNotFound(String),
+    /// 路径中间的值类型不对
+    ///
+    /// 到出错的那一段为止的路径, 期望的类型, 实际的类型
+    PathTypeMismatch(String, NbtTypeId, NbtTypeId),
+    /// SNBT 格式错误
+    ///
+    /// 行, 列 (都从 1 开始), 错误信息
+    InvalidSnbt(usize, usize, String),
+    /// JSON 格式错误
+    ///
+    /// 出错的位置 (JSON Pointer), 错误信息
+    InvalidJson(String, String),
+    /// 补丁的 test 操作没有通过
+    ///
+    /// test 的路径
+    PatchTestFailed(String),
+    /// 补丁的第几个操作失败了 (从 0 开始), 和失败的原因
+    PatchFailed(usize, Box<NbtError>),
+    /// SNBT 没法表示的 NaN 或者无穷大
+    ///
+    /// 这个值的路径
+    NonFiniteFloat(String),
+    /// insert / append / prepend 的目标不是 List 或者数组
+    ///
+    /// 目标的路径
+    ExpectedList(String),
+    /// insert 的下标超出范围
+    ///
+    /// 目标的路径, 下标 (负数已经换算过)
+    InvalidIndex(String, i64),
 }
 
 /// 返回类型
@@ -208,6 +411,9 @@ impl std::fmt::Display for NbtError {
             NbtError::RootWithoutName => {
                 write!(f, "根节点无名称, 是否应该使用 JavaNetAfter1_20_2 解析?")
             }
+            NbtError::RootNameForbidden(name) => {
+                write!(f, "这个格式的根节点不能有名称: {:?}", name)
+            }
             NbtError::UnknownType(n) => {
                 if *n == 0 {
                     write!(f, "未知类型: NBTEnd(0), 请检查数据是否正确")
@@ -232,18 +438,32 @@ impl std::fmt::Display for NbtError {
             NbtError::IncorrectType(expect, got) => {
                 write!(f, "错误类型: 期望: {}, 实际: {}", expect, got)
             }
+            NbtError::InvalidPath(s) => write!(f, "路径格式错误: {}", s),
+            NbtError::PathNotFound(path) => write!(f, "路径不存在: {}", path),
+            NbtError::PathTypeMismatch(path, expect, got) => {
+                write!(f, "路径类型错误: {} 需要类型 {}, 实际: {}", path, expect, got)
+            }
+            NbtError::InvalidSnbt(line, column, msg) => {
+                write!(f, "SNBT 格式错误: 第 {} 行第 {} 列: {}", line, column, msg)
+            }
+            NbtError::InvalidJson(pointer, msg) => {
+                write!(f, "JSON 格式错误: {:?}: {}", pointer, msg)
+            }
+            NbtError::PatchTestFailed(path) => write!(f, "补丁测试失败: {} 的值不一致", path),
+            NbtError::PatchFailed(index, err) => write!(f, "补丁第 {} 个操作失败: {}", index, err),
+            NbtError::NonFiniteFloat(path) => {
+                write!(f, "SNBT 不能表示 NaN 和无穷大: {:?}", path)
+            }
+            NbtError::ExpectedList(path) => write!(f, "{} 不是 List 或者数组", path),
+            NbtError::InvalidIndex(path, index) => write!(f, "{} 的下标超出范围: {}", path, index),
         }
     }
 }
 
 /// 核心 Value
 ///
-/// 暂时不支持 `from_value` 和 `to_value`
-/// https://github.com/shenjackyuanjie/nbt-rust/issues/1
+/// 开启 `serde` feature 之后可以用 `to_value` / `from_value` 和任意类型互转
 #[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
-#[cfg_attr(feature = "serde", derive(Deserialize))]
-#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum NbtValue {
     // end: 0
     /// 1: Byte
@@ -267,9 +487,13 @@ pub enum NbtValue {
     String(String),
     /// 9
     /// 长度: i32
-    List(Vec<NbtValue>),
+    ///
+    /// 同类型的元素, 空 List 也会保留元素类型
+    List(NbtList),
     /// 10
-    Compound(Option<String>, Vec<(String, NbtValue)>),
+    ///
+    /// 根节点的名字在 `NbtDocument` 里
+    Compound(NbtCompound),
     /// 11
     /// 长度: i32
     IntArray(Vec<i32>),
@@ -278,14 +502,72 @@ pub enum NbtValue {
     LongArray(Vec<i64>),
 }
 
+/// 输出 SNBT, `{:#}` 会带缩进
+impl std::fmt::Display for NbtValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let style = if f.alternate() {
+            SnbtStyle::pretty()
+        } else {
+            SnbtStyle::compact()
+        };
+        snbt::SnbtPrinter::new(f, &style).value(self)
+    }
+}
+
+impl std::str::FromStr for NbtValue {
+    type Err = NbtError;
+
+    fn from_str(s: &str) -> NbtResult<Self> { snbt::parse(s) }
+}
+
 impl NbtValue {
     /// 解析 Nbt 数据
+    ///
+    /// 只返回根节点, 根节点的名字请用 `NbtDocument::from_binary`
     pub fn from_binary<R>(data: &mut [u8]) -> NbtResult<NbtValue>
     where
         R: nbt_version::NbtReadTrait,
     {
-        let reader = NbtReader::new(data);
-        R::from_reader(reader)
+        NbtDocument::from_binary::<R>(data).map(|doc| doc.root)
+    }
+
+    /// 解析 SNBT (`{foo:1b,bar:[I;1,2,3]}` 这种)
+    ///
+    /// ```rust
+    /// use shen_nbt5::NbtValue;
+    ///
+    /// let value = NbtValue::from_snbt("{id: \"minecraft:stone\", Count: 0x40ub}").unwrap();
+    /// assert_eq!(value.get_path("Count").unwrap().to_value(), NbtValue::Byte(64));
+    /// ```
+    pub fn from_snbt(src: &str) -> NbtResult<NbtValue> { snbt::parse(src) }
+
+    /// 输出成紧凑的 SNBT, 和 `to_string()` 一样
+    ///
+    /// NaN 和无穷大没法用 SNBT 表示, 会输出成 `NaNf` / `inff` / `-infd` 这样的文字,
+    /// 再解析的时候会变成 String, 在 Float / Double 的 List 里会解析失败;
+    /// 需要保证能读回来的话用 [`NbtValue::try_to_snbt`]
+    pub fn to_snbt(&self) -> String { self.to_snbt_with(&SnbtStyle::compact()) }
+
+    /// 按指定的格式输出 SNBT, NaN 和无穷大的处理和 [`NbtValue::to_snbt`] 一样
+    pub fn to_snbt_with(&self, style: &SnbtStyle) -> String {
+        let mut out = String::new();
+        snbt::SnbtPrinter::new(&mut out, style)
+            .value(self)
+            .expect("写入 String 不会失败");
+        out
+    }
+
+    /// 输出成紧凑的 SNBT, 有 NaN 或者无穷大的时候返回 `NonFiniteFloat`
+    ///
+    /// 成功的话 `from_snbt` 一定能读回同样的值
+    pub fn try_to_snbt(&self) -> NbtResult<String> { self.try_to_snbt_with(&SnbtStyle::compact()) }
+
+    /// 按指定的格式输出 SNBT, 有 NaN 或者无穷大的时候返回 `NonFiniteFloat`
+    pub fn try_to_snbt_with(&self, style: &SnbtStyle) -> NbtResult<String> {
+        match snbt::non_finite_path(self, String::new()) {
+            Some(path) => Err(NbtError::NonFiniteFloat(path)),
+            None => Ok(self.to_snbt_with(style)),
+        }
     }
 
     pub fn tag(&self) -> NbtTypeId {
@@ -298,32 +580,83 @@ impl NbtValue {
             NbtValue::Double(_) => 6,
             NbtValue::ByteArray(_) => 7,
             NbtValue::String(_) => 8,
-            NbtValue::List(_) => 9,
-            NbtValue::Compound(_, _) => 10,
+            NbtValue::List(..) => 9,
+            NbtValue::Compound(_) => 10,
             NbtValue::IntArray(_) => 11,
             NbtValue::LongArray(_) => 12,
         }
     }
 
+    /// 作为没有名字的根节点写入, 必须有名字的格式 (`Java`) 写空字符串
+    ///
+    /// 需要名字的话请用 `write_to_with_name` 或者 `NbtDocument`
     pub fn write_to<W>(&self, buff: &mut Vec<u8>) -> NbtResult<()>
     where
         W: nbt_version::NbtWriteTrait,
     {
-        W::write_to(self, buff)
+        W::write_to(W::unnamed_root_name(self.tag())?, self, buff)
     }
 
+    /// 作为有名字的根节点写入
+    ///
+    /// 格式不允许根节点有名字时返回 `RootNameForbidden`
     pub fn write_to_with_name<W>(&self, name: &str, buff: &mut Vec<u8>) -> NbtResult<()>
     where
         W: nbt_version::NbtWriteTrait,
     {
-        W::write_to_with_name(name, self, buff)
+        W::write_to(Some(name), self, buff)
     }
 
+    /// 作为没有名字的根节点写入, 和 `write_to` 一样
     pub fn to_binary<W>(&self) -> NbtResult<Vec<u8>>
     where
         W: nbt_version::NbtWriteTrait,
     {
-        W::to_bytes(self)
+        W::to_bytes(W::unnamed_root_name(self.tag())?, self)
+    }
+
+    /// 作为没有名字的根节点写入时的总长度
+    ///
+    /// 与 `to_binary::<W>()` 的结果长度一致
+    pub fn encoded_len<W>(&self) -> NbtResult<usize>
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        W::encoded_len(W::unnamed_root_name(self.tag())?, self)
+    }
+
+    /// 不包括 tag 和名称的数据部分编码后的长度
+    pub fn payload_len<W>(&self) -> usize
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        match self {
+            NbtValue::Byte(_) => 1,
+            NbtValue::Short(_) => 2,
+            NbtValue::Int(x) => W::i32_len(*x),
+            NbtValue::Long(x) => W::i64_len(*x),
+            NbtValue::Float(_) => 4,
+            NbtValue::Double(_) => 8,
+            NbtValue::ByteArray(x) => W::i32_len(x.len() as i32) + x.len(),
+            NbtValue::IntArray(x) => W::i32_len(x.len() as i32) + x.len() * 4,
+            NbtValue::LongArray(x) => W::i32_len(x.len() as i32) + x.len() * 8,
+            NbtValue::String(x) => W::nbt_string_len(x),
+            // tag + 长度 + 数据
+            NbtValue::List(x) => x.payload_len::<W>(),
+            NbtValue::Compound(x) => NbtValue::compound_payload_len::<W>(x),
+        }
+    }
+
+    /// Compound 数据部分编码后的长度
+    pub(crate) fn compound_payload_len<W>(data: &NbtCompound) -> usize
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        // 每一项: tag + key + 数据, 最后还有一个 End tag
+        data.iter()
+            .map(|(k, v)| 1 + W::nbt_string_len(k) + v.payload_len::<W>())
+            .sum::<usize>()
+            + 1
     }
 
     #[inline]
@@ -396,17 +729,19 @@ impl NbtValue {
             _ => Err(NbtError::IncorrectType(8_u8, self.tag())),
         }
     }
+    /// 复制一份, 只读的话用 `as_list_ref`
     #[inline]
     pub fn as_list(&self) -> NbtResult<Vec<NbtValue>> {
         match self {
-            NbtValue::List(v) => Ok(v.clone()),
+            NbtValue::List(v) => Ok(v.to_values()),
             _ => Err(NbtError::IncorrectType(9_u8, self.tag())),
         }
     }
+    /// 复制一份, 只读的话用 `as_compound_ref`
     #[inline]
-    pub fn as_compound(&self) -> NbtResult<(Option<&String>, Vec<(String, NbtValue)>)> {
+    pub fn as_compound(&self) -> NbtResult<NbtCompound> {
         match self {
-            NbtValue::Compound(name, v) => Ok((name.as_ref(), v.clone())),
+            NbtValue::Compound(v) => Ok(v.clone()),
             _ => Err(NbtError::IncorrectType(10_u8, self.tag())),
         }
     }
@@ -432,7 +767,78 @@ impl NbtValue {
     #[inline]
     pub fn is_string(&self) -> bool { matches!(self, NbtValue::String(_)) }
     #[inline]
-    pub fn is_list(&self) -> bool { matches!(self, NbtValue::List(_)) }
+    pub fn is_list(&self) -> bool { matches!(self, NbtValue::List(..)) }
     #[inline]
-    pub fn is_compound(&self) -> bool { matches!(self, NbtValue::Compound(_, _)) }
+    pub fn is_compound(&self) -> bool { matches!(self, NbtValue::Compound(_)) }
 }
+
+/// 不复制数据的访问方法
+///
+/// `as_xxx_ref` 借用, `as_xxx_mut` 可变借用, `into_xxx` 直接拿走
+macro_rules! value_type {
+    (
+        $variant:ident, $ref_ty:ty, $ty:ty, $tag:literal,
+        $as_ref_name:ident, $as_mut_name:ident, $into_name:ident
+    ) => {
+        impl NbtValue {
+            #[inline]
+            pub fn $as_ref_name(&self) -> NbtResult<&$ref_ty> {
+                match self {
+                    NbtValue::$variant(v) => Ok(v),
+                    _ => Err(NbtError::IncorrectType($tag, self.tag())),
+                }
+            }
+            #[inline]
+            pub fn $as_mut_name(&mut self) -> NbtResult<&mut $ty> {
+                match self {
+                    NbtValue::$variant(v) => Ok(v),
+                    _ => Err(NbtError::IncorrectType($tag, self.tag())),
+                }
+            }
+            #[inline]
+            pub fn $into_name(self) -> NbtResult<$ty> {
+                match self {
+                    NbtValue::$variant(v) => Ok(v),
+                    _ => Err(NbtError::IncorrectType($tag, self.tag())),
+                }
+            }
+        }
+    };
+}
+
+value_type!(Byte, i8, i8, 1, as_i8_ref, as_i8_mut, into_i8);
+value_type!(Short, i16, i16, 2, as_i16_ref, as_i16_mut, into_i16);
+value_type!(Int, i32, i32, 3, as_i32_ref, as_i32_mut, into_i32);
+value_type!(Long, i64, i64, 4, as_i64_ref, as_i64_mut, into_i64);
+value_type!(Float, f32, f32, 5, as_f32_ref, as_f32_mut, into_f32);
+value_type!(Double, f64, f64, 6, as_f64_ref, as_f64_mut, into_f64);
+value_type!(ByteArray, [i8], Vec<i8>, 7, as_i8_array_ref, as_i8_array_mut, into_i8_array);
+value_type!(String, str, String, 8, as_string_ref, as_string_mut, into_string);
+value_type!(List, NbtList, NbtList, 9, as_list_ref, as_list_mut, into_list);
+value_type!(
+    Compound,
+    NbtCompound,
+    NbtCompound,
+    10,
+    as_compound_ref,
+    as_compound_mut,
+    into_compound
+);
+value_type!(
+    IntArray,
+    [i32],
+    Vec<i32>,
+    11,
+    as_i32_array_ref,
+    as_i32_array_mut,
+    into_i32_array
+);
+value_type!(
+    LongArray,
+    [i64],
+    Vec<i64>,
+    12,
+    as_i64_array_ref,
+    as_i64_array_mut,
+    into_i64_array
+);
@@ -0,0 +1,346 @@
+//! 同类型的 NbtList
+//!
+//! 每种元素类型一个 `Vec`, 不用把每个元素都包成 `NbtValue`
+//!
+//! 元素类型一致由类型本身保证, 写入的时候不需要再检查
+
+use crate::{nbt_version, NbtCompound, NbtError, NbtResult, NbtTypeId, NbtValue};
+
+/// 9: List
+///
+/// 空 List 也会保留元素类型, 比如 `NbtList::Compound(vec![])`
+///
+/// 元素类型为 End(0) 的 List 只能是空的, 就是 `NbtList::End`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum NbtList {
+    /// 0
+    #[default]
+    End,
+    /// 1
+    Byte(Vec<i8>),
+    /// 2
+    Short(Vec<i16>),
+    /// 3
+    Int(Vec<i32>),
+    /// 4
+    Long(Vec<i64>),
+    /// 5
+    Float(Vec<f32>),
+    /// 6
+    Double(Vec<f64>),
+    /// 7
+    ByteArray(Vec<Vec<i8>>),
+    /// 8
+    String(Vec<String>),
+    /// 9
+    List(Vec<NbtList>),
+    /// 10
+    Compound(Vec<NbtCompound>),
+    /// 11
+    IntArray(Vec<Vec<i32>>),
+    /// 12
+    LongArray(Vec<Vec<i64>>),
+}
+
+/// 对每一种非 End 的 List 做同样的事情
+macro_rules! each_list {
+    ($list:expr, $x:ident => $body:expr, End => $end:expr) => {
+        match $list {
+            NbtList::End => $end,
+            NbtList::Byte($x) => $body,
+            NbtList::Short($x) => $body,
+            NbtList::Int($x) => $body,
+            NbtList::Long($x) => $body,
+            NbtList::Float($x) => $body,
+            NbtList::Double($x) => $body,
+            NbtList::ByteArray($x) => $body,
+            NbtList::String($x) => $body,
+            NbtList::List($x) => $body,
+            NbtList::Compound($x) => $body,
+            NbtList::IntArray($x) => $body,
+            NbtList::LongArray($x) => $body,
+        }
+    };
+}
+
+/// 生成 `From<Vec<T>>` 和 `as_xxx`
+macro_rules! list_type {
+    ($variant:ident, $ty:ty, $tag:literal, $as_name:ident, $as_mut_name:ident) => {
+        impl From<Vec<$ty>> for NbtList {
+            fn from(value: Vec<$ty>) -> Self { NbtList::$variant(value) }
+        }
+
+        impl NbtList {
+            #[inline]
+            pub fn $as_name(&self) -> NbtResult<&[$ty]> {
+                match self {
+                    NbtList::$variant(v) => Ok(v),
+                    _ => Err(NbtError::IncorrectType($tag, self.element_tag())),
+                }
+            }
+            #[inline]
+            pub fn $as_mut_name(&mut self) -> NbtResult<&mut Vec<$ty>> {
+                match self {
+                    NbtList::$variant(v) => Ok(v),
+                    _ => Err(NbtError::IncorrectType($tag, self.element_tag())),
+                }
+            }
+        }
+    };
+}
+
+list_type!(Byte, i8, 1, as_i8s, as_i8s_mut);
+list_type!(Short, i16, 2, as_i16s, as_i16s_mut);
+list_type!(Int, i32, 3, as_i32s, as_i32s_mut);
+list_type!(Long, i64, 4, as_i64s, as_i64s_mut);
+list_type!(Float, f32, 5, as_f32s, as_f32s_mut);
+list_type!(Double, f64, 6, as_f64s, as_f64s_mut);
+list_type!(ByteArray, Vec<i8>, 7, as_i8_arrays, as_i8_arrays_mut);
+list_type!(String, String, 8, as_strings, as_strings_mut);
+list_type!(List, NbtList, 9, as_lists, as_lists_mut);
+list_type!(Compound, NbtCompound, 10, as_compounds, as_compounds_mut);
+list_type!(IntArray, Vec<i32>, 11, as_i32_arrays, as_i32_arrays_mut);
+list_type!(LongArray, Vec<i64>, 12, as_i64_arrays, as_i64_arrays_mut);
+
+impl NbtList {
+    /// 指定元素类型的空 List
+    pub fn empty(tag: NbtTypeId) -> NbtResult<Self> {
+        Ok(match tag {
+            0 => NbtList::End,
+            1 => NbtList::Byte(Vec::new()),
+            2 => NbtList::Short(Vec::new()),
+            3 => NbtList::Int(Vec::new()),
+            4 => NbtList::Long(Vec::new()),
+            5 => NbtList::Float(Vec::new()),
+            6 => NbtList::Double(Vec::new()),
+            7 => NbtList::ByteArray(Vec::new()),
+            8 => NbtList::String(Vec::new()),
+            9 => NbtList::List(Vec::new()),
+            10 => NbtList::Compound(Vec::new()),
+            11 => NbtList::IntArray(Vec::new()),
+            12 => NbtList::LongArray(Vec::new()),
+            x => return Err(NbtError::UnknownType(x)),
+        })
+    }
+
+    /// 元素类型
+    pub fn element_tag(&self) -> NbtTypeId {
+        match self {
+            NbtList::End => 0,
+            NbtList::Byte(_) => 1,
+            NbtList::Short(_) => 2,
+            NbtList::Int(_) => 3,
+            NbtList::Long(_) => 4,
+            NbtList::Float(_) => 5,
+            NbtList::Double(_) => 6,
+            NbtList::ByteArray(_) => 7,
+            NbtList::String(_) => 8,
+            NbtList::List(_) => 9,
+            NbtList::Compound(_) => 10,
+            NbtList::IntArray(_) => 11,
+            NbtList::LongArray(_) => 12,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { each_list!(self, x => x.len(), End => 0) }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// 取出第 `index` 个元素
+    ///
+    /// 会复制一份, 只想看一眼的话请用 `as_xxx`
+    pub fn get(&self, index: usize) -> Option<NbtValue> {
+        Some(match self {
+            NbtList::End => return None,
+            NbtList::Byte(x) => NbtValue::Byte(*x.get(index)?),
+            NbtList::Short(x) => NbtValue::Short(*x.get(index)?),
+            NbtList::Int(x) => NbtValue::Int(*x.get(index)?),
+            NbtList::Long(x) => NbtValue::Long(*x.get(index)?),
+            NbtList::Float(x) => NbtValue::Float(*x.get(index)?),
+            NbtList::Double(x) => NbtValue::Double(*x.get(index)?),
+            NbtList::ByteArray(x) => NbtValue::ByteArray(x.get(index)?.clone()),
+            NbtList::String(x) => NbtValue::String(x.get(index)?.clone()),
+            NbtList::List(x) => NbtValue::List(x.get(index)?.clone()),
+            NbtList::Compound(x) => NbtValue::Compound(x.get(index)?.clone()),
+            NbtList::IntArray(x) => NbtValue::IntArray(x.get(index)?.clone()),
+            NbtList::LongArray(x) => NbtValue::LongArray(x.get(index)?.clone()),
+        })
+    }
+
+    /// 在末尾加入一个元素
+    ///
+    /// `End` 会变成第一个元素的类型, 类型不一致时返回 `IncorrectType`
+    pub fn push(&mut self, value: NbtValue) -> NbtResult<()> { self.insert(self.len(), value) }
+
+    /// 在 `index` 处插入一个元素, 后面的元素往后移
+    ///
+    /// 类型规则和 `push` 一样, 下标大于长度时 panic (和 `Vec` 一样)
+    pub fn insert(&mut self, index: usize, value: NbtValue) -> NbtResult<()> {
+        if let NbtList::End = self {
+            *self = NbtList::empty(value.tag())?;
+        }
+        match (self, value) {
+            (NbtList::Byte(x), NbtValue::Byte(v)) => x.insert(index, v),
+            (NbtList::Short(x), NbtValue::Short(v)) => x.insert(index, v),
+            (NbtList::Int(x), NbtValue::Int(v)) => x.insert(index, v),
+            (NbtList::Long(x), NbtValue::Long(v)) => x.insert(index, v),
+            (NbtList::Float(x), NbtValue::Float(v)) => x.insert(index, v),
+            (NbtList::Double(x), NbtValue::Double(v)) => x.insert(index, v),
+            (NbtList::ByteArray(x), NbtValue::ByteArray(v)) => x.insert(index, v),
+            (NbtList::String(x), NbtValue::String(v)) => x.insert(index, v),
+            (NbtList::List(x), NbtValue::List(v)) => x.insert(index, v),
+            (NbtList::Compound(x), NbtValue::Compound(v)) => x.insert(index, v),
+            (NbtList::IntArray(x), NbtValue::IntArray(v)) => x.insert(index, v),
+            (NbtList::LongArray(x), NbtValue::LongArray(v)) => x.insert(index, v),
+            (list, value) => return Err(NbtError::IncorrectType(list.element_tag(), value.tag())),
+        }
+        Ok(())
+    }
+
+    /// 替换第 `index` 个元素, 返回旧的元素
+    ///
+    /// 类型必须和元素类型一致, 下标越界时 panic (和 `Vec` 一样)
+    pub fn set(&mut self, index: usize, value: NbtValue) -> NbtResult<NbtValue> {
+        Ok(match (self, value) {
+            (NbtList::Byte(x), NbtValue::Byte(v)) => {
+                NbtValue::Byte(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Short(x), NbtValue::Short(v)) => {
+                NbtValue::Short(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Int(x), NbtValue::Int(v)) => {
+                NbtValue::Int(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Long(x), NbtValue::Long(v)) => {
+                NbtValue::Long(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Float(x), NbtValue::Float(v)) => {
+                NbtValue::Float(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Double(x), NbtValue::Double(v)) => {
+                NbtValue::Double(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::ByteArray(x), NbtValue::ByteArray(v)) => {
+                NbtValue::ByteArray(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::String(x), NbtValue::String(v)) => {
+                NbtValue::String(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::List(x), NbtValue::List(v)) => {
+                NbtValue::List(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::Compound(x), NbtValue::Compound(v)) => {
+                NbtValue::Compound(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::IntArray(x), NbtValue::IntArray(v)) => {
+                NbtValue::IntArray(std::mem::replace(&mut x[index], v))
+            }
+            (NbtList::LongArray(x), NbtValue::LongArray(v)) => {
+                NbtValue::LongArray(std::mem::replace(&mut x[index], v))
+            }
+            (list, value) => return Err(NbtError::IncorrectType(list.element_tag(), value.tag())),
+        })
+    }
+
+    /// 删除第 `index` 个元素, 后面的元素往前移
+    ///
+    /// 删空了也保留元素类型, 下标越界时 panic (和 `Vec` 一样)
+    pub fn remove(&mut self, index: usize) -> NbtValue {
+        match self {
+            NbtList::End => panic!("removal index (is {index}) should be < len (is 0)"),
+            NbtList::Byte(x) => NbtValue::Byte(x.remove(index)),
+            NbtList::Short(x) => NbtValue::Short(x.remove(index)),
+            NbtList::Int(x) => NbtValue::Int(x.remove(index)),
+            NbtList::Long(x) => NbtValue::Long(x.remove(index)),
+            NbtList::Float(x) => NbtValue::Float(x.remove(index)),
+            NbtList::Double(x) => NbtValue::Double(x.remove(index)),
+            NbtList::ByteArray(x) => NbtValue::ByteArray(x.remove(index)),
+            NbtList::String(x) => NbtValue::String(x.remove(index)),
+            NbtList::List(x) => NbtValue::List(x.remove(index)),
+            NbtList::Compound(x) => NbtValue::Compound(x.remove(index)),
+            NbtList::IntArray(x) => NbtValue::IntArray(x.remove(index)),
+            NbtList::LongArray(x) => NbtValue::LongArray(x.remove(index)),
+        }
+    }
+
+    /// 转换成 `NbtValue` 的列表
+    pub fn into_values(self) -> Vec<NbtValue> {
+        match self {
+            NbtList::End => Vec::new(),
+            NbtList::Byte(x) => x.into_iter().map(NbtValue::Byte).collect(),
+            NbtList::Short(x) => x.into_iter().map(NbtValue::Short).collect(),
+            NbtList::Int(x) => x.into_iter().map(NbtValue::Int).collect(),
+            NbtList::Long(x) => x.into_iter().map(NbtValue::Long).collect(),
+            NbtList::Float(x) => x.into_iter().map(NbtValue::Float).collect(),
+            NbtList::Double(x) => x.into_iter().map(NbtValue::Double).collect(),
+            NbtList::ByteArray(x) => x.into_iter().map(NbtValue::ByteArray).collect(),
+            NbtList::String(x) => x.into_iter().map(NbtValue::String).collect(),
+            NbtList::List(x) => x.into_iter().map(NbtValue::List).collect(),
+            NbtList::Compound(x) => x.into_iter().map(NbtValue::Compound).collect(),
+            NbtList::IntArray(x) => x.into_iter().map(NbtValue::IntArray).collect(),
+            NbtList::LongArray(x) => x.into_iter().map(NbtValue::LongArray).collect(),
+        }
+    }
+
+    /// 复制成 `NbtValue` 的列表
+    pub fn to_values(&self) -> Vec<NbtValue> { self.clone().into_values() }
+
+    /// 数据部分的长度 (元素类型 + 长度 + 数据)
+    pub(crate) fn payload_len<W>(&self) -> usize
+    where
+        W: nbt_version::NbtWriteTrait,
+    {
+        let data = match self {
+            NbtList::End => 0,
+            NbtList::Byte(x) => x.len(),
+            NbtList::Short(x) => x.len() * 2,
+            NbtList::Int(x) => x.iter().map(|v| W::i32_len(*v)).sum(),
+            NbtList::Long(x) => x.iter().map(|v| W::i64_len(*v)).sum(),
+            NbtList::Float(x) => x.len() * 4,
+            NbtList::Double(x) => x.len() * 8,
+            NbtList::ByteArray(x) => x.iter().map(|v| W::i32_len(v.len() as i32) + v.len()).sum(),
+            NbtList::String(x) => x.iter().map(|v| W::nbt_string_len(v)).sum(),
+            NbtList::List(x) => x.iter().map(|v| v.payload_len::<W>()).sum(),
+            NbtList::Compound(x) => x.iter().map(|v| NbtValue::compound_payload_len::<W>(v)).sum(),
+            NbtList::IntArray(x) => {
+                x.iter().map(|v| W::i32_len(v.len() as i32) + v.len() * 4).sum()
+            }
+            NbtList::LongArray(x) => {
+                x.iter().map(|v| W::i32_len(v.len() as i32) + v.len() * 8).sum()
+            }
+        };
+        1 + W::i32_len(self.len() as i32) + data
+    }
+}
+
+impl TryFrom<Vec<NbtValue>> for NbtList {
+    type Error = NbtError;
+
+    /// 元素类型不一致时返回 `ListTypeNotSame`
+    fn try_from(value: Vec<NbtValue>) -> Result<Self, Self::Error> {
+        let Some(tag) = value.first().map(NbtValue::tag) else {
+            return Ok(NbtList::End);
+        };
+        if !value.iter().all(|x| x.tag() == tag) {
+            return Err(NbtError::ListTypeNotSame(value.iter().map(|x| x.tag()).collect()));
+        }
+        let mut list = NbtList::empty(tag)?;
+        for v in value {
+            list.push(v)?;
+        }
+        Ok(list)
+    }
+}
+
+impl From<NbtList> for Vec<NbtValue> {
+    fn from(value: NbtList) -> Self { value.into_values() }
+}
+
+impl IntoIterator for NbtList {
+    type Item = NbtValue;
+    type IntoIter = std::vec::IntoIter<NbtValue>;
+
+    fn into_iter(self) -> Self::IntoIter { self.into_values().into_iter() }
+}
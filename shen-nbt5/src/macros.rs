@@ -0,0 +1,96 @@
+/// 用类似 SNBT 的写法构造 `NbtValue`
+///
+/// ```rust
+/// use shen_nbt5::{nbt, NbtValue};
+///
+/// let name = "Steve";
+/// let value = nbt!{
+///     "key": 1i32,
+///     list: [1b, 2b],
+///     "arr": [I; 1, 2],
+///     nested: { name: name, health: 20.0f, pos: [0.5d, 64.0, -1.5] },
+///     longs: [L; 1, 2L, -3i64],
+/// };
+/// assert_eq!(
+///     value,
+///     NbtValue::from_snbt(
+///         "{key: 1, list: [1b, 2b], arr: [I; 1, 2], \
+///          nested: {name: Steve, health: 20f, pos: [0.5d, 64d, -1.5d]}, longs: [L; 1L, 2L, -3L]}"
+///     )
+///     .unwrap()
+/// );
+/// assert_eq!(nbt!([]), NbtValue::from_snbt("[]").unwrap());
+/// assert_eq!(nbt!("text"), NbtValue::String("text".to_string()));
+/// ```
+///
+/// - key 可以是标识符或者字符串字面量, 重复的 key 会替换掉前面的
+/// - 字面量可以用 Rust 的后缀 (`1i8`, `1u8`, `1.0f32`) 或者 SNBT 的后缀 (`1b`, `1s`, `1L`, `1.0f`, `1d`),
+///   没有后缀的整数是 Int (数组里是数组元素的类型), 小数是 Double, `true` / `false` 是 Byte
+/// - 其他表达式用 `NbtValue::from` 转换
+///
+/// List 元素类型不一致, 数组里的元素类型不对, 或者字面量无效的时候会 panic
+#[macro_export]
+macro_rules! nbt {
+    // Compound 的内容: 每一项是 `key: value`, 逗号分隔
+    (@compound $c:ident) => {};
+    (@compound $c:ident $key:tt : $($rest:tt)*) => {
+        $crate::nbt!(@entry $c $key [] $($rest)*)
+    };
+    (@entry $c:ident $key:tt [$($value:tt)+] , $($rest:tt)*) => {
+        $c.insert($crate::nbt!(@key $key), $crate::nbt!($($value)+));
+        $crate::nbt!(@compound $c $($rest)*);
+    };
+    (@entry $c:ident $key:tt [$($value:tt)+]) => {
+        $c.insert($crate::nbt!(@key $key), $crate::nbt!($($value)+));
+    };
+    (@entry $c:ident $key:tt [$($value:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@entry $c $key [$($value)* $next] $($rest)*)
+    };
+    (@key $key:ident) => { stringify!($key) };
+    (@key $key:expr) => { $key };
+
+    // List 和数组的内容: 按逗号切开放进 `[]` 里, 最后生成 `vec![]`, `$tag` 是没有后缀的整数的类型
+    (@seq $tag:tt [$(($($done:tt)*))*] ()) => {
+        ::std::vec![$($crate::nbt!(@element $tag $($done)*)),*]
+    };
+    (@seq $tag:tt [$($done:tt)*] ($($item:tt)+)) => {
+        $crate::nbt!(@seq $tag [$($done)* ($($item)+)] ())
+    };
+    (@seq $tag:tt [$($done:tt)*] ($($item:tt)+) , $($rest:tt)*) => {
+        $crate::nbt!(@seq $tag [$($done)* ($($item)+)] () $($rest)*)
+    };
+    (@seq $tag:tt [$($done:tt)*] ($($item:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@seq $tag [$($done)*] ($($item)* $next) $($rest)*)
+    };
+    // `literal` 匹配 `-x` 会直接报错, 负号要先拆出来
+    (@element $tag:tt - $lit:literal) => {
+        $crate::convert::__private::literal(concat!("-", stringify!($lit)), $tag)
+    };
+    (@element $tag:tt - $($value:tt)+) => { $crate::NbtValue::from(- $($value)+) };
+    (@element $tag:tt $lit:literal) => {
+        $crate::convert::__private::literal(stringify!($lit), $tag)
+    };
+    (@element $tag:tt $($value:tt)+) => { $crate::nbt!($($value)+) };
+    (@array $tag:tt $($body:tt)*) => {
+        $crate::convert::__private::array($tag, $crate::nbt!(@seq $tag [] () $($body)*))
+    };
+
+    () => { $crate::NbtValue::Compound($crate::NbtCompound::new()) };
+    ({ $($body:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut compound = $crate::NbtCompound::new();
+        $crate::nbt!(@compound compound $($body)*);
+        $crate::NbtValue::Compound(compound)
+    }};
+    ([B; $($body:tt)*]) => { $crate::nbt!(@array 1 $($body)*) };
+    ([I; $($body:tt)*]) => { $crate::nbt!(@array 3 $($body)*) };
+    ([L; $($body:tt)*]) => { $crate::nbt!(@array 4 $($body)*) };
+    ([ $($body:tt)* ]) => {
+        $crate::convert::__private::list($crate::nbt!(@seq 3 [] () $($body)*))
+    };
+    (- $($value:tt)+) => { $crate::nbt!(@element 3 - $($value)+) };
+    ($lit:literal) => { $crate::nbt!(@element 3 $lit) };
+    // 最外层的 `{}` 可以省略
+    ($key:tt : $($rest:tt)*) => { $crate::nbt!({ $key : $($rest)* }) };
+    ($value:expr) => { $crate::NbtValue::from($value) };
+}
@@ -0,0 +1,164 @@
+//! 深度合并
+//!
+//! 默认和游戏里的 `/data merge` (`CompoundTag.merge`) 一样: Compound 递归合并, 其他的 (包括 List) 直接替换
+//!
+//! List 的合并方式可以用 [`MergeOptions`] 改:
+//!
+//! ```rust
+//! use shen_nbt5::{ListMerge, MergeOptions, NbtValue};
+//!
+//! let mut player = NbtValue::from_snbt(
+//!     "{Inventory: [{Slot: 0b, id: stone, Count: 1b}, {Slot: 1b, id: dirt, Count: 1b}], Tags: [a]}",
+//! )
+//! .unwrap();
+//! let template = NbtValue::from_snbt("{Inventory: [{Slot: 1b, Count: 64b}, {Slot: 2b, id: sand}], Tags: [b]}")
+//!     .unwrap();
+//! let options = MergeOptions {
+//!     lists: ListMerge::Append,
+//!     ..MergeOptions::default()
+//! }
+//! .with_list("Inventory", ListMerge::ByKey("Slot".to_string()));
+//! player.merge_with(template, &options);
+//! assert_eq!(
+//!     player,
+//!     NbtValue::from_snbt(
+//!         "{Inventory: [{Slot: 0b, id: stone, Count: 1b}, {Slot: 1b, id: dirt, Count: 64b}, \
+//!          {Slot: 2b, id: sand}], Tags: [a, b]}"
+//!     )
+//!     .unwrap()
+//! );
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{NbtCompound, NbtList, NbtValue};
+
+/// List 的合并方式
+///
+/// 两边元素类型不一样的时候没法合并, 总是直接替换
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ListMerge {
+    /// 整个替换, 和游戏一样
+    #[default]
+    Replace,
+    /// 加到原来的后面
+    Append,
+    /// 同一下标的元素合并, 多出来的加到后面
+    ByIndex,
+    /// 这个 key 的值相同的 Compound 元素合并, 比如物品栏的 `Slot`, 附魔的 `id`
+    ///
+    /// 匹配不到的和没有这个 key 的元素加到后面
+    ByKey(String),
+}
+
+/// 合并选项
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MergeOptions {
+    /// 默认的 List 合并方式
+    pub lists: ListMerge,
+    /// 按 List 所在的 key 单独指定, 任意深度都生效
+    pub list_keys: HashMap<String, ListMerge>,
+}
+
+/// 有 `lists` 和 `list_keys` 的选项 (合并和对比) 共用的 `with_list` 和查找
+macro_rules! list_options {
+    ($options:ty, $rule:ty, $lookup:ident, $what:literal) => {
+        impl $options {
+            #[doc = concat!("给某个 key 下面的 List 指定", $what)]
+            pub fn with_list(mut self, key: impl Into<String>, rule: $rule) -> Self {
+                self.list_keys.insert(key.into(), rule);
+                self
+            }
+
+            /// `key` 是 List 在外层 Compound 里的 key, 没有单独指定的话用 `lists`
+            fn $lookup(&self, key: Option<&str>) -> &$rule {
+                key.and_then(|key| self.list_keys.get(key)).unwrap_or(&self.lists)
+            }
+        }
+    };
+}
+
+pub(crate) use list_options;
+
+list_options!(MergeOptions, ListMerge, list_merge, "合并方式");
+
+impl NbtValue {
+    /// 把 `other` 合并进来, 和游戏里的 `/data merge` 一样
+    ///
+    /// 两边都是 Compound 的时候递归合并, 否则用 `other` 替换
+    pub fn merge(&mut self, other: NbtValue) { self.merge_with(other, &MergeOptions::default()) }
+
+    /// 按 `options` 合并, 两边都是 List 的时候用 `options.lists`
+    pub fn merge_with(&mut self, other: NbtValue, options: &MergeOptions) {
+        merge_value(self, other, options, None)
+    }
+}
+
+impl NbtCompound {
+    /// 和 `CompoundTag.merge` 一样: 同名的 Compound 递归合并, 其他的替换, 没有的 key 加到后面
+    pub fn merge(&mut self, other: NbtCompound) { self.merge_with(other, &MergeOptions::default()) }
+
+    pub fn merge_with(&mut self, other: NbtCompound, options: &MergeOptions) {
+        for (key, value) in other.into_entries() {
+            match self.get_mut(&key) {
+                Some(old) => merge_value(old, value, options, Some(&key)),
+                None => self.push(key, value),
+            }
+        }
+    }
+}
+
+/// `key` 是这个值在外层 Compound 里的 key, 用来找 List 的合并方式
+fn merge_value(target: &mut NbtValue, other: NbtValue, options: &MergeOptions, key: Option<&str>) {
+    match (target, other) {
+        (NbtValue::Compound(target), NbtValue::Compound(other)) => {
+            target.merge_with(other, options)
+        }
+        (NbtValue::List(target), NbtValue::List(other)) => {
+            merge_list(target, other, options, options.list_merge(key))
+        }
+        (target, other) => *target = other,
+    }
+}
+
+fn merge_list(target: &mut NbtList, other: NbtList, options: &MergeOptions, merge: &ListMerge) {
+    let same_type =
+        target.is_empty() || other.is_empty() || target.element_tag() == other.element_tag();
+    if *merge == ListMerge::Replace || !same_type {
+        *target = other;
+        return;
+    }
+    if other.is_empty() {
+        return;
+    }
+    let mut values = std::mem::take(target).into_values();
+    match merge {
+        ListMerge::Replace => unreachable!(),
+        ListMerge::Append => values.extend(other),
+        ListMerge::ByIndex => {
+            for (i, value) in other.into_iter().enumerate() {
+                match values.get_mut(i) {
+                    Some(old) => merge_value(old, value, options, None),
+                    None => values.push(value),
+                }
+            }
+        }
+        ListMerge::ByKey(key) => {
+            for value in other {
+                let found = element_key(&value, key)
+                    .and_then(|id| values.iter().position(|v| element_key(v, key) == Some(id)));
+                match found {
+                    Some(i) => merge_value(&mut values[i], value, options, None),
+                    None => values.push(value),
+                }
+            }
+        }
+    }
+    // 合并前后元素的类型不变
+    *target = NbtList::try_from(values).expect("元素类型相同");
+}
+
+/// `ListMerge::ByKey` / `ListAlign::ByKey` 用来匹配的值
+pub(crate) fn element_key<'a>(value: &'a NbtValue, key: &str) -> Option<&'a NbtValue> {
+    value.as_compound_ref().ok()?.get(key)
+}
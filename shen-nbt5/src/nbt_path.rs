@@ -0,0 +1,800 @@
+//! Minecraft 的 NBT 路径
+//!
+//! `/data` 命令用的那种: `Inventory[{Slot:0b}].tag.display.Name`
+//!
+//! - `foo` / `"foo bar"` / `'foo'`: Compound 的子节点
+//! - `foo{bar:1b}`: 子节点, 并且要匹配后面的 Compound
+//! - `[n]` / `[-1]`: List 或者数组的第 n 个元素, 负数从末尾开始数
+//! - `[]`: 所有元素
+//! - `[{bar:1b}]`: 匹配的所有 Compound 元素
+//! - `{bar:1b}`: 只能在开头, 根节点要匹配这个 Compound
+//!
+//! 匹配规则和游戏一样: Compound 只比较给出的 key, List 里给出的每个元素都要在目标里找到
+//!
+//! 取值, 修改, 删除的行为都和游戏里的 `NbtPathArgument` 一致
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::snbt::SnbtParser;
+use crate::{NbtCompound, NbtError, NbtList, NbtRef, NbtResult, NbtValue};
+
+/// 路径里的一个节点
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// `foo`
+    Child(String),
+    /// `foo{bar:1b}`
+    MatchChild(String, NbtCompound),
+    /// `[]`
+    AllElements,
+    /// `[n]`
+    Index(i32),
+    /// `[{bar:1b}]`
+    MatchElement(NbtCompound),
+    /// `{bar:1b}`
+    MatchRoot(NbtCompound),
+}
+
+impl Node {
+    /// 取值的时候中间缺了东西, 要为这个节点创建什么样的父节点
+    fn preferred_parent(&self) -> NbtValue {
+        match self {
+            Node::Child(_) | Node::MatchChild(..) | Node::MatchRoot(_) => {
+                NbtValue::Compound(NbtCompound::new())
+            }
+            Node::AllElements | Node::Index(_) | Node::MatchElement(_) => {
+                NbtValue::List(NbtList::End)
+            }
+        }
+    }
+}
+
+/// 解析好的 NBT 路径
+///
+/// ```rust
+/// use shen_nbt5::{NbtPath, NbtValue};
+///
+/// let path: NbtPath = "Items[{id:\"minecraft:diamond\"}].Count".parse().unwrap();
+/// assert_eq!(path.to_string(), "Items[{id:\"minecraft:diamond\"}].Count");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtPath {
+    src: String,
+    /// 节点和它在 `src` 里结束的位置
+    nodes: Vec<(Node, usize)>,
+}
+
+/// 可以修改的节点
+///
+/// 同一个 List 的多个元素要同时可变借用, 所以按元素的类型分开
+enum Slot<'a> {
+    Compound(&'a mut NbtCompound),
+    List(&'a mut NbtList),
+    Bytes(&'a mut Vec<i8>),
+    Ints(&'a mut Vec<i32>),
+    Longs(&'a mut Vec<i64>),
+    /// 数字和字符串, 下面不会再有东西
+    Leaf,
+}
+
+impl<'a> Slot<'a> {
+    fn of(value: &'a mut NbtValue) -> Self {
+        match value {
+            NbtValue::Compound(c) => Slot::Compound(c),
+            NbtValue::List(l) => Slot::List(l),
+            NbtValue::ByteArray(v) => Slot::Bytes(v),
+            NbtValue::IntArray(v) => Slot::Ints(v),
+            NbtValue::LongArray(v) => Slot::Longs(v),
+            _ => Slot::Leaf,
+        }
+    }
+
+    fn len(&self) -> Option<usize> {
+        match self {
+            Slot::List(l) => Some(l.len()),
+            Slot::Bytes(v) => Some(v.len()),
+            Slot::Ints(v) => Some(v.len()),
+            Slot::Longs(v) => Some(v.len()),
+            _ => None,
+        }
+    }
+
+    /// 所有元素
+    fn children(self) -> Vec<Slot<'a>> {
+        match self {
+            Slot::List(NbtList::Compound(v)) => v.iter_mut().map(Slot::Compound).collect(),
+            Slot::List(NbtList::List(v)) => v.iter_mut().map(Slot::List).collect(),
+            Slot::List(NbtList::ByteArray(v)) => v.iter_mut().map(Slot::Bytes).collect(),
+            Slot::List(NbtList::IntArray(v)) => v.iter_mut().map(Slot::Ints).collect(),
+            Slot::List(NbtList::LongArray(v)) => v.iter_mut().map(Slot::Longs).collect(),
+            other => (0..other.len().unwrap_or(0)).map(|_| Slot::Leaf).collect(),
+        }
+    }
+
+    /// 第 `index` 个元素
+    fn into_child(self, index: usize) -> Slot<'a> {
+        match self {
+            Slot::List(NbtList::Compound(v)) => Slot::Compound(&mut v[index]),
+            Slot::List(NbtList::List(v)) => Slot::List(&mut v[index]),
+            Slot::List(NbtList::ByteArray(v)) => Slot::Bytes(&mut v[index]),
+            Slot::List(NbtList::IntArray(v)) => Slot::Ints(&mut v[index]),
+            Slot::List(NbtList::LongArray(v)) => Slot::Longs(&mut v[index]),
+            _ => Slot::Leaf,
+        }
+    }
+
+    /// 第 `index` 个元素是否等于 `value`
+    fn element_eq(&self, index: usize, value: &NbtValue) -> bool {
+        match (self, value) {
+            (Slot::List(l), _) => l.get(index).as_ref() == Some(value),
+            (Slot::Bytes(v), NbtValue::Byte(x)) => v[index] == *x,
+            (Slot::Ints(v), NbtValue::Int(x)) => v[index] == *x,
+            (Slot::Longs(v), NbtValue::Long(x)) => v[index] == *x,
+            _ => false,
+        }
+    }
+
+    /// 替换第 `index` 个元素, 类型不对的话返回 `false`
+    ///
+    /// 数组可以放任何数字, 会像游戏里一样转换
+    fn set_element(&mut self, index: usize, value: NbtValue) -> bool {
+        match self {
+            Slot::List(l) => l.set(index, value).is_ok(),
+            Slot::Bytes(v) => numeric_i8(&value).map(|x| v[index] = x).is_some(),
+            Slot::Ints(v) => numeric_i32(&value).map(|x| v[index] = x).is_some(),
+            Slot::Longs(v) => numeric_i64(&value).map(|x| v[index] = x).is_some(),
+            _ => false,
+        }
+    }
+
+    /// `insert_element` 能不能放进这个值, 和下标无关
+    fn accepts(&self, value: &NbtValue) -> bool {
+        match self {
+            Slot::List(l) => l.is_empty() || l.element_tag() == value.tag(),
+            Slot::Bytes(_) => numeric_i8(value).is_some(),
+            Slot::Ints(_) => numeric_i32(value).is_some(),
+            Slot::Longs(_) => numeric_i64(value).is_some(),
+            _ => false,
+        }
+    }
+
+    /// 在 `index` 处插入, 类型不对的话返回 `false`
+    ///
+    /// 空 List 可以放任何类型
+    fn insert_element(&mut self, index: usize, value: NbtValue) -> bool {
+        match self {
+            Slot::List(l) => {
+                if l.is_empty() {
+                    **l = NbtList::End;
+                }
+                l.insert(index, value).is_ok()
+            }
+            Slot::Bytes(v) => numeric_i8(&value).map(|x| v.insert(index, x)).is_some(),
+            Slot::Ints(v) => numeric_i32(&value).map(|x| v.insert(index, x)).is_some(),
+            Slot::Longs(v) => numeric_i64(&value).map(|x| v.insert(index, x)).is_some(),
+            _ => false,
+        }
+    }
+
+    fn remove_element(&mut self, index: usize) {
+        match self {
+            Slot::List(l) => {
+                l.remove(index);
+                // 和游戏一样, 删空了就没有元素类型了
+                if l.is_empty() {
+                    **l = NbtList::End;
+                }
+            }
+            Slot::Bytes(v) => {
+                v.remove(index);
+            }
+            Slot::Ints(v) => {
+                v.remove(index);
+            }
+            Slot::Longs(v) => {
+                v.remove(index);
+            }
+            _ => (),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Slot::List(l) => **l = NbtList::End,
+            Slot::Bytes(v) => v.clear(),
+            Slot::Ints(v) => v.clear(),
+            Slot::Longs(v) => v.clear(),
+            _ => (),
+        }
+    }
+}
+
+/// 游戏里 `NumericTag::getAsByte`
+fn numeric_i8(value: &NbtValue) -> Option<i8> {
+    Some(match value {
+        NbtValue::Float(x) => (x.floor() as i32) as i8,
+        NbtValue::Double(x) => (x.floor() as i32) as i8,
+        other => numeric_i64(other)? as i8,
+    })
+}
+
+/// 游戏里 `NumericTag::getAsInt`
+fn numeric_i32(value: &NbtValue) -> Option<i32> {
+    Some(match value {
+        NbtValue::Float(x) => x.floor() as i32,
+        NbtValue::Double(x) => x.floor() as i32,
+        other => numeric_i64(other)? as i32,
+    })
+}
+
+/// 游戏里 `NumericTag::getAsLong`, Float 是直接截断的
+fn numeric_i64(value: &NbtValue) -> Option<i64> {
+    Some(match value {
+        NbtValue::Byte(x) => *x as i64,
+        NbtValue::Short(x) => *x as i64,
+        NbtValue::Int(x) => *x as i64,
+        NbtValue::Long(x) => *x,
+        NbtValue::Float(x) => *x as i64,
+        NbtValue::Double(x) => x.floor() as i64,
+        _ => return None,
+    })
+}
+
+/// `actual` 是否匹配 `expect`
+///
+/// Compound 只比较 `expect` 里有的 key, List 里 `expect` 的每个元素都要在 `actual` 里找到
+pub(crate) fn value_matches(expect: &NbtValue, actual: &NbtValue) -> bool {
+    match (expect, actual) {
+        (NbtValue::Compound(e), NbtValue::Compound(a)) => compound_matches(e, a),
+        (NbtValue::List(e), NbtValue::List(a)) => list_matches(e, a),
+        _ => expect == actual,
+    }
+}
+
+pub(crate) fn compound_matches(expect: &NbtCompound, actual: &NbtCompound) -> bool {
+    expect.iter().all(|(k, v)| actual.get(k).is_some_and(|a| value_matches(v, a)))
+}
+
+fn list_matches(expect: &NbtList, actual: &NbtList) -> bool {
+    fn all_found<T>(e: &[T], a: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+        e.iter().all(|e| a.iter().any(|a| eq(e, a)))
+    }
+    if expect.is_empty() {
+        return actual.is_empty();
+    }
+    match (expect, actual) {
+        (NbtList::Compound(e), NbtList::Compound(a)) => all_found(e, a, compound_matches),
+        (NbtList::List(e), NbtList::List(a)) => all_found(e, a, list_matches),
+        (NbtList::Byte(e), NbtList::Byte(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::Short(e), NbtList::Short(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::Int(e), NbtList::Int(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::Long(e), NbtList::Long(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::Float(e), NbtList::Float(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::Double(e), NbtList::Double(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::ByteArray(e), NbtList::ByteArray(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::String(e), NbtList::String(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::IntArray(e), NbtList::IntArray(a)) => all_found(e, a, PartialEq::eq),
+        (NbtList::LongArray(e), NbtList::LongArray(a)) => all_found(e, a, PartialEq::eq),
+        _ => false,
+    }
+}
+
+/// 负数下标从末尾开始数, 越界返回 `None`
+fn resolve_index(index: i32, len: usize) -> Option<usize> {
+    let real = if index < 0 {
+        len as i64 + index as i64
+    } else {
+        index as i64
+    };
+    (0..len as i64).contains(&real).then_some(real as usize)
+}
+
+/// 不用引号的 key 可以用的字符
+fn is_unquoted_name_char(c: char) -> bool {
+    !matches!(c, ' ' | '"' | '\'' | '[' | ']' | '.' | '{' | '}')
+}
+
+impl NbtPath {
+    pub fn parse(src: &str) -> NbtResult<Self> {
+        if src.is_empty() {
+            return Err(NbtError::InvalidPath("路径不能为空".to_string()));
+        }
+        // 过滤条件里的 SNBT 错误也算路径格式错误
+        let nodes = Self::parse_nodes(src).map_err(|e| match e {
+            NbtError::InvalidSnbt(_, column, msg) => {
+                NbtError::InvalidPath(format!("{:?} 第 {} 个字符: {}", src, column, msg))
+            }
+            other => other,
+        })?;
+        Ok(Self {
+            src: src.to_string(),
+            nodes,
+        })
+    }
+
+    fn parse_nodes(src: &str) -> NbtResult<Vec<(Node, usize)>> {
+        let mut parser = SnbtParser::new(src);
+        let mut nodes = Vec::new();
+        while parser.peek().is_some_and(|c| c != ' ') {
+            let node = Self::parse_node(&mut parser, nodes.is_empty())?;
+            nodes.push((node, parser.pos));
+            match parser.peek() {
+                None | Some(' ' | '[' | '{') => (),
+                Some('.') => parser.pos += 1,
+                Some(_) => return Err(parser.error("这里应该是 `.`")),
+            }
+        }
+        if parser.peek().is_some() {
+            return Err(parser.error("路径里不能有空格"));
+        }
+        Ok(nodes)
+    }
+
+    fn parse_node(parser: &mut SnbtParser, first: bool) -> NbtResult<Node> {
+        match parser.peek() {
+            Some('{') => {
+                if !first {
+                    return Err(parser.error("`{...}` 只能在路径开头"));
+                }
+                Ok(Node::MatchRoot(parser.parse_compound()?))
+            }
+            Some('[') => {
+                parser.pos += 1;
+                match parser.peek() {
+                    Some('{') => {
+                        let pattern = parser.parse_compound()?;
+                        Self::expect(parser, ']')?;
+                        Ok(Node::MatchElement(pattern))
+                    }
+                    Some(']') => {
+                        parser.pos += 1;
+                        Ok(Node::AllElements)
+                    }
+                    _ => {
+                        let start = parser.pos;
+                        while parser.peek().is_some_and(|c| c.is_ascii_digit() || c == '-') {
+                            parser.pos += 1;
+                        }
+                        let index =
+                            parser.src()[start..parser.pos].parse::<i32>().map_err(|_| {
+                                parser.pos = start;
+                                parser.error("下标必须是整数")
+                            })?;
+                        Self::expect(parser, ']')?;
+                        Ok(Node::Index(index))
+                    }
+                }
+            }
+            Some('"' | '\'') => {
+                let name = parser.parse_quoted()?;
+                Self::parse_child(parser, name)
+            }
+            _ => {
+                let start = parser.pos;
+                while parser.peek().is_some_and(is_unquoted_name_char) {
+                    parser.pos += parser.peek().map_or(0, char::len_utf8);
+                }
+                if start == parser.pos {
+                    return Err(parser.error("这里应该是一个节点"));
+                }
+                let name = parser.src()[start..parser.pos].to_string();
+                Self::parse_child(parser, name)
+            }
+        }
+    }
+
+    fn parse_child(parser: &mut SnbtParser, name: String) -> NbtResult<Node> {
+        if parser.peek() == Some('{') {
+            Ok(Node::MatchChild(name, parser.parse_compound()?))
+        } else {
+            Ok(Node::Child(name))
+        }
+    }
+
+    fn expect(parser: &mut SnbtParser, c: char) -> NbtResult<()> {
+        if parser.peek() == Some(c) {
+            parser.pos += 1;
+            Ok(())
+        } else {
+            Err(parser.error(format!("这里应该是 {:?}", c)))
+        }
+    }
+
+    /// 到第 `i` 个节点为止的路径, 报错用
+    fn not_found(&self, i: usize) -> NbtError {
+        NbtError::PathNotFound(self.src[..self.nodes[i].1].to_string())
+    }
+
+    /// 取出所有匹配的值
+    ///
+    /// 和游戏一样, 没有匹配的时候返回 `PathNotFound`, 里面是第一个没有匹配的节点为止的路径
+    pub fn get<'a>(&self, root: &'a NbtValue) -> NbtResult<Vec<NbtRef<'a>>> {
+        let mut current = vec![NbtRef::Value(root)];
+        for (i, (node, _)) in self.nodes.iter().enumerate() {
+            let mut next = Vec::new();
+            for parent in current {
+                Self::get_children(node, parent, &mut next);
+            }
+            if next.is_empty() {
+                return Err(self.not_found(i));
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+
+    /// 匹配的值的个数
+    pub fn count(&self, root: &NbtValue) -> usize { self.get(root).map_or(0, |v| v.len()) }
+
+    fn get_children<'a>(node: &Node, parent: NbtRef<'a>, out: &mut Vec<NbtRef<'a>>) {
+        match node {
+            Node::Child(name) => {
+                if let Some(child) = parent.as_compound().ok().and_then(|c| c.get(name)) {
+                    out.push(NbtRef::Value(child));
+                }
+            }
+            Node::MatchChild(name, pattern) => {
+                match parent.as_compound().ok().and_then(|c| c.get(name)) {
+                    Some(child @ NbtValue::Compound(c)) if compound_matches(pattern, c) => {
+                        out.push(NbtRef::Value(child))
+                    }
+                    _ => (),
+                }
+            }
+            Node::AllElements => {
+                let len = parent.collection_len().unwrap_or(0);
+                out.extend((0..len).map(|i| parent.child(i)));
+            }
+            Node::Index(index) => {
+                if let Some(i) = parent.collection_len().and_then(|len| resolve_index(*index, len))
+                {
+                    out.push(parent.child(i));
+                }
+            }
+            Node::MatchElement(pattern) => {
+                if let Ok(NbtList::Compound(v)) = parent.as_list() {
+                    for (i, element) in v.iter().enumerate() {
+                        if compound_matches(pattern, element) {
+                            out.push(parent.child(i));
+                        }
+                    }
+                }
+            }
+            Node::MatchRoot(pattern) => {
+                if parent.as_compound().is_ok_and(|c| compound_matches(pattern, c)) {
+                    out.push(parent);
+                }
+            }
+        }
+    }
+
+    /// 走到第 `end` 个节点之前, 中间缺少的节点会创建出来
+    ///
+    /// `last` 是最后一个节点缺少时创建的值
+    fn get_or_create<'a>(
+        &self,
+        root: &'a mut NbtValue,
+        end: usize,
+        last: impl Fn() -> NbtValue,
+    ) -> NbtResult<Vec<Slot<'a>>> {
+        let mut current = vec![Slot::of(root)];
+        for i in 0..end {
+            let create = if i + 1 < end {
+                self.nodes[i + 1].0.preferred_parent()
+            } else {
+                last()
+            };
+            let node = &self.nodes[i].0;
+            let mut next = Vec::new();
+            for parent in current {
+                Self::get_or_create_children(node, parent, &create, &mut next);
+            }
+            if next.is_empty() {
+                return Err(self.not_found(i));
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+
+    fn get_or_create_children<'a>(
+        node: &Node,
+        parent: Slot<'a>,
+        create: &NbtValue,
+        out: &mut Vec<Slot<'a>>,
+    ) {
+        match (node, parent) {
+            (Node::Child(name), Slot::Compound(c)) => {
+                if !c.contains_key(name) {
+                    c.insert(name.clone(), create.clone());
+                }
+                out.push(Slot::of(c.get_mut(name).unwrap()));
+            }
+            (Node::MatchChild(name, pattern), Slot::Compound(c)) => {
+                // 没有的话直接用过滤条件创建, 一定能匹配
+                if !c.contains_key(name) {
+                    c.insert(name.clone(), NbtValue::Compound(pattern.clone()));
+                }
+                match c.get_mut(name) {
+                    Some(NbtValue::Compound(child)) if compound_matches(pattern, child) => {
+                        out.push(Slot::Compound(child))
+                    }
+                    _ => (),
+                }
+            }
+            (Node::AllElements, mut parent) => {
+                if parent.len() == Some(0) {
+                    if parent.insert_element(0, create.clone()) {
+                        out.push(parent.into_child(0));
+                    }
+                } else {
+                    out.extend(parent.children());
+                }
+            }
+            (Node::Index(index), parent) => {
+                if let Some(i) = parent.len().and_then(|len| resolve_index(*index, len)) {
+                    out.push(parent.into_child(i));
+                }
+            }
+            (Node::MatchElement(pattern), Slot::List(l)) => {
+                let matched = match l {
+                    NbtList::Compound(v) => {
+                        v.iter().filter(|element| compound_matches(pattern, element)).count()
+                    }
+                    _ => 0,
+                };
+                if matched == 0 {
+                    if l.is_empty() {
+                        *l = NbtList::End;
+                    }
+                    if l.push(NbtValue::Compound(pattern.clone())).is_ok() {
+                        let last = l.len() - 1;
+                        out.push(Slot::List(l).into_child(last));
+                    }
+                } else if let NbtList::Compound(v) = l {
+                    out.extend(
+                        v.iter_mut()
+                            .filter(|element| compound_matches(pattern, element))
+                            .map(Slot::Compound),
+                    );
+                }
+            }
+            (Node::MatchRoot(pattern), Slot::Compound(c)) if compound_matches(pattern, c) => {
+                out.push(Slot::Compound(c))
+            }
+            _ => (),
+        }
+    }
+
+    /// 把最后一个节点设置成 `value`, 中间缺少的 Compound 和 List 会创建出来
+    ///
+    /// 返回有变化的值的个数, 游戏里为 0 时会提示 "没有变化"
+    pub fn set(&self, root: &mut NbtValue, value: &NbtValue) -> NbtResult<usize> {
+        let last = self.nodes.len() - 1;
+        let create = || self.nodes[last].0.preferred_parent();
+        let parents = self.get_or_create(root, last, create)?;
+        Ok(parents.into_iter().map(|p| Self::set_in(&self.nodes[last].0, p, value)).sum())
+    }
+
+    fn set_in(node: &Node, parent: Slot, value: &NbtValue) -> usize {
+        match (node, parent) {
+            (Node::Child(name), Slot::Compound(c)) => {
+                let old = c.insert(name.clone(), value.clone());
+                usize::from(old.as_ref() != Some(value))
+            }
+            (Node::MatchChild(name, pattern), Slot::Compound(c)) => match c.get_mut(name) {
+                Some(old)
+                    if matches!(&*old, NbtValue::Compound(oc) if compound_matches(pattern, oc))
+                        && old != value =>
+                {
+                    *old = value.clone();
+                    1
+                }
+                _ => 0,
+            },
+            (Node::AllElements, mut parent) => {
+                let Some(len) = parent.len() else {
+                    return 0;
+                };
+                if len == 0 {
+                    return usize::from(parent.insert_element(0, value.clone()));
+                }
+                let changed = (0..len).filter(|i| !parent.element_eq(*i, value)).count();
+                if changed == 0 {
+                    return 0;
+                }
+                // 和游戏一样, 先清空再放进去, 类型不对的数组会变成空的
+                parent.clear();
+                for i in 0..len {
+                    if !parent.insert_element(i, value.clone()) {
+                        return 0;
+                    }
+                }
+                changed
+            }
+            (Node::Index(index), mut parent) => {
+                match parent.len().and_then(|len| resolve_index(*index, len)) {
+                    Some(i) if !parent.element_eq(i, value) => {
+                        usize::from(parent.set_element(i, value.clone()))
+                    }
+                    _ => 0,
+                }
+            }
+            (Node::MatchElement(pattern), mut parent @ Slot::List(_)) => {
+                // 和游戏一样, 空 List 直接放进去, 不管匹不匹配
+                if parent.len() == Some(0) {
+                    return usize::from(parent.insert_element(0, value.clone()));
+                }
+                let Slot::List(NbtList::Compound(v)) = parent else {
+                    return 0;
+                };
+                let NbtValue::Compound(new) = value else {
+                    return 0;
+                };
+                let mut changed = 0;
+                for element in v.iter_mut() {
+                    if compound_matches(pattern, element) && element != new {
+                        *element = new.clone();
+                        changed += 1;
+                    }
+                }
+                changed
+            }
+            _ => 0,
+        }
+    }
+
+    /// 在每个匹配的 List 或者数组的 `index` 处插入 `values`
+    ///
+    /// `index` 为负数时从末尾开始数, `-1` 就是末尾, 对应游戏里的 `insert`, `append` 和 `prepend`
+    ///
+    /// 返回有变化的 List 的个数; 和游戏一样, 类型不对的值会跳过
+    ///
+    /// 有目标不是 List 或者数组时返回 `ExpectedList`, 要插入的时候下标越界返回 `InvalidIndex`,
+    /// 出错时 `root` 保持不变
+    pub fn insert(&self, root: &mut NbtValue, index: i32, values: &[NbtValue]) -> NbtResult<usize> {
+        let mut copy = root.clone();
+        let targets =
+            self.get_or_create(&mut copy, self.nodes.len(), || NbtValue::List(NbtList::End))?;
+        let mut changed = 0;
+        for mut target in targets {
+            let Some(len) = target.len() else {
+                return Err(NbtError::ExpectedList(self.src.clone()));
+            };
+            let mut i = if index < 0 {
+                len as i64 + index as i64 + 1
+            } else {
+                index as i64
+            };
+            let mut any = false;
+            for value in values {
+                // 类型不对的不会用到下标, 游戏里也不会报错
+                if !target.accepts(value) {
+                    continue;
+                }
+                if i < 0 || i > target.len().unwrap_or(0) as i64 {
+                    return Err(NbtError::InvalidIndex(self.src.clone(), i));
+                }
+                target.insert_element(i as usize, value.clone());
+                i += 1;
+                any = true;
+            }
+            changed += usize::from(any);
+        }
+        *root = copy;
+        Ok(changed)
+    }
+
+    /// 加到每个匹配的 List 或者数组的末尾
+    pub fn append(&self, root: &mut NbtValue, values: &[NbtValue]) -> NbtResult<usize> {
+        self.insert(root, -1, values)
+    }
+
+    /// 加到每个匹配的 List 或者数组的开头
+    pub fn prepend(&self, root: &mut NbtValue, values: &[NbtValue]) -> NbtResult<usize> {
+        self.insert(root, 0, values)
+    }
+
+    /// 删除所有匹配的值, 返回删除的个数
+    pub fn remove(&self, root: &mut NbtValue) -> NbtResult<usize> {
+        let last = self.nodes.len() - 1;
+        let mut parents = vec![Slot::of(root)];
+        for i in 0..last {
+            let node = &self.nodes[i].0;
+            let mut next = Vec::new();
+            for parent in parents {
+                Self::get_mut_children(node, parent, &mut next);
+            }
+            if next.is_empty() {
+                return Err(self.not_found(i));
+            }
+            parents = next;
+        }
+        Ok(parents.into_iter().map(|p| Self::remove_in(&self.nodes[last].0, p)).sum())
+    }
+
+    /// 不创建的版本
+    fn get_mut_children<'a>(node: &Node, parent: Slot<'a>, out: &mut Vec<Slot<'a>>) {
+        match (node, parent) {
+            (Node::Child(name), Slot::Compound(c)) => {
+                if let Some(child) = c.get_mut(name) {
+                    out.push(Slot::of(child));
+                }
+            }
+            (Node::MatchChild(name, pattern), Slot::Compound(c)) => {
+                if let Some(NbtValue::Compound(child)) = c.get_mut(name) {
+                    if compound_matches(pattern, child) {
+                        out.push(Slot::Compound(child));
+                    }
+                }
+            }
+            (Node::AllElements, parent) => out.extend(parent.children()),
+            (Node::Index(index), parent) => {
+                if let Some(i) = parent.len().and_then(|len| resolve_index(*index, len)) {
+                    out.push(parent.into_child(i));
+                }
+            }
+            (Node::MatchElement(pattern), Slot::List(NbtList::Compound(v))) => out.extend(
+                v.iter_mut()
+                    .filter(|element| compound_matches(pattern, element))
+                    .map(Slot::Compound),
+            ),
+            (Node::MatchRoot(pattern), Slot::Compound(c)) if compound_matches(pattern, c) => {
+                out.push(Slot::Compound(c))
+            }
+            _ => (),
+        }
+    }
+
+    fn remove_in(node: &Node, parent: Slot) -> usize {
+        match (node, parent) {
+            (Node::Child(name), Slot::Compound(c)) => usize::from(c.remove(name).is_some()),
+            (Node::MatchChild(name, pattern), Slot::Compound(c)) => match c.get(name) {
+                Some(NbtValue::Compound(child)) if compound_matches(pattern, child) => {
+                    c.remove(name);
+                    1
+                }
+                _ => 0,
+            },
+            (Node::AllElements, mut parent) => {
+                let len = parent.len().unwrap_or(0);
+                parent.clear();
+                len
+            }
+            (Node::Index(index), mut parent) => {
+                match parent.len().and_then(|len| resolve_index(*index, len)) {
+                    Some(i) => {
+                        parent.remove_element(i);
+                        1
+                    }
+                    None => 0,
+                }
+            }
+            (Node::MatchElement(pattern), Slot::List(l)) => {
+                let NbtList::Compound(v) = l else {
+                    return 0;
+                };
+                let before = v.len();
+                v.retain(|element| !compound_matches(pattern, element));
+                let removed = before - v.len();
+                if v.is_empty() {
+                    *l = NbtList::End;
+                }
+                removed
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for NbtPath {
+    type Err = NbtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { NbtPath::parse(s) }
+}
+
+/// 原样输出解析时的字符串
+impl fmt::Display for NbtPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.src) }
+}
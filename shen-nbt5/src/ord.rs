@@ -0,0 +1,230 @@
+//! 全序和哈希
+//!
+//! `NbtValue` 里有浮点数, 只能 `PartialEq`; 要当 `HashMap` 的 key 或者放进 `BTreeSet` 的时候
+//! 用 [`NbtKey`] 包一下:
+//!
+//! ```rust
+//! use std::collections::HashSet;
+//!
+//! use shen_nbt5::{NbtKey, NbtUnorderedKey, NbtValue};
+//!
+//! let states = ["{Name: stone}", "{Name: dirt}", "{Name: stone}"];
+//! let unique = states
+//!     .iter()
+//!     .map(|s| NbtKey(NbtValue::from_snbt(s).unwrap()))
+//!     .collect::<HashSet<_>>();
+//! assert_eq!(unique.len(), 2);
+//!
+//! // 不管 Compound 里 key 的顺序
+//! let a = NbtValue::from_snbt("{facing: north, half: top}").unwrap();
+//! let b = NbtValue::from_snbt("{half: top, facing: north}").unwrap();
+//! assert_ne!(NbtKey(a.clone()), NbtKey(b.clone()));
+//! assert_eq!(NbtUnorderedKey(a), NbtUnorderedKey(b));
+//! ```
+//!
+//! 比较规则:
+//! - 先比较类型 id, 再比较内容
+//! - 浮点数按位比较 (`f64::total_cmp`), 所以 `NaN` 等于同样位模式的 `NaN`, `0.0` 和 `-0.0` 不相等
+//! - List 先比较元素类型 (空 List 也是), 再逐个元素比较
+//! - 除了浮点数以外和 `PartialEq` 的结果一致
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use crate::{NbtCompound, NbtList, NbtValue};
+
+impl NbtValue {
+    /// 全序比较, Compound 按 key 的顺序逐个比较
+    ///
+    /// 两个值 `==` 的时候一定是 `Equal`, 反过来只有浮点数例外
+    pub fn total_cmp(&self, other: &NbtValue) -> Ordering { cmp_value(self, other, false) }
+
+    /// 全序比较, Compound 先按 key 排序再比较 (重复的 key 保持原来的顺序)
+    pub fn total_cmp_unordered(&self, other: &NbtValue) -> Ordering { cmp_value(self, other, true) }
+}
+
+/// 可以当 `HashMap` / `BTreeMap` 的 key 的 `NbtValue`
+///
+/// `Eq` / `Ord` / `Hash` 用 [`NbtValue::total_cmp`], Compound 里 key 的顺序不同就不相等
+#[derive(Debug, Clone)]
+pub struct NbtKey(pub NbtValue);
+
+/// 和 [`NbtKey`] 一样, 但是不管 Compound 里 key 的顺序
+///
+/// `Eq` / `Ord` / `Hash` 用 [`NbtValue::total_cmp_unordered`]
+#[derive(Debug, Clone)]
+pub struct NbtUnorderedKey(pub NbtValue);
+
+/// 两种 key 的实现只差一个 `unordered`
+macro_rules! key_impl {
+    ($name:ident, $unordered:expr) => {
+        impl $name {
+            #[inline]
+            pub fn into_inner(self) -> NbtValue { self.0 }
+        }
+
+        impl From<NbtValue> for $name {
+            fn from(value: NbtValue) -> Self { Self(value) }
+        }
+
+        impl From<$name> for NbtValue {
+            fn from(key: $name) -> Self { key.0 }
+        }
+
+        impl Deref for $name {
+            type Target = NbtValue;
+
+            fn deref(&self) -> &NbtValue { &self.0 }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering { cmp_value(&self.0, &other.0, $unordered) }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) { hash_value(&self.0, state, $unordered) }
+        }
+    };
+}
+
+key_impl!(NbtKey, false);
+key_impl!(NbtUnorderedKey, true);
+
+/// 逐个元素比较, 前面都相同的时候短的在前面
+fn cmp_slice<T>(a: &[T], b: &[T], mut cmp: impl FnMut(&T, &T) -> Ordering) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| cmp(a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn cmp_value(a: &NbtValue, b: &NbtValue, unordered: bool) -> Ordering {
+    match (a, b) {
+        (NbtValue::Byte(a), NbtValue::Byte(b)) => a.cmp(b),
+        (NbtValue::Short(a), NbtValue::Short(b)) => a.cmp(b),
+        (NbtValue::Int(a), NbtValue::Int(b)) => a.cmp(b),
+        (NbtValue::Long(a), NbtValue::Long(b)) => a.cmp(b),
+        (NbtValue::Float(a), NbtValue::Float(b)) => a.total_cmp(b),
+        (NbtValue::Double(a), NbtValue::Double(b)) => a.total_cmp(b),
+        (NbtValue::ByteArray(a), NbtValue::ByteArray(b)) => a.cmp(b),
+        (NbtValue::String(a), NbtValue::String(b)) => a.cmp(b),
+        (NbtValue::List(a), NbtValue::List(b)) => cmp_list(a, b, unordered),
+        (NbtValue::Compound(a), NbtValue::Compound(b)) => cmp_compound(a, b, unordered),
+        (NbtValue::IntArray(a), NbtValue::IntArray(b)) => a.cmp(b),
+        (NbtValue::LongArray(a), NbtValue::LongArray(b)) => a.cmp(b),
+        _ => a.tag().cmp(&b.tag()),
+    }
+}
+
+fn cmp_list(a: &NbtList, b: &NbtList, unordered: bool) -> Ordering {
+    match (a, b) {
+        (NbtList::End, NbtList::End) => Ordering::Equal,
+        (NbtList::Byte(a), NbtList::Byte(b)) => a.cmp(b),
+        (NbtList::Short(a), NbtList::Short(b)) => a.cmp(b),
+        (NbtList::Int(a), NbtList::Int(b)) => a.cmp(b),
+        (NbtList::Long(a), NbtList::Long(b)) => a.cmp(b),
+        (NbtList::Float(a), NbtList::Float(b)) => cmp_slice(a, b, f32::total_cmp),
+        (NbtList::Double(a), NbtList::Double(b)) => cmp_slice(a, b, f64::total_cmp),
+        (NbtList::ByteArray(a), NbtList::ByteArray(b)) => a.cmp(b),
+        (NbtList::String(a), NbtList::String(b)) => a.cmp(b),
+        (NbtList::List(a), NbtList::List(b)) => cmp_slice(a, b, |a, b| cmp_list(a, b, unordered)),
+        (NbtList::Compound(a), NbtList::Compound(b)) => {
+            cmp_slice(a, b, |a, b| cmp_compound(a, b, unordered))
+        }
+        (NbtList::IntArray(a), NbtList::IntArray(b)) => a.cmp(b),
+        (NbtList::LongArray(a), NbtList::LongArray(b)) => a.cmp(b),
+        _ => a.element_tag().cmp(&b.element_tag()),
+    }
+}
+
+/// 按 key 稳定排序, 重复的 key 保持原来的顺序
+fn sorted_entries(data: &NbtCompound) -> Vec<&(String, NbtValue)> {
+    let mut entries = data.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn cmp_compound(a: &NbtCompound, b: &NbtCompound, unordered: bool) -> Ordering {
+    let cmp_entry = |a: &(String, NbtValue), b: &(String, NbtValue)| {
+        a.0.cmp(&b.0).then_with(|| cmp_value(&a.1, &b.1, unordered))
+    };
+    if unordered {
+        cmp_slice(&sorted_entries(a), &sorted_entries(b), |a, b| cmp_entry(a, b))
+    } else {
+        cmp_slice(a.entries(), b.entries(), cmp_entry)
+    }
+}
+
+fn hash_value<H: Hasher>(value: &NbtValue, state: &mut H, unordered: bool) {
+    state.write_u8(value.tag());
+    match value {
+        NbtValue::Byte(x) => x.hash(state),
+        NbtValue::Short(x) => x.hash(state),
+        NbtValue::Int(x) => x.hash(state),
+        NbtValue::Long(x) => x.hash(state),
+        NbtValue::Float(x) => x.to_bits().hash(state),
+        NbtValue::Double(x) => x.to_bits().hash(state),
+        NbtValue::ByteArray(x) => x.hash(state),
+        NbtValue::String(x) => x.hash(state),
+        NbtValue::List(x) => hash_list(x, state, unordered),
+        NbtValue::Compound(x) => hash_compound(x, state, unordered),
+        NbtValue::IntArray(x) => x.hash(state),
+        NbtValue::LongArray(x) => x.hash(state),
+    }
+}
+
+fn hash_list<H: Hasher>(list: &NbtList, state: &mut H, unordered: bool) {
+    state.write_u8(list.element_tag());
+    match list {
+        NbtList::End => state.write_usize(0),
+        NbtList::Byte(x) => x.hash(state),
+        NbtList::Short(x) => x.hash(state),
+        NbtList::Int(x) => x.hash(state),
+        NbtList::Long(x) => x.hash(state),
+        NbtList::Float(x) => {
+            state.write_usize(x.len());
+            x.iter().for_each(|v| v.to_bits().hash(state));
+        }
+        NbtList::Double(x) => {
+            state.write_usize(x.len());
+            x.iter().for_each(|v| v.to_bits().hash(state));
+        }
+        NbtList::ByteArray(x) => x.hash(state),
+        NbtList::String(x) => x.hash(state),
+        NbtList::List(x) => {
+            state.write_usize(x.len());
+            x.iter().for_each(|v| hash_list(v, state, unordered));
+        }
+        NbtList::Compound(x) => {
+            state.write_usize(x.len());
+            x.iter().for_each(|v| hash_compound(v, state, unordered));
+        }
+        NbtList::IntArray(x) => x.hash(state),
+        NbtList::LongArray(x) => x.hash(state),
+    }
+}
+
+fn hash_compound<H: Hasher>(data: &NbtCompound, state: &mut H, unordered: bool) {
+    state.write_usize(data.len());
+    let hash_entry = |(key, value): &(String, NbtValue)| {
+        key.hash(state);
+        hash_value(value, state, unordered);
+    };
+    if unordered {
+        sorted_entries(data).into_iter().for_each(hash_entry);
+    } else {
+        data.iter().for_each(hash_entry);
+    }
+}
@@ -0,0 +1,317 @@
+//! NBT 补丁
+//!
+//! 参考 JSON Patch (RFC 6902): 路径换成 [`NbtValue::get_path`] 的格式, 值带着 NBT 类型
+//!
+//! 补丁本身也是 NBT, 可以写成 SNBT 文件:
+//!
+//! ```rust
+//! use shen_nbt5::{NbtPatch, NbtValue};
+//!
+//! let patch = NbtPatch::from_snbt(
+//!     "[{op: test, path: DataVersion, value: 3465}, \
+//!      {op: replace, path: DataVersion, value: 3700}, \
+//!      {op: move, from: Data.OldName, path: Data.NewName}, \
+//!      {op: add, path: 'Data.Tags[-1]', value: migrated}]",
+//! )
+//! .unwrap();
+//! let mut value =
+//!     NbtValue::from_snbt("{DataVersion: 3465, Data: {OldName: x, Tags: [a]}}").unwrap();
+//! let before = value.clone();
+//! let undo = value.invert_patch(&patch).unwrap();
+//! value.apply_patch(&patch).unwrap();
+//! assert_eq!(
+//!     value,
+//!     NbtValue::from_snbt("{DataVersion: 3700, Data: {Tags: [a, migrated], NewName: x}}").unwrap()
+//! );
+//! value.apply_patch(&undo).unwrap();
+//! // 移回去的 key 在最后面, 内容是一样的
+//! assert_eq!(value.to_canonical(), before.to_canonical());
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::convert::{FromNbt, ToNbt};
+use crate::diff::{DiffChange, NbtDiff};
+use crate::{NbtCompound, NbtError, NbtResult, NbtValue};
+
+/// 一个补丁操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    /// 和 `NbtValue::insert_path` 一样: Compound 里的 key 直接写入, List 和数组里插入到这个位置;
+    /// 空路径替换整个值
+    Add { path: String, value: NbtValue },
+    /// 删掉这个值, 必须存在
+    Remove { path: String },
+    /// 替换这个值, 必须存在, List 和数组的元素只能换成同类型的值
+    Replace { path: String, value: NbtValue },
+    /// 从 `from` 删掉, 再按 `Add` 的规则写到 `path`
+    Move { from: String, path: String },
+    /// 把 `from` 的值按 `Add` 的规则写到 `path`
+    Copy { from: String, path: String },
+    /// 检查值是否相同, 类型也要一致 (`1b` 和 `1` 不一样)
+    ///
+    /// 浮点数按位比较, 和 [`NbtValue::total_cmp`] 一样: `NaN` 等于 `NaN`, `0.0` 不等于 `-0.0`
+    Test { path: String, value: NbtValue },
+}
+
+/// 补丁: 按顺序执行的一串操作
+///
+/// 和 NBT 互相转换的格式是 Compound 的 List, 每个 Compound 有 `op` 和
+/// `path` / `from` / `value` 里需要的几个
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NbtPatch {
+    pub ops: Vec<PatchOp>,
+}
+
+impl NbtPatch {
+    pub fn new() -> Self { Self::default() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.ops.is_empty() }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.ops.len() }
+
+    pub fn from_snbt(src: &str) -> NbtResult<Self> { Self::from_nbt(NbtValue::from_snbt(src)?) }
+
+    pub fn to_snbt(&self) -> String {
+        self.to_nbt().expect("补丁总是能转换成 NBT").to_snbt()
+    }
+}
+
+impl From<Vec<PatchOp>> for NbtPatch {
+    fn from(ops: Vec<PatchOp>) -> Self { Self { ops } }
+}
+
+/// 删除变成 `Remove`, 新增变成 `Add`, 修改变成 `Replace`, 移动变成 `Move`
+///
+/// 打到对比时的旧值上会得到新值
+impl From<&NbtDiff> for NbtPatch {
+    fn from(diff: &NbtDiff) -> Self {
+        diff.iter()
+            .map(|entry| {
+                let path = entry.path.clone();
+                match &entry.change {
+                    DiffChange::Added(value) => PatchOp::Add {
+                        path,
+                        value: value.clone(),
+                    },
+                    DiffChange::Removed(_) => PatchOp::Remove { path },
+                    DiffChange::Moved { from } => PatchOp::Move {
+                        from: from.clone(),
+                        path,
+                    },
+                    DiffChange::Changed { new, .. } | DiffChange::TypeChanged { new, .. } => {
+                        PatchOp::Replace {
+                            path,
+                            value: new.clone(),
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+impl From<NbtDiff> for NbtPatch {
+    fn from(diff: NbtDiff) -> Self { Self::from(&diff) }
+}
+
+impl NbtValue {
+    /// 按顺序执行补丁里的操作
+    ///
+    /// 任何一个操作失败时返回 `PatchFailed`, 并且 `self` 保持不变
+    pub fn apply_patch(&mut self, patch: &NbtPatch) -> NbtResult<()> {
+        let mut value = self.clone();
+        for (i, op) in patch.ops.iter().enumerate() {
+            apply_op(&mut value, op).map_err(|e| NbtError::PatchFailed(i, Box::new(e)))?;
+        }
+        *self = value;
+        Ok(())
+    }
+
+    /// 生成撤销补丁: `self` 是打补丁之前的值, 打完 `patch` 再打返回的补丁会变回 `self`
+    ///
+    /// 路径里的负数下标会换成真正的下标, `Test` 操作不会出现在撤销补丁里
+    ///
+    /// 删掉又加回来的 key 会在 Compound 的最后面, 所以 key 的顺序可能和原来不一样
+    pub fn invert_patch(&self, patch: &NbtPatch) -> NbtResult<NbtPatch> {
+        let mut value = self.clone();
+        let mut inverse = Vec::with_capacity(patch.ops.len());
+        for (i, op) in patch.ops.iter().enumerate() {
+            let result = invert_op(&value, op).and_then(|ops| {
+                apply_op(&mut value, op)?;
+                Ok(ops)
+            });
+            inverse.push(result.map_err(|e| NbtError::PatchFailed(i, Box::new(e)))?);
+        }
+        Ok(inverse.into_iter().rev().flatten().collect::<Vec<_>>().into())
+    }
+}
+
+fn add(root: &mut NbtValue, path: &str, value: NbtValue) -> NbtResult<()> {
+    if path.is_empty() {
+        *root = value;
+        return Ok(());
+    }
+    root.insert_path(path, value).map(|_| ())
+}
+
+fn apply_op(root: &mut NbtValue, op: &PatchOp) -> NbtResult<()> {
+    match op {
+        PatchOp::Add { path, value } => add(root, path, value.clone()),
+        PatchOp::Remove { path } => root.remove_path(path).map(|_| ()),
+        PatchOp::Replace { path, value } => root.get_path_mut(path)?.set(value.clone()).map(|_| ()),
+        PatchOp::Move { from, path } if from == path => root.get_path(from).map(|_| ()),
+        PatchOp::Move { from, path } => {
+            let value = root.remove_path(from)?;
+            add(root, path, value)
+        }
+        PatchOp::Copy { from, path } => {
+            let value = root.get_path(from)?.to_value();
+            add(root, path, value)
+        }
+        PatchOp::Test { path, value } => {
+            if root.get_path(path)?.to_value().total_cmp(value) != Ordering::Equal {
+                return Err(NbtError::PatchTestFailed(path.clone()));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 撤销 `Add` 到 `path` 的操作, `root` 是写入之前的值
+fn invert_add(root: &NbtValue, path: &str) -> NbtResult<Vec<PatchOp>> {
+    if path.is_empty() {
+        return Ok(vec![PatchOp::Replace {
+            path: String::new(),
+            value: root.clone(),
+        }]);
+    }
+    let path = root.resolve_path(path, true)?;
+    // 插入到 List 里的, 或者原来没有这个 key 的, 删掉就行
+    match root.get_path(&path) {
+        Ok(old) if !path.ends_with(']') => Ok(vec![PatchOp::Replace {
+            value: old.to_value(),
+            path,
+        }]),
+        _ => Ok(vec![PatchOp::Remove { path }]),
+    }
+}
+
+/// 撤销 `op` 需要的操作, `root` 是执行 `op` 之前的值
+fn invert_op(root: &NbtValue, op: &PatchOp) -> NbtResult<Vec<PatchOp>> {
+    match op {
+        PatchOp::Add { path, .. } | PatchOp::Copy { path, .. } => invert_add(root, path),
+        PatchOp::Remove { path } => {
+            let path = root.resolve_path(path, false)?;
+            let value = root.get_path(&path)?.to_value();
+            Ok(vec![PatchOp::Add { path, value }])
+        }
+        PatchOp::Replace { path, .. } => {
+            let path = root.resolve_path(path, false)?;
+            let value = root.get_path(&path)?.to_value();
+            Ok(vec![PatchOp::Replace { path, value }])
+        }
+        PatchOp::Move { from, path } if from == path => Ok(Vec::new()),
+        // 移到根节点的话直接换回原来的整个值
+        PatchOp::Move { path, .. } if path.is_empty() => invert_add(root, path),
+        PatchOp::Move { from, path } => {
+            let from = root.resolve_path(from, false)?;
+            let mut removed = root.clone();
+            removed.remove_path(&from)?;
+            // 先移回去, 再恢复被覆盖的值
+            let mut ops = invert_add(&removed, path)?;
+            let path = match ops.remove(0) {
+                PatchOp::Remove { path } => path,
+                PatchOp::Replace { path, value } => {
+                    ops.push(PatchOp::Add {
+                        path: path.clone(),
+                        value,
+                    });
+                    path
+                }
+                _ => unreachable!(),
+            };
+            ops.insert(
+                0,
+                PatchOp::Move {
+                    from: path,
+                    path: from,
+                },
+            );
+            Ok(ops)
+        }
+        PatchOp::Test { .. } => Ok(Vec::new()),
+    }
+}
+
+impl ToNbt for PatchOp {
+    fn to_nbt(&self) -> NbtResult<NbtValue> {
+        let mut compound = NbtCompound::new();
+        let (op, from, path, value) = match self {
+            PatchOp::Add { path, value } => ("add", None, path, Some(value)),
+            PatchOp::Remove { path } => ("remove", None, path, None),
+            PatchOp::Replace { path, value } => ("replace", None, path, Some(value)),
+            PatchOp::Move { from, path } => ("move", Some(from), path, None),
+            PatchOp::Copy { from, path } => ("copy", Some(from), path, None),
+            PatchOp::Test { path, value } => ("test", None, path, Some(value)),
+        };
+        compound.push("op", op.into());
+        if let Some(from) = from {
+            compound.push("from", from.as_str().into());
+        }
+        compound.push("path", path.as_str().into());
+        if let Some(value) = value {
+            compound.push("value", value.clone());
+        }
+        Ok(NbtValue::Compound(compound))
+    }
+}
+
+impl FromNbt for PatchOp {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        let mut compound = NbtCompound::from_nbt(value)?;
+        let mut take = |key: &str| -> NbtResult<NbtValue> {
+            compound.remove(key).ok_or_else(|| NbtError::PathNotFound(key.to_string()))
+        };
+        let mut string = |key: &str| -> NbtResult<String> {
+            String::from_nbt(take(key)?).map_err(|e| e.in_field(key))
+        };
+        let op = string("op")?;
+        let path = string("path")?;
+        Ok(match op.as_str() {
+            "add" => PatchOp::Add {
+                path,
+                value: take("value")?,
+            },
+            "remove" => PatchOp::Remove { path },
+            "replace" => PatchOp::Replace {
+                path,
+                value: take("value")?,
+            },
+            "move" => PatchOp::Move {
+                from: string("from")?,
+                path,
+            },
+            "copy" => PatchOp::Copy {
+                from: string("from")?,
+                path,
+            },
+            "test" => PatchOp::Test {
+                path,
+                value: take("value")?,
+            },
+            _ => return Err(NbtError::UnknownErr(format!("未知的补丁操作: {}", op))),
+        })
+    }
+}
+
+impl ToNbt for NbtPatch {
+    fn to_nbt(&self) -> NbtResult<NbtValue> { self.ops.to_nbt() }
+}
+
+impl FromNbt for NbtPatch {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> { Vec::from_nbt(value).map(Self::from) }
+}
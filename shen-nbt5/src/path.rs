@@ -0,0 +1,520 @@
+//! 按路径访问嵌套的值
+//!
+//! 路径长这样: `Data.Player.Inventory[3].id`
+//! - `.` 分隔 Compound 的 key
+//! - `[n]` 取 List 或者数组的第 n 个元素, 负数从末尾开始数
+//! - key 里有 `.` `[` 的时候可以用双引号包起来: `"a.b".c`, 里面用 `\"` `\\` 转义
+//!
+//! 空路径指向根节点本身
+
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue};
+
+/// 路径指向的值
+///
+/// List 的元素是按类型存的, 没有对应的 `NbtValue`, 所以用 List + 下标表示
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NbtRef<'a> {
+    /// Compound 里的值, 或者根节点
+    Value(&'a NbtValue),
+    /// List 里的第几个元素
+    Element(&'a NbtList, usize),
+    /// ByteArray 里的元素
+    Byte(&'a i8),
+    /// IntArray 里的元素
+    Int(&'a i32),
+    /// LongArray 里的元素
+    Long(&'a i64),
+}
+
+/// 路径指向的值, 可变版本
+#[derive(Debug, PartialEq)]
+pub enum NbtMut<'a> {
+    /// Compound 里的值, 或者根节点
+    Value(&'a mut NbtValue),
+    /// List 里的第几个元素
+    Element(&'a mut NbtList, usize),
+    /// ByteArray 里的元素
+    Byte(&'a mut i8),
+    /// IntArray 里的元素
+    Int(&'a mut i32),
+    /// LongArray 里的元素
+    Long(&'a mut i64),
+}
+
+impl<'a> NbtRef<'a> {
+    pub fn tag(&self) -> NbtTypeId {
+        match self {
+            NbtRef::Value(v) => v.tag(),
+            NbtRef::Element(list, _) => list.element_tag(),
+            NbtRef::Byte(_) => 1,
+            NbtRef::Int(_) => 3,
+            NbtRef::Long(_) => 4,
+        }
+    }
+
+    /// 复制一份出来
+    ///
+    /// `Element` 的下标越界时 panic
+    pub fn to_value(&self) -> NbtValue {
+        match self {
+            NbtRef::Value(v) => (*v).clone(),
+            NbtRef::Element(list, i) => list.get(*i).expect("NbtRef 的下标越界"),
+            NbtRef::Byte(v) => NbtValue::Byte(**v),
+            NbtRef::Int(v) => NbtValue::Int(**v),
+            NbtRef::Long(v) => NbtValue::Long(**v),
+        }
+    }
+
+    pub fn as_compound(&self) -> NbtResult<&'a NbtCompound> {
+        match *self {
+            NbtRef::Value(NbtValue::Compound(c)) => Ok(c),
+            NbtRef::Element(NbtList::Compound(v), i) => Ok(&v[i]),
+            _ => Err(NbtError::IncorrectType(10, self.tag())),
+        }
+    }
+
+    pub fn as_list(&self) -> NbtResult<&'a NbtList> {
+        match *self {
+            NbtRef::Value(NbtValue::List(l)) => Ok(l),
+            NbtRef::Element(NbtList::List(v), i) => Ok(&v[i]),
+            _ => Err(NbtError::IncorrectType(9, self.tag())),
+        }
+    }
+
+    /// List 或者数组的长度, 其他类型返回 `None`
+    pub(crate) fn collection_len(&self) -> Option<usize> {
+        Some(match *self {
+            NbtRef::Value(NbtValue::List(l)) => l.len(),
+            NbtRef::Value(NbtValue::ByteArray(v)) => v.len(),
+            NbtRef::Value(NbtValue::IntArray(v)) => v.len(),
+            NbtRef::Value(NbtValue::LongArray(v)) => v.len(),
+            NbtRef::Element(NbtList::List(v), i) => v[i].len(),
+            NbtRef::Element(NbtList::ByteArray(v), i) => v[i].len(),
+            NbtRef::Element(NbtList::IntArray(v), i) => v[i].len(),
+            NbtRef::Element(NbtList::LongArray(v), i) => v[i].len(),
+            _ => return None,
+        })
+    }
+
+    /// List 或者数组的第 `index` 个元素
+    ///
+    /// 先用 `collection_len` 检查过类型和长度
+    pub(crate) fn child(&self, index: usize) -> NbtRef<'a> {
+        match *self {
+            NbtRef::Value(NbtValue::List(l)) => NbtRef::Element(l, index),
+            NbtRef::Value(NbtValue::ByteArray(v)) => NbtRef::Byte(&v[index]),
+            NbtRef::Value(NbtValue::IntArray(v)) => NbtRef::Int(&v[index]),
+            NbtRef::Value(NbtValue::LongArray(v)) => NbtRef::Long(&v[index]),
+            NbtRef::Element(NbtList::List(v), i) => NbtRef::Element(&v[i], index),
+            NbtRef::Element(NbtList::ByteArray(v), i) => NbtRef::Byte(&v[i][index]),
+            NbtRef::Element(NbtList::IntArray(v), i) => NbtRef::Int(&v[i][index]),
+            NbtRef::Element(NbtList::LongArray(v), i) => NbtRef::Long(&v[i][index]),
+            _ => unreachable!("不是 List 或者数组"),
+        }
+    }
+}
+
+impl<'a> NbtMut<'a> {
+    pub fn tag(&self) -> NbtTypeId {
+        match self {
+            NbtMut::Value(v) => v.tag(),
+            NbtMut::Element(list, _) => list.element_tag(),
+            NbtMut::Byte(_) => 1,
+            NbtMut::Int(_) => 3,
+            NbtMut::Long(_) => 4,
+        }
+    }
+
+    /// 复制一份出来
+    ///
+    /// `Element` 的下标越界时 panic
+    pub fn to_value(&self) -> NbtValue {
+        match self {
+            NbtMut::Value(v) => (*v).clone(),
+            NbtMut::Element(list, i) => list.get(*i).expect("NbtMut 的下标越界"),
+            NbtMut::Byte(v) => NbtValue::Byte(**v),
+            NbtMut::Int(v) => NbtValue::Int(**v),
+            NbtMut::Long(v) => NbtValue::Long(**v),
+        }
+    }
+
+    /// 替换掉指向的值, 返回旧值
+    ///
+    /// List 和数组的元素只能换成同类型的值
+    pub fn set(&mut self, value: NbtValue) -> NbtResult<NbtValue> {
+        match (self, value) {
+            (NbtMut::Value(v), value) => Ok(std::mem::replace(*v, value)),
+            (NbtMut::Element(list, i), value) => list.set(*i, value),
+            (NbtMut::Byte(v), NbtValue::Byte(x)) => Ok(NbtValue::Byte(std::mem::replace(*v, x))),
+            (NbtMut::Int(v), NbtValue::Int(x)) => Ok(NbtValue::Int(std::mem::replace(*v, x))),
+            (NbtMut::Long(v), NbtValue::Long(x)) => Ok(NbtValue::Long(std::mem::replace(*v, x))),
+            (this, value) => Err(NbtError::IncorrectType(this.tag(), value.tag())),
+        }
+    }
+
+    pub fn into_compound(self) -> NbtResult<&'a mut NbtCompound> {
+        match self {
+            NbtMut::Value(NbtValue::Compound(c)) => Ok(c),
+            NbtMut::Element(NbtList::Compound(v), i) => Ok(&mut v[i]),
+            other => Err(NbtError::IncorrectType(10, other.tag())),
+        }
+    }
+
+    pub fn into_list(self) -> NbtResult<&'a mut NbtList> {
+        match self {
+            NbtMut::Value(NbtValue::List(l)) => Ok(l),
+            NbtMut::Element(NbtList::List(v), i) => Ok(&mut v[i]),
+            other => Err(NbtError::IncorrectType(9, other.tag())),
+        }
+    }
+
+    /// 和 `NbtRef::collection_len` 一样
+    pub(crate) fn collection_len(&self) -> Option<usize> {
+        match self {
+            NbtMut::Value(v) => NbtRef::Value(v).collection_len(),
+            NbtMut::Element(list, i) => NbtRef::Element(list, *i).collection_len(),
+            _ => None,
+        }
+    }
+
+    /// 和 `NbtRef::child` 一样
+    pub(crate) fn into_child(self, index: usize) -> NbtMut<'a> {
+        match self {
+            NbtMut::Value(NbtValue::List(l)) => NbtMut::Element(l, index),
+            NbtMut::Value(NbtValue::ByteArray(v)) => NbtMut::Byte(&mut v[index]),
+            NbtMut::Value(NbtValue::IntArray(v)) => NbtMut::Int(&mut v[index]),
+            NbtMut::Value(NbtValue::LongArray(v)) => NbtMut::Long(&mut v[index]),
+            NbtMut::Element(NbtList::List(v), i) => NbtMut::Element(&mut v[i], index),
+            NbtMut::Element(NbtList::ByteArray(v), i) => NbtMut::Byte(&mut v[i][index]),
+            NbtMut::Element(NbtList::IntArray(v), i) => NbtMut::Int(&mut v[i][index]),
+            NbtMut::Element(NbtList::LongArray(v), i) => NbtMut::Long(&mut v[i][index]),
+            _ => unreachable!("不是 List 或者数组"),
+        }
+    }
+
+    /// 在 List 或者数组的第 `index` 个位置插入, 元素类型要一致
+    pub(crate) fn insert_child(self, index: usize, value: NbtValue) -> NbtResult<()> {
+        let expect = match self.tag() {
+            7 => 1,
+            11 => 3,
+            12 => 4,
+            tag => tag,
+        };
+        match (self, value) {
+            (NbtMut::Value(NbtValue::List(l)), value) => return l.insert(index, value),
+            (NbtMut::Element(NbtList::List(v), i), value) => return v[i].insert(index, value),
+            (NbtMut::Value(NbtValue::ByteArray(v)), NbtValue::Byte(x)) => v.insert(index, x),
+            (NbtMut::Value(NbtValue::IntArray(v)), NbtValue::Int(x)) => v.insert(index, x),
+            (NbtMut::Value(NbtValue::LongArray(v)), NbtValue::Long(x)) => v.insert(index, x),
+            (NbtMut::Element(NbtList::ByteArray(v), i), NbtValue::Byte(x)) => v[i].insert(index, x),
+            (NbtMut::Element(NbtList::IntArray(v), i), NbtValue::Int(x)) => v[i].insert(index, x),
+            (NbtMut::Element(NbtList::LongArray(v), i), NbtValue::Long(x)) => v[i].insert(index, x),
+            (_, value) => return Err(NbtError::IncorrectType(expect, value.tag())),
+        }
+        Ok(())
+    }
+
+    /// 删掉 List 或者数组的第 `index` 个元素
+    pub(crate) fn remove_child(self, index: usize) -> NbtValue {
+        match self {
+            NbtMut::Value(NbtValue::List(l)) => l.remove(index),
+            NbtMut::Value(NbtValue::ByteArray(v)) => NbtValue::Byte(v.remove(index)),
+            NbtMut::Value(NbtValue::IntArray(v)) => NbtValue::Int(v.remove(index)),
+            NbtMut::Value(NbtValue::LongArray(v)) => NbtValue::Long(v.remove(index)),
+            NbtMut::Element(NbtList::List(v), i) => v[i].remove(index),
+            NbtMut::Element(NbtList::ByteArray(v), i) => NbtValue::Byte(v[i].remove(index)),
+            NbtMut::Element(NbtList::IntArray(v), i) => NbtValue::Int(v[i].remove(index)),
+            NbtMut::Element(NbtList::LongArray(v), i) => NbtValue::Long(v[i].remove(index)),
+            _ => unreachable!("不是 List 或者数组"),
+        }
+    }
+}
+
+/// 路径的一段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    /// 负数从末尾开始数
+    Index(i64),
+}
+
+/// 解析好的路径
+///
+/// 每一段都记下它在原字符串里结束的位置, 报错的时候用
+struct Path<'p> {
+    src: &'p str,
+    segments: Vec<(Segment, usize)>,
+}
+
+impl<'p> Path<'p> {
+    fn parse(src: &'p str) -> NbtResult<Self> {
+        let err = |pos: usize, msg: &str| {
+            NbtError::InvalidPath(format!("{:?} 第 {} 个字节: {}", src, pos, msg))
+        };
+        let mut segments = Vec::new();
+        let mut chars = src.char_indices().peekable();
+        // 开头和 `.` 后面要有 key
+        let mut need_key = !src.is_empty() && !src.starts_with('[');
+        loop {
+            if need_key {
+                let mut key = String::new();
+                let end = if let Some((_, '"')) = chars.peek() {
+                    chars.next();
+                    loop {
+                        match chars.next() {
+                            Some((i, '"')) => break i + 1,
+                            Some((i, '\\')) => match chars.next() {
+                                Some((_, c @ ('"' | '\\'))) => key.push(c),
+                                _ => return Err(err(i, "只能转义 `\"` 和 `\\`")),
+                            },
+                            Some((_, c)) => key.push(c),
+                            None => return Err(err(src.len(), "引号没有闭合")),
+                        }
+                    }
+                } else {
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c == '.' || c == '[' {
+                            break;
+                        }
+                        key.push(c);
+                        chars.next();
+                    }
+                    let end = chars.peek().map_or(src.len(), |&(i, _)| i);
+                    if key.is_empty() {
+                        return Err(err(end, "key 不能为空"));
+                    }
+                    end
+                };
+                segments.push((Segment::Key(key), end));
+                need_key = false;
+            }
+            match chars.next() {
+                None => break,
+                Some((_, '.')) => need_key = true,
+                Some((start, '[')) => {
+                    let mut num = String::new();
+                    let end = loop {
+                        match chars.next() {
+                            Some((i, ']')) => break i + 1,
+                            Some((_, c)) => num.push(c),
+                            None => return Err(err(src.len(), "`[` 没有闭合")),
+                        }
+                    };
+                    let index = num.parse::<i64>().map_err(|_| err(start, "下标必须是整数"))?;
+                    segments.push((Segment::Index(index), end));
+                }
+                Some((i, _)) => return Err(err(i, "这里应该是 `.` 或者 `[`")),
+            }
+        }
+        Ok(Self { src, segments })
+    }
+
+    /// 到第 `i` 段为止的路径
+    fn prefix(&self, i: usize) -> String { self.src[..self.segments[i].1].to_string() }
+
+    fn not_found(&self, i: usize) -> NbtError { NbtError::PathNotFound(self.prefix(i)) }
+
+    fn mismatch(&self, i: usize, expect: NbtTypeId, got: NbtTypeId) -> NbtError {
+        NbtError::PathTypeMismatch(self.prefix(i), expect, got)
+    }
+
+    /// 把可能是负数的下标换成真正的下标
+    fn resolve_index(&self, i: usize, index: i64, len: usize) -> NbtResult<usize> {
+        let real = if index < 0 { len as i64 + index } else { index };
+        if real < 0 || real >= len as i64 {
+            return Err(self.not_found(i));
+        }
+        Ok(real as usize)
+    }
+
+    fn step<'a>(&self, node: NbtRef<'a>, i: usize) -> NbtResult<NbtRef<'a>> {
+        match &self.segments[i].0 {
+            Segment::Key(key) => {
+                let compound = node.as_compound().map_err(|_| self.mismatch(i, 10, node.tag()))?;
+                compound.get(key).map(NbtRef::Value).ok_or_else(|| self.not_found(i))
+            }
+            Segment::Index(index) => {
+                let len = node.collection_len().ok_or_else(|| self.mismatch(i, 9, node.tag()))?;
+                Ok(node.child(self.resolve_index(i, *index, len)?))
+            }
+        }
+    }
+
+    fn step_mut<'a>(&self, node: NbtMut<'a>, i: usize) -> NbtResult<NbtMut<'a>> {
+        let tag = node.tag();
+        match &self.segments[i].0 {
+            Segment::Key(key) => {
+                let compound = node.into_compound().map_err(|_| self.mismatch(i, 10, tag))?;
+                compound.get_mut(key).map(NbtMut::Value).ok_or_else(|| self.not_found(i))
+            }
+            Segment::Index(index) => {
+                let len = node.collection_len().ok_or_else(|| self.mismatch(i, 9, tag))?;
+                Ok(node.into_child(self.resolve_index(i, *index, len)?))
+            }
+        }
+    }
+
+    /// 走到最后一段的父节点
+    ///
+    /// `create` 为 `true` 时, 后面跟着 key 的 key 不存在就创建一个空 Compound
+    fn walk_parent<'a>(&self, root: &'a mut NbtValue, create: bool) -> NbtResult<NbtMut<'a>> {
+        if self.segments.is_empty() {
+            return Err(NbtError::InvalidPath("空路径指向根节点, 不能替换或删除".to_string()));
+        }
+        let mut node = NbtMut::Value(root);
+        for i in 0..self.segments.len() - 1 {
+            node = match (&self.segments[i].0, &self.segments[i + 1].0) {
+                (Segment::Key(key), Segment::Key(_)) if create => {
+                    let tag = node.tag();
+                    let compound = node.into_compound().map_err(|_| self.mismatch(i, 10, tag))?;
+                    if !compound.contains_key(key) {
+                        compound.insert(key.clone(), NbtValue::Compound(NbtCompound::new()));
+                    }
+                    NbtMut::Value(compound.get_mut(key).unwrap())
+                }
+                _ => self.step_mut(node, i)?,
+            };
+        }
+        Ok(node)
+    }
+}
+
+impl NbtValue {
+    /// 按路径取值
+    ///
+    /// ```rust
+    /// use shen_nbt5::{NbtList, NbtValue};
+    ///
+    /// let value = NbtValue::Compound(
+    ///     vec![("Pos".to_string(), NbtValue::List(NbtList::Double(vec![1.0, 2.0, 3.0])))].into(),
+    /// );
+    /// assert_eq!(value.get_path("Pos[-1]").unwrap().to_value(), NbtValue::Double(3.0));
+    /// ```
+    pub fn get_path(&self, path: &str) -> NbtResult<NbtRef<'_>> {
+        let path = Path::parse(path)?;
+        let mut node = NbtRef::Value(self);
+        for i in 0..path.segments.len() {
+            node = path.step(node, i)?;
+        }
+        Ok(node)
+    }
+
+    /// 按路径取值, 可变版本
+    pub fn get_path_mut(&mut self, path: &str) -> NbtResult<NbtMut<'_>> {
+        let path = Path::parse(path)?;
+        let mut node = NbtMut::Value(self);
+        for i in 0..path.segments.len() {
+            node = path.step_mut(node, i)?;
+        }
+        Ok(node)
+    }
+
+    /// 按路径写入, 返回旧值
+    ///
+    /// 中间缺少的 Compound 会自动创建, List 的元素不会
+    ///
+    /// 最后一段是 key 时和 `NbtCompound::insert` 一样, 是下标时元素必须存在且类型一致
+    pub fn set_path(&mut self, path: &str, value: NbtValue) -> NbtResult<Option<NbtValue>> {
+        let path = Path::parse(path)?;
+        let node = path.walk_parent(self, true)?;
+        let last = path.segments.len() - 1;
+        match &path.segments[last].0 {
+            Segment::Key(key) => {
+                let tag = node.tag();
+                let compound = node.into_compound().map_err(|_| path.mismatch(last, 10, tag))?;
+                Ok(compound.insert(key.clone(), value))
+            }
+            Segment::Index(_) => path.step_mut(node, last)?.set(value).map(Some),
+        }
+    }
+
+    /// 按路径插入, 返回被替换掉的旧值
+    ///
+    /// 最后一段是 key 时和 `NbtCompound::insert` 一样; 是下标时插入到这个位置, 后面的元素往后移,
+    /// 下标可以等于长度 (加到末尾), 负数从末尾开始数, `-1` 也是加到末尾
+    ///
+    /// 和 `set_path` 不同, 中间缺少的 Compound 不会自动创建
+    ///
+    /// ```rust
+    /// use shen_nbt5::NbtValue;
+    ///
+    /// let mut value = NbtValue::from_snbt("{Tags: [a, c]}").unwrap();
+    /// value.insert_path("Tags[1]", NbtValue::String("b".to_string())).unwrap();
+    /// value.insert_path("Tags[-1]", NbtValue::String("d".to_string())).unwrap();
+    /// assert_eq!(value, NbtValue::from_snbt("{Tags: [a, b, c, d]}").unwrap());
+    /// ```
+    pub fn insert_path(&mut self, path: &str, value: NbtValue) -> NbtResult<Option<NbtValue>> {
+        let path = Path::parse(path)?;
+        let node = path.walk_parent(self, false)?;
+        let last = path.segments.len() - 1;
+        let tag = node.tag();
+        match &path.segments[last].0 {
+            Segment::Key(key) => {
+                let compound = node.into_compound().map_err(|_| path.mismatch(last, 10, tag))?;
+                Ok(compound.insert(key.clone(), value))
+            }
+            Segment::Index(index) => {
+                let len = node.collection_len().ok_or_else(|| path.mismatch(last, 9, tag))?;
+                let index = path.resolve_index(last, *index, len + 1)?;
+                node.insert_child(index, value).map_err(|e| match e {
+                    NbtError::IncorrectType(expect, got) => path.mismatch(last, expect, got),
+                    other => other,
+                })?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// 把路径里的负数下标换成真正的下标, key 统一加上需要的引号
+    ///
+    /// `insert` 为 `true` 时最后一段按 `insert_path` 的规则处理, 可以不存在
+    pub(crate) fn resolve_path(&self, path: &str, insert: bool) -> NbtResult<String> {
+        let path = Path::parse(path)?;
+        let mut out = String::new();
+        let mut node = NbtRef::Value(self);
+        for i in 0..path.segments.len() {
+            let last = i + 1 == path.segments.len();
+            match &path.segments[i].0 {
+                Segment::Key(key) => {
+                    if !out.is_empty() {
+                        out.push('.');
+                    }
+                    out.push_str(&crate::convert::path_key(key));
+                }
+                Segment::Index(index) if last && insert => {
+                    let len =
+                        node.collection_len().ok_or_else(|| path.mismatch(i, 9, node.tag()))?;
+                    let index = path.resolve_index(i, *index, len + 1)?;
+                    out.push_str(&format!("[{}]", index));
+                }
+                Segment::Index(index) => {
+                    let len =
+                        node.collection_len().ok_or_else(|| path.mismatch(i, 9, node.tag()))?;
+                    out.push_str(&format!("[{}]", path.resolve_index(i, *index, len)?));
+                }
+            }
+            if !(last && insert) {
+                node = path.step(node, i)?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// 按路径删除, 返回被删掉的值
+    pub fn remove_path(&mut self, path: &str) -> NbtResult<NbtValue> {
+        let path = Path::parse(path)?;
+        let node = path.walk_parent(self, false)?;
+        let last = path.segments.len() - 1;
+        let tag = node.tag();
+        match &path.segments[last].0 {
+            Segment::Key(key) => {
+                let compound = node.into_compound().map_err(|_| path.mismatch(last, 10, tag))?;
+                compound.remove(key).ok_or_else(|| path.not_found(last))
+            }
+            Segment::Index(index) => {
+                let len = node.collection_len().ok_or_else(|| path.mismatch(last, 9, tag))?;
+                Ok(node.remove_child(path.resolve_index(last, *index, len)?))
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::nbt_version::{BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtReadTrait};
-use crate::{nbt_version, NbtError, NbtResult, NbtValue};
+use crate::{
+    nbt_version, NbtCompound, NbtDocument, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue,
+};
 
 /// 用于读取 NBT 数据
 pub struct NbtReader<'data> {
@@ -9,12 +11,37 @@ pub struct NbtReader<'data> {
     pub cursor: usize,
 }
 
+/// 读取数组
+///
+/// 开启 `unchecked` feature 时使用不检查长度的 unsafe 版本
+///
+/// 只应该在数据完全可信的时候开启, 数据损坏会导致 UB
+macro_rules! read_array_checked {
+    ($reader:expr, $safe:ident, $unchecked:ident, $len:expr) => {{
+        #[cfg(feature = "unchecked")]
+        let value = unsafe { $reader.$unchecked($len) };
+        #[cfg(not(feature = "unchecked"))]
+        let value = $reader.$safe($len);
+        value
+    }};
+}
+
 /// Java 版 绝大部分的 NBT 格式
 ///
 /// 除了 1.20.2+(协议号 >= 764) 及以后 的网路传输 NBT 格式 都是这个
 ///
 /// 上面说的那玩意 请使用 `JavaNetAfter1_20_2`
 impl nbt_version::NbtReadTrait for nbt_version::Java {
+    #[inline]
+    fn read_i16(reader: &mut NbtReader) -> NbtResult<i16> { Ok(reader.read_be_i16()) }
+    #[inline]
+    fn read_i32(reader: &mut NbtReader) -> NbtResult<i32> { Ok(reader.read_be_i32()) }
+    #[inline]
+    fn read_i64(reader: &mut NbtReader) -> NbtResult<i64> { Ok(reader.read_be_i64()) }
+    #[inline]
+    fn read_f32(reader: &mut NbtReader) -> NbtResult<f32> { Ok(reader.read_be_f32()) }
+    #[inline]
+    fn read_f64(reader: &mut NbtReader) -> NbtResult<f64> { Ok(reader.read_be_f64()) }
     #[inline]
     fn read_nbt_string(reader: &mut NbtReader) -> NbtResult<String> {
         let len = reader.read_be_u16() as usize;
@@ -23,24 +50,24 @@ impl nbt_version::NbtReadTrait for nbt_version::Java {
     #[inline]
     fn read_i8_array(reader: &mut NbtReader) -> NbtResult<Vec<i8>> {
         let len = reader.read_be_i32() as usize;
-        let value = reader.read_i8_array(len);
+        let value = read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len);
         Ok(value)
     }
     #[inline]
     fn read_i32_array(reader: &mut NbtReader) -> NbtResult<Vec<i32>> {
         let len = reader.read_be_i32() as usize;
-        let value = reader.read_be_i32_array(len);
+        let value = read_array_checked!(reader, read_be_i32_array, read_be_i32_array_unsafe, len);
         Ok(value)
     }
     #[inline]
     fn read_i64_array(reader: &mut NbtReader) -> NbtResult<Vec<i64>> {
         let len = reader.read_be_i32() as usize;
-        let value = reader.read_be_i64_array(len);
+        let value = read_array_checked!(reader, read_be_i64_array, read_be_i64_array_unsafe, len);
         Ok(value)
     }
     #[inline]
-    fn read_compound(reader: &mut NbtReader) -> NbtResult<Vec<(String, NbtValue)>> {
-        let mut compound = Vec::with_capacity(10);
+    fn read_compound(reader: &mut NbtReader) -> NbtResult<NbtCompound> {
+        let mut compound = NbtCompound::with_capacity(10);
         loop {
             let tag_id = reader.read_u8();
             if tag_id == 0 {
@@ -57,57 +84,112 @@ impl nbt_version::NbtReadTrait for nbt_version::Java {
                 7 => NbtValue::ByteArray(Java::read_i8_array(reader)?),
                 8 => NbtValue::String(Java::read_nbt_string(reader)?),
                 9 => NbtValue::List(Java::read_list(reader)?),
-                10 => NbtValue::Compound(None, nbt_version::Java::read_compound(reader)?),
+                10 => NbtValue::Compound(nbt_version::Java::read_compound(reader)?),
                 11 => NbtValue::IntArray(Java::read_i32_array(reader)?),
                 12 => NbtValue::LongArray(Java::read_i64_array(reader)?),
                 _ => return Err(NbtError::UnknownType(tag_id)),
             };
-            compound.push((name, value));
+            compound.push(name, value);
         }
         Ok(compound)
     }
     #[inline]
-    fn read_list(reader: &mut NbtReader) -> NbtResult<Vec<NbtValue>> {
+    fn read_list(reader: &mut NbtReader) -> NbtResult<NbtList> {
         let type_id = reader.read_u8();
         let len = reader.read_be_i32() as usize;
-        let mut list = Vec::with_capacity(len);
-        for _ in 0..len {
-            let value = match type_id {
-                1 => NbtValue::Byte(reader.read_i8()),
-                2 => NbtValue::Short(reader.read_be_i16()),
-                3 => NbtValue::Int(reader.read_be_i32()),
-                4 => NbtValue::Long(reader.read_be_i64()),
-                5 => NbtValue::Float(reader.read_be_f32()),
-                6 => NbtValue::Double(reader.read_be_f64()),
-                7 => NbtValue::ByteArray(Java::read_i8_array(reader)?),
-                8 => NbtValue::String(Java::read_nbt_string(reader)?),
-                9 => NbtValue::List(Java::read_list(reader)?),
-                10 => NbtValue::Compound(None, nbt_version::Java::read_compound(reader)?),
-                11 => NbtValue::IntArray(Java::read_i32_array(reader)?),
-                12 => NbtValue::LongArray(Java::read_i64_array(reader)?),
-                _ => return Err(NbtError::UnknownType(type_id)),
-            };
-            list.push(value);
-        }
+        // 按元素类型整块读取, 空 List 也保留元素类型
+        let list = match type_id {
+            0 if len == 0 => NbtList::End,
+            1 => {
+                NbtList::Byte(read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len))
+            }
+            2 => NbtList::Short(read_array_checked!(
+                reader,
+                read_be_i16_array,
+                read_be_i16_array_unsafe,
+                len
+            )),
+            3 => NbtList::Int(read_array_checked!(
+                reader,
+                read_be_i32_array,
+                read_be_i32_array_unsafe,
+                len
+            )),
+            4 => NbtList::Long(read_array_checked!(
+                reader,
+                read_be_i64_array,
+                read_be_i64_array_unsafe,
+                len
+            )),
+            5 => NbtList::Float(
+                read_array_checked!(reader, read_be_i32_array, read_be_i32_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f32::from_bits(x as u32))
+                    .collect(),
+            ),
+            6 => NbtList::Double(
+                read_array_checked!(reader, read_be_i64_array, read_be_i64_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f64::from_bits(x as u64))
+                    .collect(),
+            ),
+            7 => NbtList::ByteArray(
+                (0..len).map(|_| Java::read_i8_array(reader)).collect::<NbtResult<_>>()?,
+            ),
+            8 => NbtList::String(
+                (0..len).map(|_| Java::read_nbt_string(reader)).collect::<NbtResult<_>>()?,
+            ),
+            9 => {
+                NbtList::List((0..len).map(|_| Java::read_list(reader)).collect::<NbtResult<_>>()?)
+            }
+            10 => NbtList::Compound(
+                (0..len).map(|_| Java::read_compound(reader)).collect::<NbtResult<_>>()?,
+            ),
+            11 => NbtList::IntArray(
+                (0..len).map(|_| Java::read_i32_array(reader)).collect::<NbtResult<_>>()?,
+            ),
+            12 => NbtList::LongArray(
+                (0..len).map(|_| Java::read_i64_array(reader)).collect::<NbtResult<_>>()?,
+            ),
+            _ => return Err(NbtError::UnknownType(type_id)),
+        };
         Ok(list)
     }
 
-    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtValue> {
+    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtDocument> {
         // 第一个 tag, 不可能是 0
         match reader.read_u8() {
             10 => {
                 let name = Java::read_nbt_string(&mut reader)?;
-                Ok(NbtValue::Compound(Some(name), nbt_version::Java::read_compound(&mut reader)?))
+                let root = NbtValue::Compound(Java::read_compound(&mut reader)?);
+                Ok(NbtDocument::with_name(name, root))
             }
             x => Err(NbtError::WrongRootType(x)),
         }
     }
+
+    fn read_root_header(reader: &mut NbtReader) -> NbtResult<(NbtTypeId, Option<String>)> {
+        match reader.read_u8() {
+            10 => Ok((10, Some(Java::read_nbt_string(reader)?))),
+            x => Err(NbtError::WrongRootType(x)),
+        }
+    }
 }
 
 /// 两个最好实现的就在这里了
 ///
 /// 网络 NBT: 1.20.2+ 的网络 NBT 根节点没有名字
 impl NbtReadTrait for JavaNetAfter1_20_2 {
+    #[inline]
+    fn read_i16(reader: &mut NbtReader) -> NbtResult<i16> { Java::read_i16(reader) }
+    #[inline]
+    fn read_i32(reader: &mut NbtReader) -> NbtResult<i32> { Java::read_i32(reader) }
+    #[inline]
+    fn read_i64(reader: &mut NbtReader) -> NbtResult<i64> { Java::read_i64(reader) }
+    #[inline]
+    fn read_f32(reader: &mut NbtReader) -> NbtResult<f32> { Java::read_f32(reader) }
+    #[inline]
+    fn read_f64(reader: &mut NbtReader) -> NbtResult<f64> { Java::read_f64(reader) }
     #[inline]
     fn read_nbt_string(reader: &mut NbtReader) -> NbtResult<String> {
         Java::read_nbt_string(reader)
@@ -123,28 +205,46 @@ impl NbtReadTrait for JavaNetAfter1_20_2 {
         Java::read_i64_array(reader)
     }
     #[inline]
-    fn read_compound(reader: &mut NbtReader) -> NbtResult<Vec<(String, NbtValue)>> {
+    fn read_compound(reader: &mut NbtReader) -> NbtResult<NbtCompound> {
         Java::read_compound(reader)
     }
     #[inline]
-    fn read_list(reader: &mut NbtReader) -> NbtResult<Vec<NbtValue>> { Java::read_list(reader) }
+    fn read_list(reader: &mut NbtReader) -> NbtResult<NbtList> { Java::read_list(reader) }
 
-    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtValue> {
+    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtDocument> {
         // 第一个 tag, 不可能是 0
         match reader.read_u8() {
             10 => {
                 // Java 1.20.2+ 的网络 NBT 没有名字
-                Ok(NbtValue::Compound(None, nbt_version::Java::read_compound(&mut reader)?))
+                Ok(NbtDocument::new(NbtValue::Compound(Java::read_compound(&mut reader)?)))
             }
             x => Err(NbtError::WrongRootType(x)),
         }
     }
+
+    fn read_root_header(reader: &mut NbtReader) -> NbtResult<(NbtTypeId, Option<String>)> {
+        match reader.read_u8() {
+            // Java 1.20.2+ 的网络 NBT 没有名字
+            10 => Ok((10, None)),
+            x => Err(NbtError::WrongRootType(x)),
+        }
+    }
 }
 
 /// 基岩版的其实也还行, 就是有点麻烦
 ///
 /// 所有都是小端
 impl NbtReadTrait for BedrockDisk {
+    #[inline]
+    fn read_i16(reader: &mut NbtReader) -> NbtResult<i16> { Ok(reader.read_le_i16()) }
+    #[inline]
+    fn read_i32(reader: &mut NbtReader) -> NbtResult<i32> { Ok(reader.read_le_i32()) }
+    #[inline]
+    fn read_i64(reader: &mut NbtReader) -> NbtResult<i64> { Ok(reader.read_le_i64()) }
+    #[inline]
+    fn read_f32(reader: &mut NbtReader) -> NbtResult<f32> { Ok(reader.read_le_f32()) }
+    #[inline]
+    fn read_f64(reader: &mut NbtReader) -> NbtResult<f64> { Ok(reader.read_le_f64()) }
     #[inline]
     fn read_nbt_string(reader: &mut NbtReader) -> NbtResult<String> {
         let len = reader.read_le_u16() as usize;
@@ -153,24 +253,24 @@ impl NbtReadTrait for BedrockDisk {
     #[inline]
     fn read_i8_array(reader: &mut NbtReader) -> NbtResult<Vec<i8>> {
         let len = reader.read_le_i32() as usize;
-        let value = reader.read_i8_array(len);
+        let value = read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len);
         Ok(value)
     }
     #[inline]
     fn read_i32_array(reader: &mut NbtReader) -> NbtResult<Vec<i32>> {
         let len = reader.read_le_i32() as usize;
-        let value = reader.read_le_i32_array(len);
+        let value = read_array_checked!(reader, read_le_i32_array, read_le_i32_array_unsafe, len);
         Ok(value)
     }
     #[inline]
     fn read_i64_array(reader: &mut NbtReader) -> NbtResult<Vec<i64>> {
         let len = reader.read_le_i32() as usize;
-        let value = reader.read_le_i64_array(len);
+        let value = read_array_checked!(reader, read_le_i64_array, read_le_i64_array_unsafe, len);
         Ok(value)
     }
     #[inline]
-    fn read_compound(reader: &mut NbtReader) -> NbtResult<Vec<(String, NbtValue)>> {
-        let mut compound = Vec::with_capacity(10);
+    fn read_compound(reader: &mut NbtReader) -> NbtResult<NbtCompound> {
+        let mut compound = NbtCompound::with_capacity(10);
         loop {
             let tag_id = reader.read_u8();
             if tag_id == 0 {
@@ -187,85 +287,145 @@ impl NbtReadTrait for BedrockDisk {
                 7 => NbtValue::ByteArray(BedrockDisk::read_i8_array(reader)?),
                 8 => NbtValue::String(BedrockDisk::read_nbt_string(reader)?),
                 9 => NbtValue::List(BedrockDisk::read_list(reader)?),
-                10 => NbtValue::Compound(None, nbt_version::BedrockDisk::read_compound(reader)?),
+                10 => NbtValue::Compound(nbt_version::BedrockDisk::read_compound(reader)?),
                 11 => NbtValue::IntArray(BedrockDisk::read_i32_array(reader)?),
                 12 => NbtValue::LongArray(BedrockDisk::read_i64_array(reader)?),
                 _ => return Err(NbtError::UnknownType(tag_id)),
             };
-            compound.push((name, value));
+            compound.push(name, value);
         }
         Ok(compound)
     }
     #[inline]
-    fn read_list(reader: &mut NbtReader) -> NbtResult<Vec<NbtValue>> {
+    fn read_list(reader: &mut NbtReader) -> NbtResult<NbtList> {
         let type_id = reader.read_u8();
         let len = reader.read_le_i32() as usize;
-        let mut list = Vec::with_capacity(len);
-        for _ in 0..len {
-            let value = match type_id {
-                1 => NbtValue::Byte(reader.read_i8()),
-                2 => NbtValue::Short(reader.read_le_i16()),
-                3 => NbtValue::Int(reader.read_le_i32()),
-                4 => NbtValue::Long(reader.read_le_i64()),
-                5 => NbtValue::Float(reader.read_le_f32()),
-                6 => NbtValue::Double(reader.read_le_f64()),
-                7 => NbtValue::ByteArray(BedrockDisk::read_i8_array(reader)?),
-                8 => NbtValue::String(BedrockDisk::read_nbt_string(reader)?),
-                9 => NbtValue::List(BedrockDisk::read_list(reader)?),
-                10 => NbtValue::Compound(None, nbt_version::BedrockDisk::read_compound(reader)?),
-                11 => NbtValue::IntArray(BedrockDisk::read_i32_array(reader)?),
-                12 => NbtValue::LongArray(BedrockDisk::read_i64_array(reader)?),
-                _ => return Err(NbtError::UnknownType(type_id)),
-            };
-            list.push(value);
-        }
+        // 按元素类型整块读取, 空 List 也保留元素类型
+        let list = match type_id {
+            0 if len == 0 => NbtList::End,
+            1 => {
+                NbtList::Byte(read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len))
+            }
+            2 => NbtList::Short(read_array_checked!(
+                reader,
+                read_le_i16_array,
+                read_le_i16_array_unsafe,
+                len
+            )),
+            3 => NbtList::Int(read_array_checked!(
+                reader,
+                read_le_i32_array,
+                read_le_i32_array_unsafe,
+                len
+            )),
+            4 => NbtList::Long(read_array_checked!(
+                reader,
+                read_le_i64_array,
+                read_le_i64_array_unsafe,
+                len
+            )),
+            5 => NbtList::Float(
+                read_array_checked!(reader, read_le_i32_array, read_le_i32_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f32::from_bits(x as u32))
+                    .collect(),
+            ),
+            6 => NbtList::Double(
+                read_array_checked!(reader, read_le_i64_array, read_le_i64_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f64::from_bits(x as u64))
+                    .collect(),
+            ),
+            7 => NbtList::ByteArray(
+                (0..len).map(|_| BedrockDisk::read_i8_array(reader)).collect::<NbtResult<_>>()?,
+            ),
+            8 => NbtList::String(
+                (0..len)
+                    .map(|_| BedrockDisk::read_nbt_string(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            9 => NbtList::List(
+                (0..len).map(|_| BedrockDisk::read_list(reader)).collect::<NbtResult<_>>()?,
+            ),
+            10 => NbtList::Compound(
+                (0..len).map(|_| BedrockDisk::read_compound(reader)).collect::<NbtResult<_>>()?,
+            ),
+            11 => NbtList::IntArray(
+                (0..len)
+                    .map(|_| BedrockDisk::read_i32_array(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            12 => NbtList::LongArray(
+                (0..len)
+                    .map(|_| BedrockDisk::read_i64_array(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            _ => return Err(NbtError::UnknownType(type_id)),
+        };
         Ok(list)
     }
 
-    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtValue> {
+    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtDocument> {
         // 第一个 tag, 不可能是 0
         match reader.read_u8() {
             9 => {
                 // 基岩版的 NBT 根节点可以是一个 List
-                Ok(NbtValue::List(nbt_version::BedrockDisk::read_list(&mut reader)?))
+                Ok(NbtDocument::new(NbtValue::List(BedrockDisk::read_list(&mut reader)?)))
             }
             10 => {
                 // 或者一个有名字的 Compound
                 let name = BedrockDisk::read_nbt_string(&mut reader)?;
-                Ok(NbtValue::Compound(
-                    Some(name),
-                    nbt_version::BedrockDisk::read_compound(&mut reader)?,
-                ))
+                let root = NbtValue::Compound(BedrockDisk::read_compound(&mut reader)?);
+                Ok(NbtDocument::with_name(name, root))
             }
             // 别的不行
             x => Err(NbtError::WrongRootType(x)),
         }
     }
+
+    fn read_root_header(reader: &mut NbtReader) -> NbtResult<(NbtTypeId, Option<String>)> {
+        match reader.read_u8() {
+            // 基岩版的 NBT 根节点可以是一个 List
+            9 => Ok((9, None)),
+            10 => Ok((10, Some(BedrockDisk::read_nbt_string(reader)?))),
+            x => Err(NbtError::WrongRootType(x)),
+        }
+    }
 }
 
 /// 最痛苦的来了
 impl NbtReadTrait for BedrockNetVarInt {
+    #[inline]
+    fn read_i16(reader: &mut NbtReader) -> NbtResult<i16> { Ok(reader.read_le_i16()) }
+    #[inline]
+    fn read_i32(reader: &mut NbtReader) -> NbtResult<i32> { reader.read_zigzag_var_i32() }
+    #[inline]
+    fn read_i64(reader: &mut NbtReader) -> NbtResult<i64> { reader.read_zigzag_var_i64() }
+    #[inline]
+    fn read_f32(reader: &mut NbtReader) -> NbtResult<f32> { Ok(reader.read_le_f32()) }
+    #[inline]
+    fn read_f64(reader: &mut NbtReader) -> NbtResult<f64> { Ok(reader.read_le_f64()) }
     fn read_nbt_string(reader: &mut NbtReader) -> NbtResult<String> {
         let len = reader.read_var_i32()? as usize;
         reader.read_string(len)
     }
     fn read_i8_array(reader: &mut NbtReader) -> NbtResult<Vec<i8>> {
         let len = reader.read_zigzag_var_i32()? as usize;
-        let value = reader.read_i8_array(len);
+        let value = read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len);
         Ok(value)
     }
     fn read_i32_array(reader: &mut NbtReader) -> NbtResult<Vec<i32>> {
         let len = reader.read_zigzag_var_i32()? as usize;
-        let value = reader.read_le_i32_array(len);
+        let value = read_array_checked!(reader, read_le_i32_array, read_le_i32_array_unsafe, len);
         Ok(value)
     }
     fn read_i64_array(reader: &mut NbtReader) -> NbtResult<Vec<i64>> {
         let len = reader.read_zigzag_var_i32()? as usize;
-        let value = reader.read_le_i64_array(len);
+        let value = read_array_checked!(reader, read_le_i64_array, read_le_i64_array_unsafe, len);
         Ok(value)
     }
-    fn read_compound(reader: &mut NbtReader) -> NbtResult<Vec<(String, NbtValue)>> {
-        let mut compound = Vec::with_capacity(10);
+    fn read_compound(reader: &mut NbtReader) -> NbtResult<NbtCompound> {
+        let mut compound = NbtCompound::with_capacity(10);
         loop {
             let tag_id = reader.read_u8();
             if tag_id == 0 {
@@ -282,54 +442,107 @@ impl NbtReadTrait for BedrockNetVarInt {
                 7 => NbtValue::ByteArray(BedrockNetVarInt::read_i8_array(reader)?),
                 8 => NbtValue::String(BedrockNetVarInt::read_nbt_string(reader)?),
                 9 => NbtValue::List(BedrockNetVarInt::read_list(reader)?),
-                10 => NbtValue::Compound(None, BedrockNetVarInt::read_compound(reader)?),
+                10 => NbtValue::Compound(BedrockNetVarInt::read_compound(reader)?),
                 11 => NbtValue::IntArray(BedrockNetVarInt::read_i32_array(reader)?),
                 12 => NbtValue::LongArray(BedrockNetVarInt::read_i64_array(reader)?),
                 _ => return Err(NbtError::UnknownType(tag_id)),
             };
-            compound.push((name, value));
+            compound.push(name, value);
         }
         Ok(compound)
     }
-    fn read_list(reader: &mut NbtReader) -> NbtResult<Vec<NbtValue>> {
+    fn read_list(reader: &mut NbtReader) -> NbtResult<NbtList> {
         let type_id = reader.read_u8();
         let len = reader.read_zigzag_var_i32()? as usize;
-        let mut list = Vec::with_capacity(len);
-        for _ in 0..len {
-            let value = match type_id {
-                1 => NbtValue::Byte(reader.read_i8()),
-                2 => NbtValue::Short(reader.read_le_i16()),
-                3 => NbtValue::Int(reader.read_zigzag_var_i32()?),
-                4 => NbtValue::Long(reader.read_zigzag_var_i64()?),
-                5 => NbtValue::Float(reader.read_le_f32()),
-                6 => NbtValue::Double(reader.read_le_f64()),
-                7 => NbtValue::ByteArray(BedrockNetVarInt::read_i8_array(reader)?),
-                8 => NbtValue::String(BedrockNetVarInt::read_nbt_string(reader)?),
-                9 => NbtValue::List(BedrockNetVarInt::read_list(reader)?),
-                10 => NbtValue::Compound(None, BedrockNetVarInt::read_compound(reader)?),
-                11 => NbtValue::IntArray(BedrockNetVarInt::read_i32_array(reader)?),
-                12 => NbtValue::LongArray(BedrockNetVarInt::read_i64_array(reader)?),
-                _ => return Err(NbtError::UnknownType(type_id)),
-            };
-            list.push(value);
-        }
+        // 按元素类型整块读取, 空 List 也保留元素类型
+        let list = match type_id {
+            0 if len == 0 => NbtList::End,
+            1 => {
+                NbtList::Byte(read_array_checked!(reader, read_i8_array, read_i8_array_unsafe, len))
+            }
+            2 => NbtList::Short(read_array_checked!(
+                reader,
+                read_le_i16_array,
+                read_le_i16_array_unsafe,
+                len
+            )),
+            3 => NbtList::Int(
+                (0..len).map(|_| reader.read_zigzag_var_i32()).collect::<NbtResult<_>>()?,
+            ),
+            4 => NbtList::Long(
+                (0..len).map(|_| reader.read_zigzag_var_i64()).collect::<NbtResult<_>>()?,
+            ),
+            5 => NbtList::Float(
+                read_array_checked!(reader, read_le_i32_array, read_le_i32_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f32::from_bits(x as u32))
+                    .collect(),
+            ),
+            6 => NbtList::Double(
+                read_array_checked!(reader, read_le_i64_array, read_le_i64_array_unsafe, len)
+                    .into_iter()
+                    .map(|x| f64::from_bits(x as u64))
+                    .collect(),
+            ),
+            7 => NbtList::ByteArray(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_i8_array(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            8 => NbtList::String(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_nbt_string(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            9 => NbtList::List(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_list(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            10 => NbtList::Compound(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_compound(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            11 => NbtList::IntArray(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_i32_array(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            12 => NbtList::LongArray(
+                (0..len)
+                    .map(|_| BedrockNetVarInt::read_i64_array(reader))
+                    .collect::<NbtResult<_>>()?,
+            ),
+            _ => return Err(NbtError::UnknownType(type_id)),
+        };
         Ok(list)
     }
-    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtValue> {
+    fn from_reader(mut reader: NbtReader) -> NbtResult<NbtDocument> {
         match reader.read_u8() {
             9 => {
                 // 基岩版的 NBT 根节点可以是一个 List
-                Ok(NbtValue::List(BedrockNetVarInt::read_list(&mut reader)?))
+                Ok(NbtDocument::new(NbtValue::List(BedrockNetVarInt::read_list(&mut reader)?)))
             }
             10 => {
                 // 或者一个有名字的 Compound
                 let name = BedrockNetVarInt::read_nbt_string(&mut reader)?;
-                Ok(NbtValue::Compound(Some(name), BedrockNetVarInt::read_compound(&mut reader)?))
+                let root = NbtValue::Compound(BedrockNetVarInt::read_compound(&mut reader)?);
+                Ok(NbtDocument::with_name(name, root))
             }
             // 别的不行
             x => Err(NbtError::WrongRootType(x)),
         }
     }
+
+    fn read_root_header(reader: &mut NbtReader) -> NbtResult<(NbtTypeId, Option<String>)> {
+        match reader.read_u8() {
+            // 基岩版的 NBT 根节点可以是一个 List
+            9 => Ok((9, None)),
+            10 => Ok((10, Some(BedrockNetVarInt::read_nbt_string(reader)?))),
+            x => Err(NbtError::WrongRootType(x)),
+        }
+    }
 }
 
 macro_rules! read_uncheck {
@@ -338,7 +551,7 @@ macro_rules! read_uncheck {
         ///
         /// 转换大小端(大端)
         ///
-        /// # 安全性
+        /// # Safety
         /// 允许未对齐的地址
         /// 长度溢出会导致 UB
         #[inline]
@@ -354,7 +567,7 @@ macro_rules! read_uncheck {
         ///
         /// 转换大小端(小端)
         ///
-        /// # 安全性
+        /// # Safety
         /// 允许未对齐的地址
         /// 长度溢出会导致 UB
         #[inline]
@@ -368,6 +581,41 @@ macro_rules! read_uncheck {
     };
 }
 
+/// 批量读取数组
+///
+/// 每次处理 `LANES` 个元素, 定长的数组让编译器可以直接生成 SIMD 的字节序转换
+///
+/// 全程按字节读取, 不要求对齐
+macro_rules! read_array {
+    ($name:ident, $ty:ty, $size:literal, $from_bytes:ident) => {
+        #[doc = concat!("读取指定长度的 ", stringify!($ty), " 数组 (", stringify!($from_bytes), ")")]
+        ///
+        /// # Panics
+        ///
+        /// 剩下的数据不够 `len` 个元素时 panic, 不会越界读取
+        #[inline]
+        pub fn $name(&mut self, len: usize) -> Vec<$ty> {
+            const LANES: usize = 8;
+            let bytes = &self.data[self.cursor..self.cursor + len * $size];
+            let mut value: Vec<$ty> = vec![0; len];
+            let mut dst = value.chunks_exact_mut(LANES);
+            let mut src = bytes.chunks_exact(LANES * $size);
+            for (dst, src) in (&mut dst).zip(&mut src) {
+                for (n, bytes) in dst.iter_mut().zip(src.chunks_exact($size)) {
+                    *n = <$ty>::$from_bytes(bytes.try_into().unwrap());
+                }
+            }
+            for (n, bytes) in
+                dst.into_remainder().iter_mut().zip(src.remainder().chunks_exact($size))
+            {
+                *n = <$ty>::$from_bytes(bytes.try_into().unwrap());
+            }
+            self.cursor += len * $size;
+            value
+        }
+    };
+}
+
 impl NbtReader<'_> {
     pub fn new(data: &mut [u8]) -> NbtReader { NbtReader { data, cursor: 0 } }
     /// 向后滚动
@@ -492,7 +740,8 @@ impl NbtReader<'_> {
     #[inline]
     pub fn read_zigzag_var_i32(&mut self) -> NbtResult<i32> {
         let value = self.read_var_i32()?;
-        Ok((value >> 1) ^ (-(value & 1)))
+        // 逻辑右移, 不然最高位会被带进来
+        Ok(((value as u32 >> 1) as i32) ^ (-(value & 1)))
     }
     /// 安全的读取一个 zigzag 编码的 varlong
     ///
@@ -500,7 +749,7 @@ impl NbtReader<'_> {
     #[inline]
     pub fn read_zigzag_var_i64(&mut self) -> NbtResult<i64> {
         let value = self.read_var_i64()?;
-        Ok((value >> 1) ^ (-(value & 1)))
+        Ok(((value as u64 >> 1) as i64) ^ (-(value & 1)))
     }
     /// 安全的读取一个小端 i32 数据
     ///
@@ -650,7 +899,7 @@ impl NbtReader<'_> {
     pub fn read_le_f64(&mut self) -> f64 { f64::from_bits(self.read_le_u64()) }
     /// 读取一个大端 f32 数据
     ///
-    /// # 安全性
+    /// # Safety
     /// 允许未对齐的地址
     /// 长度溢出会导致 UB
     #[inline]
@@ -660,7 +909,7 @@ impl NbtReader<'_> {
     }
     /// 读取一个小端 f32 数据
     ///
-    /// # 安全性
+    /// # Safety
     /// 允许未对齐的地址
     /// 长度溢出会导致 UB
     #[inline]
@@ -670,7 +919,7 @@ impl NbtReader<'_> {
     }
     /// 读取一个大端 f64 数据
     ///
-    /// # 安全性
+    /// # Safety
     /// 允许未对齐的地址
     /// 长度溢出会导致 UB
     #[inline]
@@ -680,7 +929,7 @@ impl NbtReader<'_> {
     }
     /// 读取一个小端 f64 数据
     ///
-    /// # 安全性
+    /// # Safety
     /// 允许未对齐的地址
     /// 长度溢出会导致 UB
     #[inline]
@@ -701,7 +950,7 @@ impl NbtReader<'_> {
     }
     /// 读取指定长度的 i8 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
     /// 长度溢出会导致 UB
     #[inline]
@@ -723,7 +972,7 @@ impl NbtReader<'_> {
     }
     /// 读取指定长度的 i16 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
     /// 长度溢出会导致 UB
     #[inline]
@@ -731,7 +980,7 @@ impl NbtReader<'_> {
         let mut value: Vec<i16> = Vec::with_capacity(len);
         std::ptr::copy_nonoverlapping(
             self.data[self.cursor..].as_ptr() as *const u8,
-            value.as_ptr() as *mut u8,
+            value.as_mut_ptr() as *mut u8,
             len * 2,
         );
         value.set_len(len);
@@ -743,7 +992,7 @@ impl NbtReader<'_> {
     }
     /// 读取指定长度的 i32 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
     /// 长度溢出会导致 UB
     #[inline]
@@ -751,7 +1000,7 @@ impl NbtReader<'_> {
         let mut value: Vec<i32> = Vec::with_capacity(len);
         std::ptr::copy_nonoverlapping(
             self.data[self.cursor..].as_ptr() as *const u8,
-            value.as_ptr() as *mut u8,
+            value.as_mut_ptr() as *mut u8,
             len * 4,
         );
         value.set_len(len);
@@ -763,7 +1012,7 @@ impl NbtReader<'_> {
     }
     /// 读取指定长度的 i64 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
     /// 长度溢出会导致 UB
     #[inline]
@@ -771,7 +1020,7 @@ impl NbtReader<'_> {
         let mut value: Vec<i64> = Vec::with_capacity(len);
         std::ptr::copy_nonoverlapping(
             self.data[self.cursor..].as_ptr() as *const u8,
-            value.as_ptr() as *mut u8,
+            value.as_mut_ptr() as *mut u8,
             len * 8,
         );
         value.set_len(len);
@@ -781,73 +1030,69 @@ impl NbtReader<'_> {
         self.cursor += len * 8;
         value
     }
-    /// 读取指定长度的 i32 数组
-    ///
-    /// # 安全性
-    ///
-    /// 长度溢出会导致 panic
-    #[inline]
-    pub fn read_be_i32_array(&mut self, len: usize) -> Vec<i32> {
-        let value = self.data[self.cursor..self.cursor + len * 4]
-            .chunks_exact(4)
-            .map(|n| i32::from_be_bytes(n[0..4].try_into().unwrap()))
-            .collect();
-        self.cursor += len * 4;
-        value
-    }
-    /// 读取指定长度的 i64 数组
-    ///
-    /// # 安全性
-    ///
-    /// 长度溢出会导致 panic
-    #[inline]
-    pub fn read_be_i64_array(&mut self, len: usize) -> Vec<i64> {
-        let value = self.data[self.cursor..self.cursor + len * 8]
-            .chunks_exact(8)
-            .map(|n| i64::from_be_bytes(n[0..8].try_into().unwrap()))
-            .collect();
-        self.cursor += len * 8;
-        value
-    }
+    read_array!(read_be_i16_array, i16, 2, from_be_bytes);
+    read_array!(read_be_i32_array, i32, 4, from_be_bytes);
+    read_array!(read_be_i64_array, i64, 8, from_be_bytes);
+    read_array!(read_le_i16_array, i16, 2, from_le_bytes);
+    read_array!(read_le_i32_array, i32, 4, from_le_bytes);
+    read_array!(read_le_i64_array, i64, 8, from_le_bytes);
     /// 读取指定长度的 le i16 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
-    /// 长度溢出会导致 panic
+    /// 长度溢出会导致 UB
     #[inline]
-    pub fn read_le_i16_array(&mut self, len: usize) -> Vec<i16> {
-        let value = self.data[self.cursor..self.cursor + len * 2]
-            .chunks_exact(2)
-            .map(|n| i16::from_le_bytes(n[0..2].try_into().unwrap()))
-            .collect();
+    pub unsafe fn read_le_i16_array_unsafe(&mut self, len: usize) -> Vec<i16> {
+        let mut value: Vec<i16> = Vec::with_capacity(len);
+        std::ptr::copy_nonoverlapping(
+            self.data[self.cursor..].as_ptr(),
+            value.as_mut_ptr() as *mut u8,
+            len * 2,
+        );
+        value.set_len(len);
+        for n in &mut value {
+            *n = n.to_le();
+        }
         self.cursor += len * 2;
         value
     }
     /// 读取指定长度的 le i32 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
-    /// 长度溢出会导致 panic
+    /// 长度溢出会导致 UB
     #[inline]
-    pub fn read_le_i32_array(&mut self, len: usize) -> Vec<i32> {
-        let value = self.data[self.cursor..self.cursor + len * 4]
-            .chunks_exact(4)
-            .map(|n| i32::from_le_bytes(n[0..4].try_into().unwrap()))
-            .collect();
+    pub unsafe fn read_le_i32_array_unsafe(&mut self, len: usize) -> Vec<i32> {
+        let mut value: Vec<i32> = Vec::with_capacity(len);
+        std::ptr::copy_nonoverlapping(
+            self.data[self.cursor..].as_ptr(),
+            value.as_mut_ptr() as *mut u8,
+            len * 4,
+        );
+        value.set_len(len);
+        for n in &mut value {
+            *n = n.to_le();
+        }
         self.cursor += len * 4;
         value
     }
     /// 读取指定长度的 le i64 数组
     ///
-    /// # 安全性
+    /// # Safety
     ///
-    /// 长度溢出会导致 panic
+    /// 长度溢出会导致 UB
     #[inline]
-    pub fn read_le_i64_array(&mut self, len: usize) -> Vec<i64> {
-        let value = self.data[self.cursor..self.cursor + len * 8]
-            .chunks_exact(8)
-            .map(|n| i64::from_le_bytes(n[0..8].try_into().unwrap()))
-            .collect();
+    pub unsafe fn read_le_i64_array_unsafe(&mut self, len: usize) -> Vec<i64> {
+        let mut value: Vec<i64> = Vec::with_capacity(len);
+        std::ptr::copy_nonoverlapping(
+            self.data[self.cursor..].as_ptr(),
+            value.as_mut_ptr() as *mut u8,
+            len * 8,
+        );
+        value.set_len(len);
+        for n in &mut value {
+            *n = n.to_le();
+        }
         self.cursor += len * 8;
         value
     }
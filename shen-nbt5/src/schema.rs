@@ -0,0 +1,550 @@
+//! NBT 结构描述和校验
+//!
+//! 可以在 Rust 里拼出来:
+//!
+//! ```rust
+//! use shen_nbt5::{NbtSchema, NbtValue, ViolationKind};
+//!
+//! let schema = NbtSchema::compound()
+//!     .field("Health", NbtSchema::float().range(0.0, 20.0))
+//!     .field("Inventory", NbtSchema::list(NbtSchema::compound().field("Slot", NbtSchema::byte()).open()))
+//!     .optional("GameMode", NbtSchema::string().one_of(["survival", "creative"]));
+//!
+//! let player = NbtValue::from_snbt("{Health: 25f, Inventory: [{Slot: 1}], Motion: [0d, 0d, 0d]}").unwrap();
+//! let violations = schema.validate(&player);
+//! assert_eq!(violations.len(), 3);
+//! assert_eq!(violations[1].path, "Inventory[0].Slot");
+//! assert_eq!(violations[2].kind, ViolationKind::Unexpected);
+//! assert_eq!(
+//!     violations.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+//!     [
+//!         "Health: 25 超出范围 0..=20",
+//!         "Inventory[0].Slot: 类型应该是 byte, 实际是 int",
+//!         "Motion: 不允许的 key",
+//!     ]
+//! );
+//! ```
+//!
+//! 也可以从 SNBT (或者开启 `json` feature 之后从 JSON) 读进来, 格式是一个 Compound:
+//! - `type`: 类型名 (`byte`, `int_array`, `compound` ...), 不写或者 `any` 是任意类型
+//! - `min` / `max`: 数值范围, 包含两端, 数组是每个元素; 整数之间按整数比较, 不会丢精度
+//! - `values`: String 只能是其中之一
+//! - `elements`: List 的元素
+//! - `fields`: Compound 的字段, 字段里写 `optional: true` 是可选的
+//! - `open`: `type` 是 `compound` 的时候是否允许 `fields` 里没有的 key, 默认不允许
+//!
+//! 只有类型的时候可以直接写类型名, `"int"` 和 `{type: int}` 一样
+//!
+//! ```rust
+//! use shen_nbt5::NbtSchema;
+//!
+//! let schema = NbtSchema::from_snbt(
+//!     "{type: compound, fields: {id: string, Count: {type: byte, min: 1, max: 64}, \
+//!      tag: {type: compound, open: true, optional: true}}}",
+//! )
+//! .unwrap();
+//! assert!(schema.is_valid(&"{id: stone, Count: 1b}".parse().unwrap()));
+//! assert!(!schema.is_valid(&"{id: stone, Count: 65b}".parse().unwrap()));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::convert::{path_key, FromNbt, ToNbt};
+use crate::{type_id, NbtCompound, NbtError, NbtResult, NbtTypeId, NbtValue, TYPE_NAMES};
+
+/// 一个值应该长什么样
+///
+/// 各项检查只对对应的类型生效, 比如 `values` 只管 String; 类型不对的时候不再往下检查
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NbtSchema {
+    /// 要求的类型, `None` 是任意类型
+    pub tag: Option<NbtTypeId>,
+    /// 数值的最小值 (包含), 数组是每个元素
+    pub min: Option<SchemaNumber>,
+    /// 数值的最大值 (包含)
+    pub max: Option<SchemaNumber>,
+    /// String 只能是其中之一
+    pub values: Option<Vec<String>>,
+    /// List 元素的要求, `None` 是不检查
+    pub elements: Option<Box<NbtSchema>>,
+    /// Compound 的字段
+    pub fields: Vec<SchemaField>,
+    /// Compound 是否允许 `fields` 里没有的 key, 只对类型是 Compound 的 schema 生效
+    pub open: bool,
+}
+
+/// 范围的边界和被检查的数值
+///
+/// 整数 (Byte 到 Long 和整数数组) 是 `Int`, 和整数边界直接比较;
+/// 整数和浮点数之间也是精确比较, 不会先转成 `f64` 丢掉 2^53 以上的精度
+///
+/// 相等按数值算, `Int(1) == Float(1.0)`
+#[derive(Debug, Clone, Copy)]
+pub enum SchemaNumber {
+    Int(i64),
+    Float(f64),
+}
+
+impl PartialEq for SchemaNumber {
+    fn eq(&self, other: &Self) -> bool { self.partial_cmp(other) == Some(Ordering::Equal) }
+}
+
+impl PartialOrd for SchemaNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (*self, *other) {
+            (SchemaNumber::Int(a), SchemaNumber::Int(b)) => Some(a.cmp(&b)),
+            (SchemaNumber::Float(a), SchemaNumber::Float(b)) => a.partial_cmp(&b),
+            (SchemaNumber::Int(a), SchemaNumber::Float(b)) => int_cmp_float(a, b),
+            (SchemaNumber::Float(a), SchemaNumber::Int(b)) => {
+                int_cmp_float(b, a).map(Ordering::reverse)
+            }
+        }
+    }
+}
+
+/// 精确比较整数和浮点数, NaN 返回 `None`
+fn int_cmp_float(int: i64, float: f64) -> Option<Ordering> {
+    // 2^63, 超出 i64 的范围
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() {
+        return None;
+    }
+    if float >= LIMIT {
+        return Some(Ordering::Less);
+    }
+    if float < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+    // 在 i64 范围内, 整数部分可以精确转换, 相等时再看小数部分
+    let whole = float.trunc();
+    Some(int.cmp(&(whole as i64)).then(0.0_f64.partial_cmp(&(float - whole))?))
+}
+
+impl fmt::Display for SchemaNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaNumber::Int(x) => write!(f, "{}", x),
+            SchemaNumber::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+macro_rules! schema_number_from {
+    ($variant:ident, $target:ty: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for SchemaNumber {
+                fn from(value: $ty) -> Self { SchemaNumber::$variant(value as $target) }
+            }
+        )*
+    };
+}
+
+schema_number_from!(Int, i64: i8, i16, i32, i64, u8, u16, u32);
+schema_number_from!(Float, f64: f32, f64);
+
+/// Compound 里的一个字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    pub key: String,
+    pub schema: NbtSchema,
+    /// 必须存在
+    pub required: bool,
+}
+
+/// 不符合要求的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    /// 类型不对
+    TypeMismatch {
+        expected: NbtTypeId,
+        found: NbtTypeId,
+    },
+    /// 缺少必需的 key
+    Missing,
+    /// 不允许的 key
+    Unexpected,
+    /// 数值超出范围
+    OutOfRange {
+        value: SchemaNumber,
+        min: Option<SchemaNumber>,
+        max: Option<SchemaNumber>,
+    },
+    /// String 不是允许的值
+    NotAllowed(String),
+}
+
+/// 一处不符合要求的地方和它的路径
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    /// [`NbtValue::get_path`] 的格式, 空路径是根节点
+    pub path: String,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "<root>"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: ", path)?;
+        match &self.kind {
+            ViolationKind::TypeMismatch { expected, found } => write!(
+                f,
+                "类型应该是 {}, 实际是 {}",
+                TYPE_NAMES[*expected as usize], TYPE_NAMES[*found as usize]
+            ),
+            ViolationKind::Missing => f.write_str("缺少必需的 key"),
+            ViolationKind::Unexpected => f.write_str("不允许的 key"),
+            ViolationKind::OutOfRange { value, min, max } => {
+                write!(f, "{} 超出范围 ", value)?;
+                if let Some(min) = min {
+                    write!(f, "{}", min)?;
+                }
+                f.write_str("..=")?;
+                if let Some(max) = max {
+                    write!(f, "{}", max)?;
+                }
+                Ok(())
+            }
+            ViolationKind::NotAllowed(value) => write!(f, "{:?} 不是允许的值", value),
+        }
+    }
+}
+
+/// 只要求类型的构造函数
+macro_rules! typed {
+    ($($name:ident => $tag:literal),* $(,)?) => {
+        $(
+            pub fn $name() -> Self { Self::of($tag) }
+        )*
+    };
+}
+
+impl NbtSchema {
+    /// 任意值
+    pub fn any() -> Self { Self::default() }
+
+    /// 只要求类型
+    pub fn of(tag: NbtTypeId) -> Self {
+        Self {
+            tag: Some(tag),
+            ..Self::default()
+        }
+    }
+
+    typed! {
+        byte => 1,
+        short => 2,
+        int => 3,
+        long => 4,
+        float => 5,
+        double => 6,
+        byte_array => 7,
+        string => 8,
+        compound => 10,
+        int_array => 11,
+        long_array => 12,
+    }
+
+    /// 元素都符合 `elements` 的 List
+    pub fn list(elements: NbtSchema) -> Self {
+        Self {
+            elements: Some(Box::new(elements)),
+            ..Self::of(9)
+        }
+    }
+
+    pub fn min(mut self, min: impl Into<SchemaNumber>) -> Self {
+        self.min = Some(min.into());
+        self
+    }
+
+    pub fn max(mut self, max: impl Into<SchemaNumber>) -> Self {
+        self.max = Some(max.into());
+        self
+    }
+
+    /// 包含两端
+    pub fn range(self, min: impl Into<SchemaNumber>, max: impl Into<SchemaNumber>) -> Self {
+        self.min(min).max(max)
+    }
+
+    /// String 只能是这些值
+    pub fn one_of<S: Into<String>>(mut self, values: impl IntoIterator<Item = S>) -> Self {
+        self.values = Some(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 加一个必需的字段
+    pub fn field(mut self, key: impl Into<String>, schema: NbtSchema) -> Self {
+        self.fields.push(SchemaField {
+            key: key.into(),
+            schema,
+            required: true,
+        });
+        self
+    }
+
+    /// 加一个可选的字段
+    pub fn optional(mut self, key: impl Into<String>, schema: NbtSchema) -> Self {
+        self.fields.push(SchemaField {
+            key: key.into(),
+            schema,
+            required: false,
+        });
+        self
+    }
+
+    /// 允许 `fields` 里没有的 key
+    pub fn open(mut self) -> Self {
+        self.open = true;
+        self
+    }
+
+    pub fn from_snbt(src: &str) -> NbtResult<Self> { Self::from_nbt(NbtValue::from_snbt(src)?) }
+
+    /// 从普通的 JSON 读取, 格式和 SNBT 一样
+    #[cfg(feature = "json")]
+    pub fn from_json(value: &serde_json::Value) -> NbtResult<Self> {
+        Self::from_nbt(NbtValue::from_json(value)?)
+    }
+
+    pub fn to_snbt(&self) -> String {
+        self.to_nbt().expect("schema 总是能转换成 NBT").to_snbt()
+    }
+
+    /// 检查 `value`, 返回所有不符合要求的地方
+    pub fn validate(&self, value: &NbtValue) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        self.check(String::new(), value, &mut violations);
+        violations
+    }
+
+    pub fn is_valid(&self, value: &NbtValue) -> bool { self.validate(value).is_empty() }
+
+    fn check(&self, path: String, value: &NbtValue, out: &mut Vec<SchemaViolation>) {
+        if let Some(expected) = self.tag {
+            if value.tag() != expected {
+                let kind = ViolationKind::TypeMismatch {
+                    expected,
+                    found: value.tag(),
+                };
+                out.push(SchemaViolation { path, kind });
+                return;
+            }
+        }
+        let index = |i: usize| format!("{}[{}]", path, i);
+        match value {
+            NbtValue::Byte(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::Short(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::Int(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::Long(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::Float(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::Double(x) => self.check_range(path, (*x).into(), out),
+            NbtValue::ByteArray(x) => x
+                .iter()
+                .enumerate()
+                .for_each(|(i, v)| self.check_range(index(i), (*v).into(), out)),
+            NbtValue::IntArray(x) => x
+                .iter()
+                .enumerate()
+                .for_each(|(i, v)| self.check_range(index(i), (*v).into(), out)),
+            NbtValue::LongArray(x) => x
+                .iter()
+                .enumerate()
+                .for_each(|(i, v)| self.check_range(index(i), (*v).into(), out)),
+            NbtValue::String(x) => {
+                if self.values.as_ref().is_some_and(|values| !values.contains(x)) {
+                    out.push(SchemaViolation {
+                        path,
+                        kind: ViolationKind::NotAllowed(x.clone()),
+                    });
+                }
+            }
+            NbtValue::List(list) => {
+                if let Some(elements) = &self.elements {
+                    for (i, element) in list.to_values().iter().enumerate() {
+                        elements.check(index(i), element, out);
+                    }
+                }
+            }
+            NbtValue::Compound(compound) => self.check_compound(&path, compound, out),
+        }
+    }
+
+    fn check_range(&self, path: String, value: SchemaNumber, out: &mut Vec<SchemaViolation>) {
+        let too_small = self.min.is_some_and(|min| value < min);
+        let too_large = self.max.is_some_and(|max| value > max);
+        // 有范围的时候 NaN 总是超出范围
+        let nan = matches!(value, SchemaNumber::Float(x) if x.is_nan())
+            && (self.min.is_some() || self.max.is_some());
+        if too_small || too_large || nan {
+            let kind = ViolationKind::OutOfRange {
+                value,
+                min: self.min,
+                max: self.max,
+            };
+            out.push(SchemaViolation { path, kind });
+        }
+    }
+
+    fn check_compound(&self, path: &str, compound: &NbtCompound, out: &mut Vec<SchemaViolation>) {
+        let child = |key: &str| match path {
+            "" => path_key(key),
+            _ => format!("{}.{}", path, path_key(key)),
+        };
+        for field in &self.fields {
+            match compound.get(&field.key) {
+                Some(value) => field.schema.check(child(&field.key), value, out),
+                None if field.required => out.push(SchemaViolation {
+                    path: child(&field.key),
+                    kind: ViolationKind::Missing,
+                }),
+                None => {}
+            }
+        }
+        if self.tag == Some(10) && !self.open {
+            for key in compound.keys() {
+                if !self.fields.iter().any(|field| field.key == *key) {
+                    out.push(SchemaViolation {
+                        path: child(key),
+                        kind: ViolationKind::Unexpected,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// schema 里的数字, 任何数值类型都可以, 整数类型读成 `Int`
+fn number(key: &str, value: NbtValue) -> NbtResult<SchemaNumber> {
+    match value {
+        NbtValue::Byte(x) => Ok(x.into()),
+        NbtValue::Short(x) => Ok(x.into()),
+        NbtValue::Int(x) => Ok(x.into()),
+        NbtValue::Long(x) => Ok(x.into()),
+        NbtValue::Float(x) => Ok(x.into()),
+        NbtValue::Double(x) => Ok(x.into()),
+        other => Err(NbtError::IncorrectType(6, other.tag()).in_field(key)),
+    }
+}
+
+/// 整数放得下的时候写成 Int, 不然写成 Long; 浮点数写成 Double
+fn number_value(number: SchemaNumber) -> NbtValue {
+    match number {
+        SchemaNumber::Int(x) => i32::try_from(x).map_or(NbtValue::Long(x), NbtValue::Int),
+        SchemaNumber::Float(x) => NbtValue::Double(x),
+    }
+}
+
+impl ToNbt for NbtSchema {
+    /// 只写出不是默认值的项, 只有类型的时候是类型名
+    fn to_nbt(&self) -> NbtResult<NbtValue> {
+        let mut compound = NbtCompound::new();
+        if let Some(tag) = self.tag {
+            compound.push("type", TYPE_NAMES[tag as usize].into());
+        }
+        if let Some(min) = self.min {
+            compound.push("min", number_value(min));
+        }
+        if let Some(max) = self.max {
+            compound.push("max", number_value(max));
+        }
+        if let Some(values) = &self.values {
+            compound.push("values", values.to_nbt()?);
+        }
+        if let Some(elements) = &self.elements {
+            compound.push("elements", elements.to_nbt()?);
+        }
+        if !self.fields.is_empty() {
+            let mut fields = NbtCompound::new();
+            for field in &self.fields {
+                let mut schema = match field.schema.to_nbt()? {
+                    NbtValue::String(name) => {
+                        NbtCompound::from(vec![("type".to_string(), name.into())])
+                    }
+                    other => other.into_compound()?,
+                };
+                if !field.required {
+                    schema.push("optional", true.into());
+                }
+                fields.push(field.key.clone(), NbtValue::Compound(schema));
+            }
+            compound.push("fields", NbtValue::Compound(fields));
+        }
+        if self.open {
+            compound.push("open", true.into());
+        }
+        Ok(match (self.tag, compound.len()) {
+            (Some(tag), 1) => TYPE_NAMES[tag as usize].into(),
+            _ => NbtValue::Compound(compound),
+        })
+    }
+}
+
+impl FromNbt for NbtSchema {
+    fn from_nbt(value: NbtValue) -> NbtResult<Self> {
+        let compound = match value {
+            NbtValue::String(name) => NbtCompound::from(vec![("type".to_string(), name.into())]),
+            other => NbtCompound::from_nbt(other)?,
+        };
+        let mut schema = NbtSchema::default();
+        for (key, value) in compound {
+            match key.as_str() {
+                "type" => {
+                    let name = String::from_nbt(value).map_err(|e| e.in_field(&key))?;
+                    schema.tag = match name.as_str() {
+                        "any" => None,
+                        _ => Some(type_id(&name).filter(|tag| *tag != 0).ok_or_else(|| {
+                            NbtError::UnknownErr(format!("未知的类型名: {}", name))
+                        })?),
+                    };
+                }
+                "min" => schema.min = Some(number(&key, value)?),
+                "max" => schema.max = Some(number(&key, value)?),
+                "values" => {
+                    schema.values = Some(Vec::from_nbt(value).map_err(|e| e.in_field(&key))?)
+                }
+                "elements" => {
+                    schema.elements =
+                        Some(Box::new(Self::from_nbt(value).map_err(|e| e.in_field(&key))?))
+                }
+                "fields" => {
+                    let fields = NbtCompound::from_nbt(value).map_err(|e| e.in_field(&key))?;
+                    for (name, value) in fields {
+                        let field = SchemaField::from_nbt(name.clone(), value)
+                            .map_err(|e| e.in_field(&name).in_field(&key))?;
+                        schema.fields.push(field);
+                    }
+                }
+                "open" => schema.open = bool::from_nbt(value).map_err(|e| e.in_field(&key))?,
+                _ => return Err(NbtError::UnknownErr(format!("schema 里未知的 key: {}", key))),
+            }
+        }
+        Ok(schema)
+    }
+}
+
+impl SchemaField {
+    /// 字段的 schema 可以多一个 `optional`
+    fn from_nbt(key: String, value: NbtValue) -> NbtResult<Self> {
+        let mut required = true;
+        let value = match value {
+            NbtValue::Compound(mut compound) => {
+                if let Some(optional) = compound.remove("optional") {
+                    required = !bool::from_nbt(optional).map_err(|e| e.in_field("optional"))?;
+                }
+                NbtValue::Compound(compound)
+            }
+            other => other,
+        };
+        Ok(Self {
+            key,
+            schema: NbtSchema::from_nbt(value)?,
+            required,
+        })
+    }
+}
@@ -1,82 +1,1500 @@
-// use serde::{de as serde_de, forward_to_deserialize_any, ser as serde_ser, Deserialize};
-
-use crate::{nbt_version, NbtError, NbtResult, NbtValue};
-
-compile_error!("Serde support is not yet implemented, awaiting for PR https://github.com/shenjackyuanjie/nbt-rust/issues/1");
-// impl serde_ser::Error for NbtError {
-//     fn custom<T: Display>(msg: T) -> Self { NbtError::UnknownErr(msg.to_string()) }
-// }
-
-// impl serde_de::Error for NbtError {
-//     fn custom<T: Display>(msg: T) -> Self { NbtError::UnknownErr(msg.to_string()) }
-// }
-
-// pub fn from_bytes<'de, T, V>(bytes: &'de mut [u8]) -> NbtResult<T>
-// where
-//     T: serde_de::Deserialize<'de>,
-//     V: nbt_version::NbtReadTrait,
-// {
-//     let value = crate::NbtValue::from_binary::<V>(bytes)?;
-//     let deserializer = DeserializeNbtValue { value: &value };
-//     T::deserialize(deserializer)
-// }
-
-// pub struct DeserializeNbtValue<'de> {
-//     value: &'de NbtValue,
-// }
-
-// impl<'de> DeserializeNbtValue<'de> {
-//     pub fn new(value: &'de NbtValue) -> Self { Self { value } }
-// }
-
-// pub struct ArrayDeserializer<'de, T> {
-//     iter: std::slice::Iter<'de, T>,
-// }
-
-// impl <'de, T> ArrayDeserializer<'de, T> {
-//     pub fn new(iter: std::slice::Iter<'de, T>) -> Self {
-//         Self { iter }
-//     }
-// }
-
-// impl <'de, T> serde_de::SeqAccess<'de> for ArrayDeserializer<'de, T> {
-//     type Error = NbtError;
-
-//     fn next_element_seed<U>(&mut self, seed: U) -> NbtResult<Option<U::Value>>
-//     where
-//         U: serde_de::DeserializeSeed<'de>,
-//     {
-//         match self.iter.next() {
-//             Some(v) => seed.deserialize(DeserializeNbtValue::new(v)).map(Some),
-//             None => Ok(None),
-//         }
-//     }
-
-// }
-
-// impl<'de, 'a> serde_de::Deserializer for DeserializeNbtValue<'de> {
-//     forward_to_deserialize_any! {
-//         bool i8 i16 i32 i64 f32 f64 char string bytes
-//     }
-
-//     fn deserialize_any<V>(self, visitor: V) -> NbtResult<V::Value>
-//     where
-//         V: serde_de::Visitor<'de>,
-//     {
-//         match self.value {
-//             NbtValue::Byte(v) => visitor.visit_i8(*v),
-//             NbtValue::Short(v) => visitor.visit_i16(*v),
-//             NbtValue::Int(v) => visitor.visit_i32(*v),
-//             NbtValue::Long(v) => visitor.visit_i64(*v),
-//             NbtValue::Float(v) => visitor.visit_f32(*v),
-//             NbtValue::Double(v) => visitor.visit_f64(*v),
-//             NbtValue::ByteArray(v) => visitor.visit_seq(ArrayDeserializer::new(v.iter())),
-//             NbtValue::String(v) => visitor.visit_string(v.clone()),
-//             NbtValue::List(v) => visitor.visit_seq(ListDeserializer::new(v)),
-//             NbtValue::Compound(name, v) => visitor.visit_map(CompoundDeserializer::new(v)),
-//             NbtValue::IntArray(v) => visitor.visit_seq(ArrayDeserializer::new(v.iter())),
-//             NbtValue::LongArray(v) => visitor.visit_seq(ArrayDeserializer::new(v.iter())),
-
-//         }
-//     }
-// }
+//! serde 序列化, 直接写成二进制, 不经过 `NbtValue`; [`to_value`] 直接构造 `NbtValue`, 不经过二进制
+//!
+//! 类型对应关系:
+//! - `bool` / `i8` / `u8` -> Byte, `i16` / `u16` -> Short, `i32` / `u32` -> Int, `i64` / `u64` -> Long
+//!   (无符号的按补码存, 读回来还是原来的值)
+//! - `f32` -> Float, `f64` -> Double
+//! - `char` / `str` / 单元枚举 -> String
+//! - `bytes` (`serde_bytes`) -> ByteArray
+//! - 序列 / 元组 -> List, 元素类型必须一样
+//! - 结构体 / map -> Compound, map 的 key 可以是字符串, 字符或者整数
+//! - 其他枚举 -> 只有一个 key (变体名) 的 Compound
+//! - `None` / `()` / 单元结构体 -> 什么都不写, 在 Compound 里就是没有这个 key, 不能放在 List 里
+//! - `NbtValue` / `NbtList` / `NbtCompound` -> 原样写成对应的类型
+//!
+//! 在 JSON 之类的自描述格式里, `NbtValue` 写成 `{"Int": 1}`, `NbtList` 写成 `{"Int": [1, 2]}`,
+//! 这样 12 种类型和空 List 的元素类型都不会丢
+//!
+//! ```rust
+//! use serde::Serialize;
+//! use shen_nbt5::nbt_version::Java;
+//! use shen_nbt5::{to_bytes, NbtValue};
+//!
+//! #[derive(Serialize)]
+//! struct Item {
+//!     id: String,
+//!     count: i8,
+//! }
+//!
+//! let bytes = to_bytes::<Java, _>(&Item { id: "minecraft:stone".to_string(), count: 64 }).unwrap();
+//! let value = NbtValue::from_binary::<Java>(&mut bytes.clone()).unwrap();
+//! assert_eq!(value, NbtValue::from_snbt("{id: 'minecraft:stone', count: 64b}").unwrap());
+//! ```
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::nbt_version::NbtWriteTrait;
+use crate::serde::array_token;
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue};
+
+/// `NbtValue` 序列化时的枚举名, 本库的序列化器看到它会直接写里面的值
+pub(crate) const VALUE_TOKEN: &str = "$shen_nbt5::NbtValue";
+/// `NbtList` 序列化时的枚举名, 变体的序号就是元素类型
+pub(crate) const LIST_TOKEN: &str = "$shen_nbt5::NbtList";
+
+/// 按类型 id 排的变体名
+pub(crate) const TAG_VARIANTS: [&str; 13] = [
+    "End",
+    "Byte",
+    "Short",
+    "Int",
+    "Long",
+    "Float",
+    "Double",
+    "ByteArray",
+    "String",
+    "List",
+    "Compound",
+    "IntArray",
+    "LongArray",
+];
+
+impl ser::Error for NbtError {
+    fn custom<T: Display>(msg: T) -> Self { NbtError::UnknownErr(msg.to_string()) }
+}
+
+/// 序列化成二进制, 根节点名字为空
+///
+/// 根节点必须是 Compound (基岩版也可以是 List)
+pub fn to_bytes<W, T>(value: &T) -> NbtResult<Vec<u8>>
+where
+    W: NbtWriteTrait,
+    T: Serialize + ?Sized,
+{
+    to_bytes_impl::<W, T>(None, value)
+}
+
+/// 序列化成二进制, 带根节点名字
+pub fn to_bytes_with_name<W, T>(name: &str, value: &T) -> NbtResult<Vec<u8>>
+where
+    W: NbtWriteTrait,
+    T: Serialize + ?Sized,
+{
+    to_bytes_impl::<W, T>(Some(name), value)
+}
+
+/// 把任意 `Serialize` 的值转成 `NbtValue`
+///
+/// 直接构造, 不经过二进制, 类型对应关系和 [`to_bytes`] 完全一样, 但是根节点可以是任意类型
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> NbtResult<NbtValue> {
+    value
+        .serialize(ValueSerializer)?
+        .ok_or_else(|| NbtError::UnknownErr("没有可以转换的值".to_string()))
+}
+
+#[inline]
+fn is_array(tag: NbtTypeId) -> bool { matches!(tag, 7 | 11 | 12) }
+
+fn to_bytes_impl<W, T>(name: Option<&str>, value: &T) -> NbtResult<Vec<u8>>
+where
+    W: NbtWriteTrait,
+    T: Serialize + ?Sized,
+{
+    // 根节点类型要写完才知道, 先按 Compound 的名字规则写好头部, 写完再补上 tag;
+    // 这里不报错, 错误留给最后的检查
+    let guess = match name {
+        Some(name) => Ok(Some(name)),
+        None => W::unnamed_root_name(10),
+    };
+    let mut buff = vec![0];
+    if let Ok(Some(guess)) = guess.and_then(|name| W::check_root_name(10, name)) {
+        W::write_nbt_string(&mut buff, guess);
+    }
+    let header_len = buff.len();
+    let tag = value.serialize(Serializer::<W>::new(&mut buff))?;
+    let name = match name {
+        Some(name) => Some(name),
+        None => W::unnamed_root_name(tag)?,
+    };
+    let mut header = Vec::new();
+    W::write_root_header(&mut header, tag, name)?;
+    if header.len() == header_len {
+        buff[..header_len].copy_from_slice(&header);
+    } else {
+        // 名字规则和 Compound 不一样的根节点 (比如基岩版的 List) 才需要挪动
+        buff.splice(0..header_len, header);
+    }
+    Ok(buff)
+}
+
+/// 把一个值的内容 (不包括 tag 和名字) 写进 `buff`
+///
+/// 返回写入的类型, 什么都没写 (`None`) 的时候返回 End(0)
+pub struct Serializer<'a, W> {
+    buff: &'a mut Vec<u8>,
+    _format: PhantomData<W>,
+}
+
+impl<'a, W: NbtWriteTrait> Serializer<'a, W> {
+    pub fn new(buff: &'a mut Vec<u8>) -> Self {
+        Self {
+            buff,
+            _format: PhantomData,
+        }
+    }
+
+    /// 把 `value` 按 `tag` 类型的数组写入
+    fn array<T: Serialize + ?Sized>(self, tag: NbtTypeId, value: &T) -> NbtResult<NbtTypeId> {
+        match value.serialize(ArraySerializer { tag })? {
+            NbtValue::ByteArray(data) => W::write_i8_array(self.buff, &data),
+            NbtValue::IntArray(data) => W::write_i32_array(self.buff, &data),
+            NbtValue::LongArray(data) => W::write_i64_array(self.buff, &data),
+            _ => unreachable!("ArraySerializer 只会返回数组"),
+        }
+        Ok(tag)
+    }
+
+    /// 写入 Compound 里一个值的 tag 占位和名字, 返回 tag 的位置
+    fn begin_field(&mut self, key: &str) -> usize {
+        let start = self.buff.len();
+        self.buff.push(0);
+        W::write_nbt_string(self.buff, key);
+        start
+    }
+}
+
+/// 写完一个值之后补上 tag, 什么都没写的话把名字也删掉
+fn end_field(buff: &mut Vec<u8>, start: usize, tag: NbtTypeId) {
+    if tag == 0 {
+        buff.truncate(start);
+    } else {
+        buff[start] = tag;
+    }
+}
+
+impl<'a, W: NbtWriteTrait> ser::Serializer for Serializer<'a, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+    type SerializeSeq = ListSerializer<'a, W>;
+    type SerializeTuple = ListSerializer<'a, W>;
+    type SerializeTupleStruct = ListSerializer<'a, W>;
+    type SerializeTupleVariant = VariantSerializer<ListSerializer<'a, W>>;
+    type SerializeMap = CompoundSerializer<'a, W>;
+    type SerializeStruct = CompoundSerializer<'a, W>;
+    type SerializeStructVariant = VariantSerializer<CompoundSerializer<'a, W>>;
+
+    fn serialize_bool(self, v: bool) -> NbtResult<NbtTypeId> { self.serialize_i8(v as i8) }
+
+    fn serialize_i8(self, v: i8) -> NbtResult<NbtTypeId> {
+        self.buff.push(v as u8);
+        Ok(1)
+    }
+
+    fn serialize_i16(self, v: i16) -> NbtResult<NbtTypeId> {
+        W::write_i16(self.buff, v);
+        Ok(2)
+    }
+
+    fn serialize_i32(self, v: i32) -> NbtResult<NbtTypeId> {
+        W::write_i32(self.buff, v);
+        Ok(3)
+    }
+
+    fn serialize_i64(self, v: i64) -> NbtResult<NbtTypeId> {
+        W::write_i64(self.buff, v);
+        Ok(4)
+    }
+
+    fn serialize_u8(self, v: u8) -> NbtResult<NbtTypeId> { self.serialize_i8(v as i8) }
+
+    fn serialize_u16(self, v: u16) -> NbtResult<NbtTypeId> { self.serialize_i16(v as i16) }
+
+    fn serialize_u32(self, v: u32) -> NbtResult<NbtTypeId> { self.serialize_i32(v as i32) }
+
+    fn serialize_u64(self, v: u64) -> NbtResult<NbtTypeId> { self.serialize_i64(v as i64) }
+
+    fn serialize_f32(self, v: f32) -> NbtResult<NbtTypeId> {
+        W::write_f32(self.buff, v);
+        Ok(5)
+    }
+
+    fn serialize_f64(self, v: f64) -> NbtResult<NbtTypeId> {
+        W::write_f64(self.buff, v);
+        Ok(6)
+    }
+
+    fn serialize_char(self, v: char) -> NbtResult<NbtTypeId> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> NbtResult<NbtTypeId> {
+        W::write_nbt_string(self.buff, v);
+        Ok(8)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> NbtResult<NbtTypeId> {
+        let data: Vec<i8> = v.iter().map(|b| *b as i8).collect();
+        W::write_i8_array(self.buff, &data);
+        Ok(7)
+    }
+
+    fn serialize_none(self) -> NbtResult<NbtTypeId> { Ok(0) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> NbtResult<NbtTypeId> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> NbtResult<NbtTypeId> { Ok(0) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<NbtTypeId> { Ok(0) }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> NbtResult<NbtTypeId> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> NbtResult<NbtTypeId> {
+        match array_token(name) {
+            Some(tag) => self.array(tag, value),
+            None => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        mut self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> NbtResult<NbtTypeId> {
+        // `NbtValue`, 序号就是类型
+        if name == VALUE_TOKEN {
+            return match index as NbtTypeId {
+                tag if is_array(tag) => self.array(tag, value),
+                _ => value.serialize(self),
+            };
+        }
+        let start = self.begin_field(variant);
+        let tag = value.serialize(Serializer::<W>::new(self.buff))?;
+        end_field(self.buff, start, tag);
+        self.buff.push(0);
+        Ok(10)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> NbtResult<ListSerializer<'a, W>> {
+        ListSerializer::new(self.buff, len, 0)
+    }
+
+    fn serialize_tuple(self, len: usize) -> NbtResult<ListSerializer<'a, W>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> NbtResult<ListSerializer<'a, W>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        mut self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> NbtResult<Self::SerializeTupleVariant> {
+        if name == LIST_TOKEN {
+            return Ok(VariantSerializer {
+                inner: ListSerializer::new(self.buff, Some(len), index as NbtTypeId)?,
+                start: None,
+            });
+        }
+        let start = self.begin_field(variant);
+        Ok(VariantSerializer {
+            inner: ListSerializer::new(self.buff, Some(len), 0)?,
+            start: Some(start),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<CompoundSerializer<'a, W>> {
+        Ok(CompoundSerializer::new(self.buff))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<CompoundSerializer<'a, W>> {
+        Ok(CompoundSerializer::new(self.buff))
+    }
+
+    fn serialize_struct_variant(
+        mut self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStructVariant> {
+        let start = self.begin_field(variant);
+        Ok(VariantSerializer {
+            inner: CompoundSerializer::new(self.buff),
+            start: Some(start),
+        })
+    }
+}
+
+/// 写 List
+///
+/// 元素类型不是事先给定的话, 要写完第一个元素才知道, 先占一个字节;
+/// 长度不知道的时候先按 0 占位, 最后再改成实际的长度
+pub struct ListSerializer<'a, W> {
+    buff: &'a mut Vec<u8>,
+    /// 元素类型的位置
+    tag_pos: usize,
+    /// 事先不知道长度的时候, 长度占位的位置
+    len_pos: Option<usize>,
+    expect_len: Option<usize>,
+    element_tag: NbtTypeId,
+    /// `NbtList` 的数组元素要按数组写
+    array: bool,
+    len: usize,
+    _format: PhantomData<W>,
+}
+
+impl<'a, W: NbtWriteTrait> ListSerializer<'a, W> {
+    fn new(buff: &'a mut Vec<u8>, len: Option<usize>, element_tag: NbtTypeId) -> NbtResult<Self> {
+        let tag_pos = buff.len();
+        buff.push(element_tag);
+        let len_pos = match len {
+            Some(len) => {
+                let len = i32::try_from(len)
+                    .map_err(|_| NbtError::UnknownErr(format!("List 太长了: {}", len)))?;
+                W::write_i32(buff, len);
+                None
+            }
+            None => {
+                let pos = buff.len();
+                W::write_i32(buff, 0);
+                Some(pos)
+            }
+        };
+        Ok(Self {
+            buff,
+            tag_pos,
+            len_pos,
+            expect_len: len,
+            element_tag,
+            array: is_array(element_tag),
+            len: 0,
+            _format: PhantomData,
+        })
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let tag = match self.array {
+            true => Serializer::<W>::new(self.buff).array(self.element_tag, value)?,
+            false => value.serialize(Serializer::<W>::new(self.buff))?,
+        };
+        if tag == 0 {
+            return Err(NbtError::UnknownErr("List 里不能有 None".to_string()));
+        }
+        if self.element_tag == 0 {
+            self.element_tag = tag;
+            self.buff[self.tag_pos] = tag;
+        } else if tag != self.element_tag {
+            return Err(NbtError::ListTypeNotSame(vec![self.element_tag, tag]));
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> NbtResult<&'a mut Vec<u8>> {
+        if let Some(expect) = self.expect_len {
+            if expect != self.len {
+                return Err(NbtError::UnknownErr(format!(
+                    "List 的长度是 {}, 实际写了 {} 个",
+                    expect, self.len
+                )));
+            }
+        }
+        if let Some(pos) = self.len_pos {
+            let len = i32::try_from(self.len)
+                .map_err(|_| NbtError::UnknownErr(format!("List 太长了: {}", self.len)))?;
+            let mut prefix = Vec::new();
+            W::write_i32(&mut prefix, len);
+            let end = pos + W::i32_len(0);
+            if prefix.len() == end - pos {
+                self.buff[pos..end].copy_from_slice(&prefix);
+            } else {
+                // 只有变长编码的长度比占位长时才需要挪动
+                self.buff.splice(pos..end, prefix);
+            }
+        }
+        Ok(self.buff)
+    }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeSeq for ListSerializer<'_, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> { self.finish().map(|_| 9) }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeTuple for ListSerializer<'_, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> { self.finish().map(|_| 9) }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeTupleStruct for ListSerializer<'_, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> { self.finish().map(|_| 9) }
+}
+
+/// 写 Compound, 每个值写完之后补上 tag
+pub struct CompoundSerializer<'a, W> {
+    buff: &'a mut Vec<u8>,
+    /// `serialize_key` 之后, 等待写入的值的 tag 位置
+    key_start: Option<usize>,
+    _format: PhantomData<W>,
+}
+
+impl<'a, W: NbtWriteTrait> CompoundSerializer<'a, W> {
+    fn new(buff: &'a mut Vec<u8>) -> Self {
+        Self {
+            buff,
+            key_start: None,
+            _format: PhantomData,
+        }
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> NbtResult<()> {
+        let start = Serializer::<W>::new(self.buff).begin_field(key);
+        self.value_at(start, value)
+    }
+
+    fn value_at<T: Serialize + ?Sized>(&mut self, start: usize, value: &T) -> NbtResult<()> {
+        let tag = value.serialize(Serializer::<W>::new(self.buff))?;
+        end_field(self.buff, start, tag);
+        Ok(())
+    }
+
+    fn finish(self) -> NbtResult<&'a mut Vec<u8>> {
+        self.buff.push(0);
+        Ok(self.buff)
+    }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeMap for CompoundSerializer<'_, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> NbtResult<()> {
+        let key = key.serialize(KeySerializer)?;
+        self.key_start = Some(Serializer::<W>::new(self.buff).begin_field(&key));
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let start = self
+            .key_start
+            .take()
+            .ok_or_else(|| NbtError::UnknownErr("serialize_value 之前没有 key".to_string()))?;
+        self.value_at(start, value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> { self.finish().map(|_| 10) }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeStruct for CompoundSerializer<'_, W> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> { self.finish().map(|_| 10) }
+}
+
+/// 枚举的元组 / 结构体变体: `{变体名: 内容}`
+///
+/// `NbtList` 没有外面这一层
+pub struct VariantSerializer<S> {
+    inner: S,
+    /// 变体名前面 tag 的位置
+    start: Option<usize>,
+}
+
+/// 补上变体内容的 tag, 再结束外面的 Compound
+fn end_variant(buff: &mut Vec<u8>, start: Option<usize>, tag: NbtTypeId) -> NbtTypeId {
+    match start {
+        Some(start) => {
+            buff[start] = tag;
+            buff.push(0);
+            10
+        }
+        None => tag,
+    }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeTupleVariant for VariantSerializer<ListSerializer<'_, W>> {
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.inner.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> {
+        let buff = self.inner.finish()?;
+        Ok(end_variant(buff, self.start, 9))
+    }
+}
+
+impl<W: NbtWriteTrait> ser::SerializeStructVariant
+    for VariantSerializer<CompoundSerializer<'_, W>>
+{
+    type Ok = NbtTypeId;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.inner.field(key, value)
+    }
+
+    fn end(self) -> NbtResult<NbtTypeId> {
+        let buff = self.inner.finish()?;
+        Ok(end_variant(buff, self.start, 10))
+    }
+}
+
+/// 直接构造 `NbtValue`, 和 [`Serializer`] 的类型对应关系一样
+///
+/// 什么都没有 (`None`) 的时候返回 `None`
+pub struct ValueSerializer;
+
+impl ValueSerializer {
+    fn array<T: Serialize + ?Sized>(tag: NbtTypeId, value: &T) -> NbtResult<Option<NbtValue>> {
+        value.serialize(ArraySerializer { tag }).map(Some)
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+    type SerializeSeq = ValueListSerializer;
+    type SerializeTuple = ValueListSerializer;
+    type SerializeTupleStruct = ValueListSerializer;
+    type SerializeTupleVariant = ValueVariantSerializer<ValueListSerializer>;
+    type SerializeMap = ValueCompoundSerializer;
+    type SerializeStruct = ValueCompoundSerializer;
+    type SerializeStructVariant = ValueVariantSerializer<ValueCompoundSerializer>;
+
+    fn serialize_bool(self, v: bool) -> NbtResult<Option<NbtValue>> { self.serialize_i8(v as i8) }
+
+    fn serialize_i8(self, v: i8) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Byte(v))) }
+
+    fn serialize_i16(self, v: i16) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Short(v))) }
+
+    fn serialize_i32(self, v: i32) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Int(v))) }
+
+    fn serialize_i64(self, v: i64) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Long(v))) }
+
+    fn serialize_u8(self, v: u8) -> NbtResult<Option<NbtValue>> { self.serialize_i8(v as i8) }
+
+    fn serialize_u16(self, v: u16) -> NbtResult<Option<NbtValue>> { self.serialize_i16(v as i16) }
+
+    fn serialize_u32(self, v: u32) -> NbtResult<Option<NbtValue>> { self.serialize_i32(v as i32) }
+
+    fn serialize_u64(self, v: u64) -> NbtResult<Option<NbtValue>> { self.serialize_i64(v as i64) }
+
+    fn serialize_f32(self, v: f32) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Float(v))) }
+
+    fn serialize_f64(self, v: f64) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Double(v))) }
+
+    fn serialize_char(self, v: char) -> NbtResult<Option<NbtValue>> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> NbtResult<Option<NbtValue>> {
+        Ok(Some(NbtValue::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> NbtResult<Option<NbtValue>> {
+        Ok(Some(NbtValue::ByteArray(v.iter().map(|b| *b as i8).collect())))
+    }
+
+    fn serialize_none(self) -> NbtResult<Option<NbtValue>> { Ok(None) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> NbtResult<Option<NbtValue>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> NbtResult<Option<NbtValue>> { Ok(None) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<Option<NbtValue>> { Ok(None) }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> NbtResult<Option<NbtValue>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> NbtResult<Option<NbtValue>> {
+        match array_token(name) {
+            Some(tag) => Self::array(tag, value),
+            None => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> NbtResult<Option<NbtValue>> {
+        if name == VALUE_TOKEN {
+            return match index as NbtTypeId {
+                tag if is_array(tag) => Self::array(tag, value),
+                _ => value.serialize(self),
+            };
+        }
+        let mut compound = NbtCompound::new();
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            compound.push(variant, value);
+        }
+        Ok(Some(NbtValue::Compound(compound)))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> NbtResult<ValueListSerializer> {
+        Ok(ValueListSerializer::new(0))
+    }
+
+    fn serialize_tuple(self, len: usize) -> NbtResult<ValueListSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> NbtResult<ValueListSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleVariant> {
+        if name == LIST_TOKEN {
+            return Ok(ValueVariantSerializer {
+                inner: ValueListSerializer::new(index as NbtTypeId),
+                variant: None,
+            });
+        }
+        Ok(ValueVariantSerializer {
+            inner: ValueListSerializer::new(0),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<ValueCompoundSerializer> {
+        Ok(ValueCompoundSerializer::default())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<ValueCompoundSerializer> {
+        Ok(ValueCompoundSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStructVariant> {
+        Ok(ValueVariantSerializer {
+            inner: ValueCompoundSerializer::default(),
+            variant: Some(variant),
+        })
+    }
+}
+
+/// 构造 List, 元素类型没有事先给定的话由第一个元素决定
+pub struct ValueListSerializer {
+    element_tag: NbtTypeId,
+    /// `NbtList` 的数组元素要按数组构造
+    array: bool,
+    values: Vec<NbtValue>,
+}
+
+impl ValueListSerializer {
+    fn new(element_tag: NbtTypeId) -> Self {
+        Self {
+            element_tag,
+            array: is_array(element_tag),
+            values: Vec::new(),
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let value = match self.array {
+            true => ValueSerializer::array(self.element_tag, value)?,
+            false => value.serialize(ValueSerializer)?,
+        };
+        let Some(value) = value else {
+            return Err(NbtError::UnknownErr("List 里不能有 None".to_string()));
+        };
+        let tag = value.tag();
+        if self.element_tag == 0 {
+            self.element_tag = tag;
+        } else if tag != self.element_tag {
+            return Err(NbtError::ListTypeNotSame(vec![self.element_tag, tag]));
+        }
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> NbtResult<NbtValue> {
+        let list = match self.values.is_empty() {
+            true => NbtList::empty(self.element_tag)?,
+            false => NbtList::try_from(self.values)?,
+        };
+        Ok(NbtValue::List(list))
+    }
+}
+
+impl ser::SerializeSeq for ValueListSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> { self.finish().map(Some) }
+}
+
+impl ser::SerializeTuple for ValueListSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> { self.finish().map(Some) }
+}
+
+impl ser::SerializeTupleStruct for ValueListSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> { self.finish().map(Some) }
+}
+
+/// 构造 Compound, 值是 `None` 的 key 直接跳过
+#[derive(Default)]
+pub struct ValueCompoundSerializer {
+    compound: NbtCompound,
+    /// `serialize_key` 之后, 等待写入的值的 key
+    key: Option<String>,
+}
+
+impl ValueCompoundSerializer {
+    fn field<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> NbtResult<()> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.compound.push(key, value);
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for ValueCompoundSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> NbtResult<()> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| NbtError::UnknownErr("serialize_value 之前没有 key".to_string()))?;
+        self.field(key, value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Compound(self.compound))) }
+}
+
+impl ser::SerializeStruct for ValueCompoundSerializer {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.field(key.to_string(), value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> { Ok(Some(NbtValue::Compound(self.compound))) }
+}
+
+/// 枚举的元组 / 结构体变体: `{变体名: 内容}`, `NbtList` 没有外面这一层
+pub struct ValueVariantSerializer<S> {
+    inner: S,
+    variant: Option<&'static str>,
+}
+
+impl<S> ValueVariantSerializer<S> {
+    fn wrap(variant: Option<&'static str>, value: NbtValue) -> Option<NbtValue> {
+        Some(match variant {
+            Some(variant) => {
+                let mut compound = NbtCompound::new();
+                compound.push(variant, value);
+                NbtValue::Compound(compound)
+            }
+            None => value,
+        })
+    }
+}
+
+impl ser::SerializeTupleVariant for ValueVariantSerializer<ValueListSerializer> {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.inner.element(value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> {
+        Ok(Self::wrap(self.variant, self.inner.finish()?))
+    }
+}
+
+impl ser::SerializeStructVariant for ValueVariantSerializer<ValueCompoundSerializer> {
+    type Ok = Option<NbtValue>;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.inner.field(key.to_string(), value)
+    }
+
+    fn end(self) -> NbtResult<Option<NbtValue>> {
+        Ok(Self::wrap(self.variant, NbtValue::Compound(self.inner.compound)))
+    }
+}
+
+/// 构造 ByteArray / IntArray / LongArray, 内容必须是整数序列
+pub struct ArraySerializer {
+    tag: NbtTypeId,
+}
+
+fn array_error(tag: NbtTypeId) -> NbtError {
+    NbtError::UnknownErr(format!("{} 只能由整数序列组成", TAG_VARIANTS[tag as usize]))
+}
+
+impl ArraySerializer {
+    fn collector(self, len: Option<usize>) -> ArrayCollector {
+        ArrayCollector {
+            tag: self.tag,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        }
+    }
+}
+
+impl ser::Serializer for ArraySerializer {
+    type Ok = NbtValue;
+    type Error = NbtError;
+    type SerializeSeq = ArrayCollector;
+    type SerializeTuple = ArrayCollector;
+    type SerializeTupleStruct = Impossible<NbtValue, NbtError>;
+    type SerializeTupleVariant = Impossible<NbtValue, NbtError>;
+    type SerializeMap = Impossible<NbtValue, NbtError>;
+    type SerializeStruct = Impossible<NbtValue, NbtError>;
+    type SerializeStructVariant = Impossible<NbtValue, NbtError>;
+
+    fn serialize_seq(self, len: Option<usize>) -> NbtResult<ArrayCollector> {
+        Ok(self.collector(len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> NbtResult<ArrayCollector> {
+        Ok(self.collector(Some(len)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> NbtResult<NbtValue> {
+        let mut collector = self.collector(Some(v.len()));
+        collector.values.extend(v.iter().map(|b| *b as i64));
+        collector.finish()
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> NbtResult<NbtValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_i8(self, _v: i8) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_i16(self, _v: i16) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_i32(self, _v: i32) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_i64(self, _v: i64) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_u8(self, _v: u8) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_u16(self, _v: u16) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_u32(self, _v: u32) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_u64(self, _v: u64) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_f32(self, _v: f32) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_f64(self, _v: f64) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_char(self, _v: char) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_str(self, _v: &str) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_none(self) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> NbtResult<NbtValue> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_unit(self) -> NbtResult<NbtValue> { Err(array_error(self.tag)) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<NbtValue> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> NbtResult<NbtValue> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> NbtResult<NbtValue> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleStruct> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleVariant> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<Self::SerializeMap> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStruct> {
+        Err(array_error(self.tag))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStructVariant> {
+        Err(array_error(self.tag))
+    }
+}
+
+/// 收集数组的元素, 结束时检查范围再一次构造
+///
+/// 无符号数按补码存, 比如 ByteArray 里的 `255u8` 就是 `-1`
+pub struct ArrayCollector {
+    tag: NbtTypeId,
+    values: Vec<i64>,
+}
+
+fn out_of_range(value: i64, tag: NbtTypeId) -> NbtError {
+    NbtError::UnknownErr(format!("{} 超出了 {} 元素的范围", value, TAG_VARIANTS[tag as usize]))
+}
+
+impl ArrayCollector {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let value = value.serialize(IntSerializer).map_err(|_| array_error(self.tag))?;
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> NbtResult<NbtValue> {
+        let tag = self.tag;
+        Ok(match tag {
+            7 => NbtValue::ByteArray(
+                self.values
+                    .iter()
+                    .map(|&v| {
+                        i8::try_from(v)
+                            .or_else(|_| u8::try_from(v).map(|u| u as i8))
+                            .map_err(|_| out_of_range(v, tag))
+                    })
+                    .collect::<NbtResult<Vec<_>>>()?,
+            ),
+            11 => NbtValue::IntArray(
+                self.values
+                    .iter()
+                    .map(|&v| {
+                        i32::try_from(v)
+                            .or_else(|_| u32::try_from(v).map(|u| u as i32))
+                            .map_err(|_| out_of_range(v, tag))
+                    })
+                    .collect::<NbtResult<Vec<_>>>()?,
+            ),
+            _ => NbtValue::LongArray(self.values),
+        })
+    }
+}
+
+impl ser::SerializeSeq for ArrayCollector {
+    type Ok = NbtValue;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtValue> { self.finish() }
+}
+
+impl ser::SerializeTuple for ArrayCollector {
+    type Ok = NbtValue;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> NbtResult<NbtValue> { self.finish() }
+}
+
+/// 数组的元素, 只接受整数
+struct IntSerializer;
+
+impl ser::Serializer for IntSerializer {
+    type Ok = i64;
+    type Error = NbtError;
+    type SerializeSeq = Impossible<i64, NbtError>;
+    type SerializeTuple = Impossible<i64, NbtError>;
+    type SerializeTupleStruct = Impossible<i64, NbtError>;
+    type SerializeTupleVariant = Impossible<i64, NbtError>;
+    type SerializeMap = Impossible<i64, NbtError>;
+    type SerializeStruct = Impossible<i64, NbtError>;
+    type SerializeStructVariant = Impossible<i64, NbtError>;
+
+    fn serialize_i8(self, v: i8) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_i16(self, v: i16) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_i32(self, v: i32) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_i64(self, v: i64) -> NbtResult<i64> { Ok(v) }
+
+    fn serialize_u8(self, v: u8) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_u16(self, v: u16) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_u32(self, v: u32) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_u64(self, v: u64) -> NbtResult<i64> { Ok(v as i64) }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> NbtResult<i64> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_f32(self, _v: f32) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_f64(self, _v: f64) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_char(self, _v: char) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_str(self, _v: &str) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_bytes(self, _v: &[u8]) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_none(self) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> NbtResult<i64> {
+        Err(int_error())
+    }
+
+    fn serialize_unit(self) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<i64> { Err(int_error()) }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> NbtResult<i64> {
+        Err(int_error())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> NbtResult<i64> {
+        Err(int_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> NbtResult<Self::SerializeSeq> {
+        Err(int_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> NbtResult<Self::SerializeTuple> { Err(int_error()) }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleStruct> {
+        Err(int_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleVariant> {
+        Err(int_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<Self::SerializeMap> {
+        Err(int_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStruct> {
+        Err(int_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStructVariant> {
+        Err(int_error())
+    }
+}
+
+fn int_error() -> NbtError { NbtError::UnknownErr("数组的元素必须是整数".to_string()) }
+
+/// Compound 的 key, 字符串 / 字符 / 整数 / 单元枚举都可以
+struct KeySerializer;
+
+fn key_error() -> NbtError {
+    NbtError::UnknownErr("Compound 的 key 必须是字符串".to_string())
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = NbtError;
+    type SerializeSeq = Impossible<String, NbtError>;
+    type SerializeTuple = Impossible<String, NbtError>;
+    type SerializeTupleStruct = Impossible<String, NbtError>;
+    type SerializeTupleVariant = Impossible<String, NbtError>;
+    type SerializeMap = Impossible<String, NbtError>;
+    type SerializeStruct = Impossible<String, NbtError>;
+    type SerializeStructVariant = Impossible<String, NbtError>;
+
+    fn serialize_str(self, v: &str) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_char(self, v: char) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_i8(self, v: i8) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_i16(self, v: i16) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_i32(self, v: i32) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_i64(self, v: i64) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_u8(self, v: u8) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_u16(self, v: u16) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_u32(self, v: u32) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_u64(self, v: u64) -> NbtResult<String> { Ok(v.to_string()) }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> NbtResult<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> NbtResult<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_f32(self, _v: f32) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_f64(self, _v: f64) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_bytes(self, _v: &[u8]) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_none(self) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> NbtResult<String> {
+        Err(key_error())
+    }
+
+    fn serialize_unit(self) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<String> { Err(key_error()) }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> NbtResult<String> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> NbtResult<Self::SerializeSeq> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> NbtResult<Self::SerializeTuple> { Err(key_error()) }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleStruct> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeTupleVariant> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<Self::SerializeMap> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStruct> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<Self::SerializeStructVariant> {
+        Err(key_error())
+    }
+}
+
+impl Serialize for NbtValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tag = self.tag();
+        let name = TAG_VARIANTS[tag as usize];
+        let tag = tag as u32;
+        match self {
+            NbtValue::Byte(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Short(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Int(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Long(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Float(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Double(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::String(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::List(v) => serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v),
+            NbtValue::Compound(v) => {
+                serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v)
+            }
+            NbtValue::ByteArray(v) => {
+                serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v)
+            }
+            NbtValue::IntArray(v) => {
+                serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v)
+            }
+            NbtValue::LongArray(v) => {
+                serializer.serialize_newtype_variant(VALUE_TOKEN, tag, name, v)
+            }
+        }
+    }
+}
+
+/// 写成元组变体, 变体就是元素类型, 所以空 List 也不会丢元素类型
+impl Serialize for NbtList {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::SerializeTupleVariant;
+
+        let tag = self.element_tag();
+        let mut seq = serializer.serialize_tuple_variant(
+            LIST_TOKEN,
+            tag as u32,
+            TAG_VARIANTS[tag as usize],
+            self.len(),
+        )?;
+        match self {
+            NbtList::End => {}
+            NbtList::Byte(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Short(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Int(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Long(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Float(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Double(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::String(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::List(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::Compound(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::ByteArray(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::IntArray(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+            NbtList::LongArray(v) => v.iter().try_for_each(|x| seq.serialize_field(x))?,
+        }
+        seq.end()
+    }
+}
+
+/// 按顺序写成 map, 重复的 key 也原样写出去
+impl Serialize for NbtCompound {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,165 @@
+//! serde 里区分不出来的 NBT 类型
+//!
+//! `Vec<i32>` 默认写成 Int 的 List, 要写成 IntArray 的话用包装类型或者 `with` 模块:
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use shen_nbt5::serde::LongArray;
+//! use shen_nbt5::{to_value, NbtValue};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Section {
+//!     #[serde(with = "shen_nbt5::serde::int_array")]
+//!     biomes: Vec<i32>,
+//!     states: LongArray,
+//!     #[serde(with = "shen_nbt5::serde::uuid")]
+//!     owner: [u8; 16],
+//!     #[serde(rename = "Y")]
+//!     y: i8,
+//! }
+//!
+//! let section = Section {
+//!     biomes: vec![1, 2],
+//!     states: LongArray(vec![3]),
+//!     owner: [0; 16],
+//!     y: -4,
+//! };
+//! assert_eq!(
+//!     to_value(&section).unwrap(),
+//!     NbtValue::from_snbt("{biomes: [I; 1, 2], states: [L; 3L], owner: [I; 0, 0, 0, 0], Y: -4b}")
+//!         .unwrap()
+//! );
+//! ```
+//!
+//! 其他和游戏一致的约定:
+//! - `bool` 写成 Byte (0b / 1b), 读的时候任何数字类型非 0 都是 `true`
+//! - 单元枚举写成变体名的 String, 游戏里一般是小写, 配合 `#[serde(rename_all = "snake_case")]` 使用;
+//!   读的时候也接受 Byte / Short / Int 的序号, 比如 `playerGameType: 1`
+//!
+//! 数组的元素可以是任意整数类型, 无符号数按补码存, 比如 ByteArray 里的 `255u8` 就是 `-1b`
+
+use std::ops::{Deref, DerefMut};
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::NbtTypeId;
+
+/// 数组序列化时的 newtype 名字, 本库的序列化器看到它会写成数组而不是 List
+pub(crate) const BYTE_ARRAY_TOKEN: &str = "$shen_nbt5::ByteArray";
+pub(crate) const INT_ARRAY_TOKEN: &str = "$shen_nbt5::IntArray";
+pub(crate) const LONG_ARRAY_TOKEN: &str = "$shen_nbt5::LongArray";
+
+/// 数组序列化时的 newtype 名字对应的类型
+pub(crate) fn array_token(name: &str) -> Option<NbtTypeId> {
+    match name {
+        BYTE_ARRAY_TOKEN => Some(7),
+        INT_ARRAY_TOKEN => Some(11),
+        LONG_ARRAY_TOKEN => Some(12),
+        _ => None,
+    }
+}
+
+macro_rules! array_type {
+    ($($name:ident($ty:ty) => $token:ident, $module:ident, $snbt:literal);* $(;)?) => {
+        $(
+            #[doc = concat!("强制写成 ", stringify!($name), " 的数组, 比如 `", $snbt, "`")]
+            #[derive(Debug, Clone, PartialEq, Eq, Default)]
+            pub struct $name(pub Vec<$ty>);
+
+            impl $name {
+                pub fn into_inner(self) -> Vec<$ty> { self.0 }
+            }
+
+            impl Deref for $name {
+                type Target = Vec<$ty>;
+
+                fn deref(&self) -> &Vec<$ty> { &self.0 }
+            }
+
+            impl DerefMut for $name {
+                fn deref_mut(&mut self) -> &mut Vec<$ty> { &mut self.0 }
+            }
+
+            impl From<Vec<$ty>> for $name {
+                fn from(value: Vec<$ty>) -> Self { Self(value) }
+            }
+
+            impl From<$name> for Vec<$ty> {
+                fn from(value: $name) -> Self { value.0 }
+            }
+
+            impl Serialize for $name {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    $module::serialize(&self.0, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    $module::deserialize(deserializer).map(Self)
+                }
+            }
+
+            #[doc = concat!("`#[serde(with = \"shen_nbt5::serde::", stringify!($module), "\")]`")]
+            ///
+            /// 可以用在任意整数的序列上, 比如 `Vec<u8>`, `[i32; 4]`
+            pub mod $module {
+                use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+                use super::$token;
+
+                pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    T: Serialize + ?Sized,
+                    S: Serializer,
+                {
+                    serializer.serialize_newtype_struct($token, value)
+                }
+
+                /// 数组和 List 都可以读
+                pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                where
+                    T: Deserialize<'de>,
+                    D: Deserializer<'de>,
+                {
+                    T::deserialize(deserializer)
+                }
+            }
+        )*
+    };
+}
+
+array_type! {
+    ByteArray(i8) => BYTE_ARRAY_TOKEN, byte_array, "[B; 1b, 2b]";
+    IntArray(i32) => INT_ARRAY_TOKEN, int_array, "[I; 1, 2]";
+    LongArray(i64) => LONG_ARRAY_TOKEN, long_array, "[L; 1L, 2L]";
+}
+
+/// `#[serde(with = "shen_nbt5::serde::uuid")]`
+///
+/// 游戏里的 UUID: 4 个大端序 Int 组成的 IntArray, 对应 `[u8; 16]` (比如 `uuid::Uuid::as_bytes`)
+pub mod uuid {
+    use ::serde::{de, Deserialize, Deserializer, Serializer};
+
+    use super::INT_ARRAY_TOKEN;
+
+    pub fn serialize<S: Serializer>(value: &[u8; 16], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut ints = [0i32; 4];
+        for (int, chunk) in ints.iter_mut().zip(value.chunks_exact(4)) {
+            *int = i32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, &ints)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 16], D::Error> {
+        let ints = Vec::<i32>::deserialize(deserializer)?;
+        if ints.len() != 4 {
+            return Err(de::Error::invalid_length(ints.len(), &"4 个 Int 的 UUID"));
+        }
+        let mut bytes = [0u8; 16];
+        for (chunk, int) in bytes.chunks_exact_mut(4).zip(ints) {
+            chunk.copy_from_slice(&int.to_be_bytes());
+        }
+        Ok(bytes)
+    }
+}
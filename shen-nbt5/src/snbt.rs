@@ -0,0 +1,710 @@
+//! SNBT (字符串形式的 NBT)
+//!
+//! `{foo:1b,bar:[I;1,2,3]}` 这种, 命令和数据包里用的就是这个
+//!
+//! 语法和 1.21.5 之后的游戏一致:
+//! - 数字后缀 `b` `s` `i` `l` `f` `d`, 整数后缀前面可以加 `u` / `s` 表示无符号 / 有符号 (`255ub`)
+//! - `0x` 十六进制 和 `0b` 二进制, 默认按无符号处理 (`0xFFFFFFFF` 是 `-1`)
+//! - 数字中间可以有 `_` (`1_000_000`)
+//! - `true` / `false` 是 Byte
+//! - 字符串转义 `\\` `\'` `\"` `\b` `\s` `\t` `\n` `\f` `\r` `\xHH` `\uHHHH` `\UHHHHHHHH`
+//! - `[B;` `[I;` `[L;` 里没有后缀的整数直接当成数组的元素类型
+//!
+//! 不支持混合类型的 List 和 `bool(..)` 这类函数
+//!
+//! 输出的格式见 [`SnbtStyle`], 输出的结果都可以再解析回来
+
+use std::fmt::{self, Write};
+
+use crate::convert::path_key;
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue};
+
+/// 解析一整段 SNBT, 前后可以有空白
+pub(crate) fn parse(src: &str) -> NbtResult<NbtValue> {
+    let mut parser = SnbtParser::new(src);
+    let value = parser.parse_value()?;
+    parser.skip_ws();
+    if parser.peek().is_some() {
+        return Err(parser.error("多余的内容"));
+    }
+    Ok(value)
+}
+
+/// 不用引号的 key 和字符串可以用的字符
+#[inline]
+pub(crate) fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+pub(crate) struct SnbtParser<'s> {
+    src: &'s str,
+    /// 当前位置 (字节)
+    pub(crate) pos: usize,
+}
+
+impl<'s> SnbtParser<'s> {
+    pub(crate) fn new(src: &'s str) -> Self { Self { src, pos: 0 } }
+
+    /// 报错, 位置是当前位置的行和列 (都从 1 开始)
+    pub(crate) fn error(&self, msg: impl Into<String>) -> NbtError {
+        let before = &self.src[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        NbtError::InvalidSnbt(line, column, msg.into())
+    }
+
+    #[inline]
+    pub(crate) fn src(&self) -> &'s str { self.src }
+
+    #[inline]
+    pub(crate) fn peek(&self) -> Option<char> { self.src[self.pos..].chars().next() }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> Option<char> { self.src[self.pos..].chars().nth(n) }
+
+    #[inline]
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    pub(crate) fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    /// 跳过空白之后必须是 `c`
+    fn expect(&mut self, c: char) -> NbtResult<()> {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(format!("这里应该是 {:?}", c)))
+        }
+    }
+
+    /// 有逗号的话跳过, 返回是否有
+    fn element_separator(&mut self) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(',') {
+            self.bump();
+            self.skip_ws();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn parse_value(&mut self) -> NbtResult<NbtValue> { self.parse_value_as(3) }
+
+    /// `int_tag` 是没有后缀的整数的类型
+    fn parse_value_as(&mut self, int_tag: NbtTypeId) -> NbtResult<NbtValue> {
+        self.skip_ws();
+        match self.peek() {
+            None => Err(self.error("这里应该是一个值")),
+            Some('{') => Ok(NbtValue::Compound(self.parse_compound()?)),
+            Some('[') => self.parse_list_or_array(),
+            Some(_) => self.parse_scalar(int_tag),
+        }
+    }
+
+    pub(crate) fn parse_compound(&mut self) -> NbtResult<NbtCompound> {
+        self.expect('{')?;
+        self.skip_ws();
+        let mut compound = NbtCompound::new();
+        while self.peek().is_some_and(|c| c != '}') {
+            let start = self.pos;
+            let key = self.parse_key()?;
+            // 加了引号的空 key 是可以的
+            if self.pos == start {
+                self.pos = start;
+                return Err(self.error("这里应该是一个 key"));
+            }
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            compound.insert(key, value);
+            if !self.element_separator() {
+                break;
+            }
+            if self.peek().is_none() {
+                return Err(self.error("这里应该是一个 key"));
+            }
+        }
+        self.expect('}')?;
+        Ok(compound)
+    }
+
+    fn parse_key(&mut self) -> NbtResult<String> {
+        self.skip_ws();
+        match self.peek() {
+            None => Err(self.error("这里应该是一个 key")),
+            Some('"' | '\'') => self.parse_quoted(),
+            Some(_) => Ok(self.parse_unquoted().to_string()),
+        }
+    }
+
+    /// 引号里的字符串
+    pub(crate) fn parse_quoted(&mut self) -> NbtResult<String> {
+        let quote = self.bump().expect("调用前检查过引号");
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            match self.bump() {
+                None => return Err(self.error("引号没有闭合")),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some(c @ ('\\' | '\'' | '"')) => Some(c),
+                        Some('b') => Some('\u{8}'),
+                        Some('s') => Some(' '),
+                        Some('t') => Some('\t'),
+                        Some('n') => Some('\n'),
+                        Some('f') => Some('\u{c}'),
+                        Some('r') => Some('\r'),
+                        Some('x') => self.parse_hex_char(2),
+                        Some('u') => self.parse_hex_char(4),
+                        Some('U') => self.parse_hex_char(8),
+                        _ => None,
+                    };
+                    match escaped {
+                        Some(c) => out.push(c),
+                        None => {
+                            self.pos = start;
+                            return Err(self.error("无效的转义"));
+                        }
+                    }
+                }
+                Some(c) if c == quote => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    /// `\\x` `\\u` `\\U` 后面固定长度的十六进制
+    fn parse_hex_char(&mut self, len: usize) -> Option<char> {
+        let digits = self.src[self.pos..].get(..len)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += len;
+        char::from_u32(u32::from_str_radix(digits, 16).ok()?)
+    }
+
+    fn parse_unquoted(&mut self) -> &'s str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn parse_scalar(&mut self, int_tag: NbtTypeId) -> NbtResult<NbtValue> {
+        if let Some('"' | '\'') = self.peek() {
+            return Ok(NbtValue::String(self.parse_quoted()?));
+        }
+        let start = self.pos;
+        let text = self.parse_unquoted();
+        if text.is_empty() {
+            return Err(self.error("这里应该是一个值"));
+        }
+        match number(text, int_tag) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) if text == "true" => Ok(NbtValue::Byte(1)),
+            Ok(None) if text == "false" => Ok(NbtValue::Byte(0)),
+            Ok(None) => Ok(NbtValue::String(text.to_string())),
+            Err(msg) => {
+                self.pos = start;
+                Err(self.error(msg))
+            }
+        }
+    }
+
+    fn parse_list_or_array(&mut self) -> NbtResult<NbtValue> {
+        let is_array = self.peek_nth(1).is_some_and(|c| c != '"' && c != '\'')
+            && self.peek_nth(2) == Some(';');
+        if is_array {
+            return self.parse_array();
+        }
+        self.expect('[')?;
+        self.skip_ws();
+        if self.peek().is_none() {
+            return Err(self.error("这里应该是一个值"));
+        }
+        let mut list = NbtList::End;
+        while self.peek().is_some_and(|c| c != ']') {
+            let start = self.pos;
+            let value = self.parse_value()?;
+            if list.push(value).is_err() {
+                let (got, expect) = (self.src[start..self.pos].trim(), list.element_tag());
+                self.pos = start;
+                return Err(self.error(format!("{} 不能放进元素类型为 {} 的 List", got, expect)));
+            }
+            if !self.element_separator() {
+                break;
+            }
+            if self.peek().is_none() {
+                return Err(self.error("这里应该是一个值"));
+            }
+        }
+        self.expect(']')?;
+        Ok(NbtValue::List(list))
+    }
+
+    /// `[B;` `[I;` `[L;`
+    fn parse_array(&mut self) -> NbtResult<NbtValue> {
+        self.expect('[')?;
+        let start = self.pos;
+        let kind = self.bump().expect("调用前检查过长度");
+        self.bump();
+        self.skip_ws();
+        if self.peek().is_none() {
+            return Err(self.error("这里应该是一个值"));
+        }
+        let tag = match kind {
+            'B' => 1,
+            'I' => 3,
+            'L' => 4,
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!("无效的数组类型: {:?}", kind)));
+            }
+        };
+        let mut values = Vec::new();
+        while self.peek().is_some_and(|c| c != ']') {
+            let start = self.pos;
+            let value = self.parse_value_as(tag)?;
+            if value.tag() != tag {
+                self.pos = start;
+                return Err(self.error(format!("{} 类型的值不能放进 [{};", value.tag(), kind)));
+            }
+            values.push(value);
+            if !self.element_separator() {
+                break;
+            }
+            if self.peek().is_none() {
+                return Err(self.error("这里应该是一个值"));
+            }
+        }
+        self.expect(']')?;
+        Ok(match kind {
+            'B' => {
+                NbtValue::ByteArray(values.into_iter().filter_map(|v| v.into_i8().ok()).collect())
+            }
+            'I' => {
+                NbtValue::IntArray(values.into_iter().filter_map(|v| v.into_i32().ok()).collect())
+            }
+            _ => {
+                NbtValue::LongArray(values.into_iter().filter_map(|v| v.into_i64().ok()).collect())
+            }
+        })
+    }
+}
+
+/// 数字中的一段: 非空, `_` 不能在开头和结尾
+fn is_digits(text: &str, radix: u32) -> bool {
+    !text.is_empty()
+        && !text.starts_with('_')
+        && !text.ends_with('_')
+        && text.chars().all(|c| c == '_' || c.is_digit(radix))
+}
+
+/// 整数后缀: `(是否有符号, 类型)`
+fn integer_suffix(suffix: &str) -> Option<(Option<bool>, Option<NbtTypeId>)> {
+    let tag = |c: u8| match c.to_ascii_lowercase() {
+        b'b' => Some(1),
+        b's' => Some(2),
+        b'i' => Some(3),
+        b'l' => Some(4),
+        _ => None,
+    };
+    match suffix.as_bytes() {
+        [] => Some((None, None)),
+        [t] => Some((None, Some(tag(*t)?))),
+        [sign, t] => {
+            let signed = match sign.to_ascii_lowercase() {
+                b's' => true,
+                b'u' => false,
+                _ => return None,
+            };
+            Some((Some(signed), Some(tag(*t)?)))
+        }
+        _ => None,
+    }
+}
+
+/// 按类型检查范围, 超出范围返回 `None`
+///
+/// 无符号的值按补码重新解释, `255ub` 是 `-1b`
+fn integer(negative: bool, magnitude: u64, signed: bool, tag: NbtTypeId) -> Option<NbtValue> {
+    let bits = match tag {
+        1 => 8,
+        2 => 16,
+        3 => 32,
+        _ => 64,
+    };
+    let value = if signed {
+        let value = if negative {
+            -(magnitude as i128)
+        } else {
+            magnitude as i128
+        };
+        if value < -(1 << (bits - 1)) || value > (1 << (bits - 1)) - 1 {
+            return None;
+        }
+        value as i64
+    } else {
+        if (negative && magnitude != 0) || (bits < 64 && magnitude >> bits != 0) {
+            return None;
+        }
+        ((magnitude << (64 - bits)) as i64) >> (64 - bits)
+    };
+    Some(match tag {
+        1 => NbtValue::Byte(value as i8),
+        2 => NbtValue::Short(value as i16),
+        3 => NbtValue::Int(value as i32),
+        _ => NbtValue::Long(value),
+    })
+}
+
+/// 没有引号的值如果是数字的话解析出来
+///
+/// 不是数字返回 `Ok(None)`, 是数字但是超出范围返回 `Err`
+pub(crate) fn number(text: &str, int_tag: NbtTypeId) -> Result<Option<NbtValue>, &'static str> {
+    const OUT_OF_RANGE: &str = "数字超出范围";
+    let (negative, body) = match text.as_bytes()[0] {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let bytes = body.as_bytes();
+    let radix = match bytes {
+        [b'0', b'x' | b'X', ..] => 16,
+        [b'0', b'b' | b'B', b'0' | b'1', ..] => 2,
+        _ => 10,
+    };
+    let digits_start = if radix == 10 { 0 } else { 2 };
+    let digits_end = body[digits_start..]
+        .find(|c: char| c != '_' && !c.is_digit(radix))
+        .map_or(body.len(), |i| i + digits_start);
+    let (digits, suffix) = (&body[digits_start..digits_end], &body[digits_end..]);
+
+    if radix == 10
+        && (suffix.starts_with(['.', 'e', 'E']) || matches!(suffix, "f" | "F" | "d" | "D"))
+    {
+        return float(negative, body);
+    }
+    if !is_digits(digits, radix) || (radix == 10 && digits.len() > 1 && digits.starts_with('0')) {
+        return Ok(None);
+    }
+    let Some((signed, tag)) = integer_suffix(suffix) else {
+        return Ok(None);
+    };
+    let magnitude =
+        u64::from_str_radix(&digits.replace('_', ""), radix).map_err(|_| OUT_OF_RANGE)?;
+    // 十进制和带负号的默认是有符号的
+    let signed = signed.unwrap_or(radix == 10 || negative);
+    integer(negative, magnitude, signed, tag.unwrap_or(int_tag))
+        .map(Some)
+        .ok_or(OUT_OF_RANGE)
+}
+
+/// `([0-9]+[.]?|[0-9]*[.][0-9]+)(e[-+]?[0-9]+)?[fd]?`
+///
+/// 没有后缀的是 Double
+fn float(negative: bool, body: &str) -> Result<Option<NbtValue>, &'static str> {
+    let (body, is_float) = match body.as_bytes()[body.len() - 1] {
+        b'f' | b'F' => (&body[..body.len() - 1], true),
+        b'd' | b'D' => (&body[..body.len() - 1], false),
+        _ => (body, false),
+    };
+    let (mantissa, exponent) = match body.find(['e', 'E']) {
+        Some(i) => (&body[..i], Some(&body[i + 1..])),
+        None => (body, None),
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            (int.is_empty() || is_digits(int, 10))
+                && (frac.is_empty() || is_digits(frac, 10))
+                && !(int.is_empty() && frac.is_empty())
+        }
+        None => is_digits(mantissa, 10),
+    };
+    let exponent_ok =
+        exponent.is_none_or(|e| is_digits(e.strip_prefix(['-', '+']).unwrap_or(e), 10));
+    if !mantissa_ok || !exponent_ok {
+        return Ok(None);
+    }
+    let text = format!("{}{}", if negative { "-" } else { "" }, body.replace('_', ""));
+    let value = if is_float {
+        text.parse().ok().filter(|v: &f32| v.is_finite()).map(NbtValue::Float)
+    } else {
+        text.parse().ok().filter(|v: &f64| v.is_finite()).map(NbtValue::Double)
+    };
+    value.map(Some).ok_or("数字超出范围")
+}
+
+/// SNBT 输出格式
+///
+/// 默认是紧凑的一行, 和游戏里 `toString` 的格式一样
+///
+/// ```rust
+/// use shen_nbt5::{NbtValue, SnbtStyle};
+///
+/// let value = NbtValue::from_snbt("{b:[I;1,2],a:'x'}").unwrap();
+/// assert_eq!(value.to_snbt(), "{b:[I;1,2],a:x}");
+/// let style = SnbtStyle { sort_keys: true, always_quote: true, ..SnbtStyle::pretty() };
+/// assert_eq!(value.to_snbt_with(&style), "{\n    \"a\": \"x\",\n    \"b\": [I; 1, 2]\n}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SnbtStyle {
+    /// 每层缩进的空格数, `None` 就是紧凑的一行
+    pub indent: Option<usize>,
+    /// Compound 的 key 按字典序输出
+    pub sort_keys: bool,
+    /// key 和字符串总是加引号, 否则只在需要的时候加
+    pub always_quote: bool,
+    /// 优先用单引号, 内容里有单引号没有双引号的时候还是用双引号
+    pub single_quote: bool,
+}
+
+impl SnbtStyle {
+    /// 紧凑的一行
+    pub fn compact() -> Self { Self::default() }
+
+    /// 缩进 4 个空格
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(4),
+            ..Self::default()
+        }
+    }
+}
+
+/// key 是否可以不加引号
+fn is_bare_key(key: &str) -> bool { !key.is_empty() && key.chars().all(is_unquoted_char) }
+
+/// 字符串值是否可以不加引号
+///
+/// 除了 key 的要求以外, 还不能被当成数字或者 `true` / `false`,
+/// 开头是数字或者符号的也加上引号, 免得不同版本的游戏理解不一样
+fn is_bare_string(text: &str) -> bool {
+    is_bare_key(text)
+        && !text.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'))
+        && !matches!(text, "true" | "false")
+}
+
+/// 第一个 NaN 或者无穷大的路径, `path` 是 `value` 自己的路径
+pub(crate) fn non_finite_path(value: &NbtValue, path: String) -> Option<String> {
+    match value {
+        NbtValue::Float(x) if !x.is_finite() => Some(path),
+        NbtValue::Double(x) if !x.is_finite() => Some(path),
+        NbtValue::List(list) => non_finite_in_list(list, &path),
+        NbtValue::Compound(compound) => non_finite_in_compound(compound, &path),
+        _ => None,
+    }
+}
+
+fn non_finite_in_list(list: &NbtList, path: &str) -> Option<String> {
+    let index = |i: usize| format!("{}[{}]", path, i);
+    match list {
+        NbtList::Float(v) => v.iter().position(|x| !x.is_finite()).map(index),
+        NbtList::Double(v) => v.iter().position(|x| !x.is_finite()).map(index),
+        NbtList::List(v) => {
+            v.iter().enumerate().find_map(|(i, x)| non_finite_in_list(x, &index(i)))
+        }
+        NbtList::Compound(v) => {
+            v.iter().enumerate().find_map(|(i, x)| non_finite_in_compound(x, &index(i)))
+        }
+        _ => None,
+    }
+}
+
+fn non_finite_in_compound(compound: &NbtCompound, path: &str) -> Option<String> {
+    compound.iter().find_map(|(key, value)| {
+        let child = match path {
+            "" => path_key(key),
+            _ => format!("{}.{}", path, path_key(key)),
+        };
+        non_finite_path(value, child)
+    })
+}
+
+pub(crate) struct SnbtPrinter<'a, W> {
+    out: W,
+    style: &'a SnbtStyle,
+    depth: usize,
+}
+
+impl<'a, W: Write> SnbtPrinter<'a, W> {
+    pub(crate) fn new(out: W, style: &'a SnbtStyle) -> Self {
+        Self {
+            out,
+            style,
+            depth: 0,
+        }
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        if let Some(indent) = self.style.indent {
+            self.out.write_char('\n')?;
+            for _ in 0..indent * self.depth {
+                self.out.write_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 紧凑模式下没有空格
+    fn space(&mut self) -> fmt::Result {
+        if self.style.indent.is_some() {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn quoted(&mut self, text: &str) -> fmt::Result {
+        let (prefer, other) = if self.style.single_quote {
+            ('\'', '"')
+        } else {
+            ('"', '\'')
+        };
+        let quote = if text.contains(prefer) && !text.contains(other) {
+            other
+        } else {
+            prefer
+        };
+        self.out.write_char(quote)?;
+        for c in text.chars() {
+            match c {
+                '\\' => self.out.write_str("\\\\")?,
+                c if c == quote => {
+                    self.out.write_char('\\')?;
+                    self.out.write_char(c)?;
+                }
+                '\u{8}' => self.out.write_str("\\b")?,
+                '\t' => self.out.write_str("\\t")?,
+                '\n' => self.out.write_str("\\n")?,
+                '\u{c}' => self.out.write_str("\\f")?,
+                '\r' => self.out.write_str("\\r")?,
+                c if (c as u32) < 0x100 && c.is_control() => {
+                    write!(self.out, "\\x{:02x}", c as u32)?
+                }
+                c if c.is_control() => write!(self.out, "\\u{:04x}", c as u32)?,
+                c => self.out.write_char(c)?,
+            }
+        }
+        self.out.write_char(quote)
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        if !self.style.always_quote && is_bare_key(key) {
+            self.out.write_str(key)
+        } else {
+            self.quoted(key)
+        }
+    }
+
+    fn string(&mut self, text: &str) -> fmt::Result {
+        if !self.style.always_quote && is_bare_string(text) {
+            self.out.write_str(text)
+        } else {
+            self.quoted(text)
+        }
+    }
+
+    /// `[B;` `[I;` `[L;` 总是一行
+    fn array<T>(&mut self, kind: char, items: &[T], suffix: &str) -> fmt::Result
+    where
+        T: fmt::Display,
+    {
+        write!(self.out, "[{};", kind)?;
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 {
+                self.out.write_char(',')?;
+            }
+            self.space()?;
+            write!(self.out, "{}{}", item, suffix)?;
+        }
+        self.out.write_char(']')
+    }
+
+    /// 一个元素一行的 `[...]` 或者 `{...}`
+    fn seq<T>(
+        &mut self,
+        (open, close): (char, char),
+        items: impl ExactSizeIterator<Item = T>,
+        mut item: impl FnMut(&mut Self, T) -> fmt::Result,
+    ) -> fmt::Result {
+        self.out.write_char(open)?;
+        if items.len() != 0 {
+            self.depth += 1;
+            for (i, value) in items.enumerate() {
+                if i != 0 {
+                    self.out.write_char(',')?;
+                }
+                self.newline()?;
+                item(self, value)?;
+            }
+            self.depth -= 1;
+            self.newline()?;
+        }
+        self.out.write_char(close)
+    }
+
+    pub(crate) fn compound(&mut self, compound: &NbtCompound) -> fmt::Result {
+        let mut entries: Vec<&(String, NbtValue)> = compound.iter().collect();
+        if self.style.sort_keys {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        self.seq(('{', '}'), entries.into_iter(), |p, (key, value)| {
+            p.key(key)?;
+            p.out.write_char(':')?;
+            p.space()?;
+            p.value(value)
+        })
+    }
+
+    pub(crate) fn list(&mut self, list: &NbtList) -> fmt::Result {
+        const BRACKETS: (char, char) = ('[', ']');
+        match list {
+            NbtList::End => self.out.write_str("[]"),
+            NbtList::Byte(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{}b", x)),
+            NbtList::Short(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{}s", x)),
+            NbtList::Int(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{}", x)),
+            NbtList::Long(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{}L", x)),
+            NbtList::Float(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{:?}f", x)),
+            NbtList::Double(v) => self.seq(BRACKETS, v.iter(), |p, x| write!(p.out, "{:?}d", x)),
+            NbtList::ByteArray(v) => self.seq(BRACKETS, v.iter(), |p, x| p.array('B', x, "b")),
+            NbtList::String(v) => self.seq(BRACKETS, v.iter(), |p, x| p.string(x)),
+            NbtList::List(v) => self.seq(BRACKETS, v.iter(), |p, x| p.list(x)),
+            NbtList::Compound(v) => self.seq(BRACKETS, v.iter(), |p, x| p.compound(x)),
+            NbtList::IntArray(v) => self.seq(BRACKETS, v.iter(), |p, x| p.array('I', x, "")),
+            NbtList::LongArray(v) => self.seq(BRACKETS, v.iter(), |p, x| p.array('L', x, "L")),
+        }
+    }
+
+    /// 浮点数用 `{:?}` 输出, 整数部分也会带上 `.0`
+    ///
+    /// NaN 和无穷大在 SNBT 里没法表示, 输出的 `NaNf` / `inff` / `-infd` 读回来不是原来的值
+    pub(crate) fn value(&mut self, value: &NbtValue) -> fmt::Result {
+        match value {
+            NbtValue::Byte(x) => write!(self.out, "{}b", x),
+            NbtValue::Short(x) => write!(self.out, "{}s", x),
+            NbtValue::Int(x) => write!(self.out, "{}", x),
+            NbtValue::Long(x) => write!(self.out, "{}L", x),
+            NbtValue::Float(x) => write!(self.out, "{:?}f", x),
+            NbtValue::Double(x) => write!(self.out, "{:?}d", x),
+            NbtValue::ByteArray(x) => self.array('B', x, "b"),
+            NbtValue::String(x) => self.string(x),
+            NbtValue::List(x) => self.list(x),
+            NbtValue::Compound(x) => self.compound(x),
+            NbtValue::IntArray(x) => self.array('I', x, ""),
+            NbtValue::LongArray(x) => self.array('L', x, "L"),
+        }
+    }
+}
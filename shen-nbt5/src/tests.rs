@@ -1,4 +1,6 @@
-use crate::{nbt_version, NbtReader, NbtTypeConversion, NbtValue};
+use crate::{
+    nbt_version, NbtCompound, NbtDocument, NbtList, NbtReader, NbtTypeConversion, NbtValue,
+};
 
 /// 生成测试数据
 pub fn gen_datas(len: usize) -> Vec<u8> {
@@ -108,6 +110,34 @@ mod safe_test {
         assert_eq!(reader.cursor, 24);
     }
 
+    /// 批量读取要和逐个读取结果一致
+    ///
+    /// 长度不是 8 的倍数, 起始地址也不对齐
+    #[test]
+    fn read_array_bulk() {
+        let mut data = gen_datas(1 + 8 * 37);
+        let mut reader = NbtReader::new(&mut data);
+        reader.roll_down(1);
+        let be_i32 = reader.read_be_i32_array(37);
+        reader.roll_back(37 * 4);
+        let expect = (0..37).map(|_| reader.read_be_i32()).collect::<Vec<_>>();
+        assert_eq!(be_i32, expect);
+
+        reader.cursor = 1;
+        let le_i64 = reader.read_le_i64_array(37);
+        assert_eq!(reader.cursor, 1 + 8 * 37);
+        reader.cursor = 1;
+        let expect = (0..37).map(|_| reader.read_le_i64()).collect::<Vec<_>>();
+        assert_eq!(le_i64, expect);
+
+        reader.cursor = 1;
+        let le_i16 = reader.read_le_i16_array(37);
+        reader.cursor = 1;
+        let expect = (0..37).map(|_| reader.read_le_i16()).collect::<Vec<_>>();
+        assert_eq!(le_i16, expect);
+        assert!(reader.read_be_i64_array(0).is_empty());
+    }
+
     #[test]
     fn read_string() {
         let mut data = Vec::with_capacity(20);
@@ -281,6 +311,27 @@ mod unsafe_test {
         }
     }
 
+    #[test]
+    fn read_le_array() {
+        let mut value = gen_datas(1 + 8 * 100);
+        let mut reader = NbtReader::new(&mut value);
+        reader.roll_down(1);
+        unsafe {
+            let value = reader.read_le_i16_array_unsafe(100);
+            reader.roll_back(100 * 2);
+            assert_eq!(value, reader.read_le_i16_array(100));
+            reader.cursor = 1;
+            let value = reader.read_le_i32_array_unsafe(100);
+            reader.roll_back(100 * 4);
+            assert_eq!(value, reader.read_le_i32_array(100));
+            reader.cursor = 1;
+            let value = reader.read_le_i64_array_unsafe(100);
+            reader.roll_back(100 * 8);
+            assert_eq!(value, reader.read_le_i64_array(100));
+            assert_eq!(reader.cursor, 1 + 8 * 100);
+        }
+    }
+
     /// 未对齐的地址
     #[test]
     fn unaligned_read_u16_array() {
@@ -334,11 +385,13 @@ mod nbt {
             0x08, 0x00, 0x04, 0x6E, 0x61, 0x6D, 0x65, 0x00, 0x09, 0x42, 0x61, 0x6E, 0x61, 0x6E,
             0x72, 0x61, 0x6D, 0x61, 0x00,
         ];
-        let data = NbtValue::from_binary::<nbt_version::Java>(&mut data);
+        let data = NbtDocument::from_binary::<nbt_version::Java>(&mut data);
         println!("{:?}", data);
-        let correct_data = NbtValue::Compound(
-            Some("hello world".to_string()),
-            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))],
+        let correct_data = NbtDocument::with_name(
+            "hello world",
+            NbtValue::Compound(
+                vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))].into(),
+            ),
         );
         assert_eq!(data, Ok(correct_data));
     }
@@ -349,20 +402,21 @@ mod nbt {
             0x0A, 0x08, 0x00, 0x04, 0x6E, 0x61, 0x6D, 0x65, 0x00, 0x09, 0x42, 0x61, 0x6E, 0x61,
             0x6E, 0x72, 0x61, 0x6D, 0x61, 0x00,
         ];
-        let data = NbtValue::from_binary::<nbt_version::JavaNetAfter1_20_2>(&mut data);
+        let data = NbtDocument::from_binary::<nbt_version::JavaNetAfter1_20_2>(&mut data);
         println!("{:?}", data);
-        let correct_data = NbtValue::Compound(
-            None,
-            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))],
-        );
+        let correct_data = NbtDocument::new(NbtValue::Compound(
+            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))].into(),
+        ));
         assert_eq!(data, Ok(correct_data))
     }
 
     #[test]
     fn hello_write() {
-        let data = NbtValue::Compound(
-            Some("hello world".to_string()),
-            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))],
+        let data = NbtDocument::with_name(
+            "hello world",
+            NbtValue::Compound(
+                vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))].into(),
+            ),
         );
         // 写入
         let mut buff: Vec<u8> = Vec::new();
@@ -379,8 +433,7 @@ mod nbt {
     #[test]
     fn hello_write_java_net() {
         let data = NbtValue::Compound(
-            None,
-            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))],
+            vec![("name".to_string(), NbtValue::String("Bananrama".to_string()))].into(),
         );
         // 写入
         let mut buff: Vec<u8> = Vec::new();
@@ -508,9 +561,13 @@ mod nbt {
             0x6F, 0x75, 0x62, 0x6C, 0x65, 0x54, 0x65, 0x73, 0x74, 0x3F, 0xDF, 0x8F, 0x6B, 0xBB,
             0xFF, 0x6A, 0x5E, 0x00,
         ];
-        let value = NbtValue::from_binary::<nbt_version::Java>(&mut data);
+        let value = NbtDocument::from_binary::<nbt_version::Java>(&mut data);
         println!("{:?}", value);
         assert!(value.is_ok());
+        // 写回去应该完全一致, 包括根节点的名字
+        let value = value.unwrap();
+        assert_eq!(value.encoded_len::<nbt_version::Java>(), Ok(data.len()));
+        assert_eq!(value.to_binary::<nbt_version::Java>().unwrap(), data);
         // 其他版本
     }
 
@@ -575,3 +632,3440 @@ mod nbt {
         );
     }
 }
+
+mod encoded_len {
+    use super::*;
+    use crate::nbt_version::{
+        BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtReadTrait, NbtWriteTrait,
+    };
+    use crate::writer::{var_i32_len, var_i32_to_bytes, var_i64_len, var_i64_to_bytes};
+
+    /// 什么都有一点的测试数据
+    fn gen_value() -> NbtValue {
+        NbtValue::Compound(
+            vec![
+                ("byte".to_string(), NbtValue::Byte(-1)),
+                ("short".to_string(), NbtValue::Short(i16::MIN)),
+                ("int".to_string(), NbtValue::Int(i32::MIN)),
+                ("small int".to_string(), NbtValue::Int(-64)),
+                ("long".to_string(), NbtValue::Long(i64::MAX)),
+                ("float".to_string(), NbtValue::Float(1.5)),
+                ("double".to_string(), NbtValue::Double(-2.25)),
+                ("bytes".to_string(), NbtValue::ByteArray(vec![1, -2, 3])),
+                ("ints".to_string(), NbtValue::IntArray(vec![1, -2, 3])),
+                ("longs".to_string(), NbtValue::LongArray(vec![1, -2, 3])),
+                ("string".to_string(), NbtValue::String("啊？".repeat(50))),
+                ("empty list".to_string(), NbtValue::List(NbtList::Compound(vec![]))),
+                ("list".to_string(), NbtValue::List(NbtList::Long(vec![300, -300]))),
+                (
+                    "nested".to_string(),
+                    NbtValue::Compound(
+                        vec![(
+                            "list of compound".to_string(),
+                            NbtValue::List(NbtList::Compound(vec![
+                                NbtCompound::new(),
+                                vec![("a".to_string(), NbtValue::Int(1))].into(),
+                            ])),
+                        )]
+                        .into(),
+                    ),
+                ),
+            ]
+            .into(),
+        )
+    }
+
+    fn check<F: NbtWriteTrait + NbtReadTrait>(doc: &NbtDocument) {
+        let mut buff = doc.to_binary::<F>().unwrap();
+        assert_eq!(doc.encoded_len::<F>(), Ok(buff.len()));
+        // 只分配了一次
+        assert_eq!(buff.capacity(), buff.len());
+        // 写出来的东西也要读得回去
+        assert_eq!(NbtDocument::from_binary::<F>(&mut buff), Ok(doc.clone()));
+    }
+
+    #[test]
+    fn all_formats() {
+        let doc = NbtDocument::with_name("root", gen_value());
+        check::<Java>(&doc);
+        check::<BedrockDisk>(&doc);
+        check::<BedrockNetVarInt>(&doc);
+        // 1.20.2+ 网络格式没有名字
+        check::<JavaNetAfter1_20_2>(&NbtDocument::new(gen_value()));
+    }
+
+    #[test]
+    fn bedrock_list_root() {
+        let value = NbtValue::List(NbtList::Int(vec![i32::MAX, 0]));
+        check::<BedrockDisk>(&NbtDocument::new(value.clone()));
+        check::<BedrockNetVarInt>(&NbtDocument::new(value.clone()));
+        assert_eq!(value.encoded_len::<Java>(), Err(crate::NbtError::WrongRootType(9)));
+    }
+
+    #[test]
+    fn var_int_len() {
+        for value in [0, 1, 127, 128, 16383, 16384, i32::MAX, -1, i32::MIN] {
+            assert_eq!(var_i32_len(value), var_i32_to_bytes(value).len(), "{}", value);
+        }
+        for value in [0, 1, 127, 128, i32::MAX as i64 + 1, i64::MAX, -1, i64::MIN] {
+            assert_eq!(var_i64_len(value), var_i64_to_bytes(value).len(), "{}", value);
+        }
+        assert_eq!(var_i32_len(-1), 5);
+        assert_eq!(var_i64_len(-1), 10);
+    }
+}
+
+mod canonical {
+    use super::*;
+    use crate::nbt_version::{BedrockDisk, BedrockNetVarInt, Java};
+
+    fn gen_value(reverse: bool) -> NbtValue {
+        let mut inner = vec![
+            ("z".to_string(), NbtValue::Double(f64::NAN)),
+            ("a".to_string(), NbtValue::IntArray(vec![1, 2, 3])),
+        ];
+        if reverse {
+            inner.reverse();
+        }
+        let mut outer = vec![
+            ("list".to_string(), NbtValue::List(NbtList::Compound(vec![inner.into()]))),
+            ("float".to_string(), NbtValue::Float(f32::from_bits(0x7fc0_0001))),
+            ("empty".to_string(), NbtValue::List(NbtList::End)),
+            ("b".to_string(), NbtValue::Byte(1)),
+        ];
+        if reverse {
+            outer.reverse();
+        }
+        NbtValue::Compound(outer.into())
+    }
+
+    #[test]
+    fn key_order() {
+        let a = gen_value(false);
+        let b = gen_value(true);
+        assert_ne!(a.to_binary::<Java>().unwrap(), b.to_binary::<Java>().unwrap());
+        assert_eq!(
+            a.to_canonical_binary::<Java>().unwrap(),
+            b.to_canonical_binary::<Java>().unwrap()
+        );
+        assert_eq!(a.content_hash(), b.content_hash());
+        // 规范化之后 key 是有序的
+        let NbtValue::Compound(data) = a.to_canonical() else {
+            unreachable!()
+        };
+        let keys = data.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["b", "empty", "float", "list"]);
+    }
+
+    #[test]
+    fn nan() {
+        let value = gen_value(false).to_canonical();
+        let NbtValue::Compound(data) = value else {
+            unreachable!()
+        };
+        assert_eq!(data.entries()[2].1.as_f32().unwrap().to_bits(), f32::NAN.to_bits());
+    }
+
+    #[test]
+    fn source_format() {
+        let value = gen_value(false);
+        let hash = value.content_hash();
+        let mut java = value.to_binary::<Java>().unwrap();
+        let mut disk = value.to_binary::<BedrockDisk>().unwrap();
+        let mut net = value.to_binary::<BedrockNetVarInt>().unwrap();
+        let from_java = NbtValue::from_binary::<Java>(&mut java).unwrap();
+        let from_disk = NbtValue::from_binary::<BedrockDisk>(&mut disk).unwrap();
+        let from_net = NbtValue::from_binary::<BedrockNetVarInt>(&mut net).unwrap();
+        assert_eq!(from_java.content_hash(), hash);
+        assert_eq!(from_disk.content_hash(), hash);
+        assert_eq!(from_net.content_hash(), hash);
+    }
+
+    #[test]
+    fn type_matters() {
+        assert_ne!(NbtValue::Int(1).content_hash(), NbtValue::Long(1).content_hash());
+        assert_ne!(NbtValue::Int(1).content_hash(), NbtValue::Int(2).content_hash());
+        assert_ne!(
+            NbtValue::List(NbtList::End).content_hash(),
+            NbtValue::IntArray(vec![]).content_hash()
+        );
+        // 固定值, 保证跨版本稳定
+        assert_eq!(NbtValue::Int(1).content_hash(), 0xf16b_3403_a27b_5565);
+    }
+}
+
+mod write_array {
+    use crate::writer::{
+        extend_be_i32_array, extend_be_i64_array, extend_i8_array, extend_le_i32_array,
+        extend_le_i64_array,
+    };
+
+    #[test]
+    fn same_as_per_element() {
+        let ints = (0..100).map(|i| i * 0x0102_0304 - 7).collect::<Vec<i32>>();
+        let longs = (0..100).map(|i| i * 0x0102_0304_0506_0708 - 7).collect::<Vec<i64>>();
+        let bytes = (0..100).map(|i| i as i8).collect::<Vec<i8>>();
+        // 先写一个字节, 让后面的数据不对齐
+        let mut buff = vec![0xAA];
+        extend_be_i32_array(&mut buff, &ints);
+        extend_le_i32_array(&mut buff, &ints);
+        extend_be_i64_array(&mut buff, &longs);
+        extend_le_i64_array(&mut buff, &longs);
+        extend_i8_array(&mut buff, &bytes);
+
+        let mut expect = vec![0xAA];
+        ints.iter().for_each(|x| expect.extend_from_slice(&x.to_be_bytes()));
+        ints.iter().for_each(|x| expect.extend_from_slice(&x.to_le_bytes()));
+        longs.iter().for_each(|x| expect.extend_from_slice(&x.to_be_bytes()));
+        longs.iter().for_each(|x| expect.extend_from_slice(&x.to_le_bytes()));
+        bytes.iter().for_each(|x| expect.push(*x as u8));
+        assert_eq!(buff, expect);
+    }
+
+    #[test]
+    fn empty() {
+        let mut buff = Vec::new();
+        extend_be_i32_array(&mut buff, &[]);
+        extend_le_i64_array(&mut buff, &[]);
+        extend_i8_array(&mut buff, &[]);
+        assert!(buff.is_empty());
+    }
+}
+
+mod stream_writer {
+    use super::*;
+    use crate::nbt_version::{
+        BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtWriteTrait, RootName,
+    };
+    use crate::writer::NbtWriter;
+    use crate::{NbtError, NbtResult, NbtTypeId};
+
+    fn expect_value() -> NbtValue {
+        NbtValue::Compound(
+            vec![
+                ("byte".to_string(), NbtValue::Byte(-1)),
+                ("short".to_string(), NbtValue::Short(-300)),
+                ("int".to_string(), NbtValue::Int(-70000)),
+                ("long".to_string(), NbtValue::Long(1 << 40)),
+                ("float".to_string(), NbtValue::Float(0.5)),
+                ("double".to_string(), NbtValue::Double(-1.25)),
+                ("bytes".to_string(), NbtValue::ByteArray(vec![1, -2, 3])),
+                ("name".to_string(), NbtValue::String("shenjack".to_string())),
+                ("ints".to_string(), NbtValue::IntArray(vec![1, -2, 3])),
+                ("longs".to_string(), NbtValue::LongArray(vec![-1, 2])),
+                ("pos".to_string(), NbtValue::List(NbtList::Double(vec![1.0, 2.0]))),
+                ("empty".to_string(), NbtValue::List(NbtList::End)),
+                (
+                    "items".to_string(),
+                    NbtValue::List(NbtList::Compound(vec![
+                        vec![("id".to_string(), NbtValue::Int(1))].into(),
+                        NbtCompound::new(),
+                    ])),
+                ),
+                (
+                    "nested".to_string(),
+                    NbtValue::List(NbtList::List(vec![NbtList::Long(vec![5])])),
+                ),
+                (
+                    "sub".to_string(),
+                    NbtValue::Compound(vec![("x".to_string(), NbtValue::Short(7))].into()),
+                ),
+            ]
+            .into(),
+        )
+    }
+
+    fn write_stream<W: NbtWriteTrait>(name: Option<&str>) -> NbtResult<Vec<u8>> {
+        let mut writer = NbtWriter::<W>::new();
+        writer.begin_root_compound(name)?;
+        writer
+            .field_i8("byte", -1)?
+            .field_i16("short", -300)?
+            .field_i32("int", -70000)?
+            .field_i64("long", 1 << 40)?
+            .field_f32("float", 0.5)?
+            .field_f64("double", -1.25)?
+            .field_i8_array("bytes", &[1, -2, 3])?
+            .field_string("name", "shenjack")?
+            .field_i32_array("ints", &[1, -2, 3])?
+            .field_i64_array("longs", &[-1, 2])?;
+        writer.begin_list("pos", 6, 2)?.item_f64(1.0)?.item_f64(2.0)?.end()?;
+        writer.begin_list("empty", 0, 0)?.end()?;
+        writer.begin_list("items", 10, 2)?;
+        writer.begin_compound_item()?.field_i32("id", 1)?.end()?;
+        writer.begin_compound_item()?.end()?;
+        writer.end()?;
+        writer.begin_list("nested", 9, 1)?;
+        writer.begin_list_item(4, 1)?.item_value(&NbtValue::Long(5))?.end()?;
+        writer.end()?;
+        writer.field_value(
+            "sub",
+            &NbtValue::Compound(vec![("x".to_string(), NbtValue::Short(7))].into()),
+        )?;
+        writer.end()?;
+        writer.finish()
+    }
+
+    #[test]
+    fn same_as_to_binary() {
+        let doc = NbtDocument::with_name("root", expect_value());
+        assert_eq!(write_stream::<Java>(Some("root")).unwrap(), doc.to_binary::<Java>().unwrap());
+        assert_eq!(
+            write_stream::<BedrockDisk>(Some("root")).unwrap(),
+            doc.to_binary::<BedrockDisk>().unwrap()
+        );
+        assert_eq!(
+            write_stream::<BedrockNetVarInt>(Some("root")).unwrap(),
+            doc.to_binary::<BedrockNetVarInt>().unwrap()
+        );
+        assert_eq!(
+            write_stream::<JavaNetAfter1_20_2>(None).unwrap(),
+            doc.root.to_binary::<JavaNetAfter1_20_2>().unwrap()
+        );
+        // Java 的根节点必须有名字
+        assert!(matches!(write_stream::<Java>(None), Err(NbtError::RootWithoutName)));
+        assert_eq!(write_stream::<Java>(Some("")).unwrap(), doc.root.to_binary::<Java>().unwrap());
+        assert!(matches!(
+            write_stream::<JavaNetAfter1_20_2>(Some("root")),
+            Err(NbtError::RootNameForbidden(_))
+        ));
+    }
+
+    #[test]
+    fn bedrock_list_root() {
+        let mut writer = NbtWriter::<BedrockDisk>::new();
+        writer.begin_root_list(3, 2).unwrap().item_i32(1).unwrap().item_i32(2).unwrap();
+        writer.end().unwrap();
+        let value = NbtValue::List(NbtList::Int(vec![1, 2]));
+        assert_eq!(writer.finish().unwrap(), value.to_binary::<BedrockDisk>().unwrap());
+
+        let mut writer = NbtWriter::<Java>::new();
+        assert!(matches!(writer.begin_root_list(3, 0), Err(NbtError::WrongRootType(9))));
+    }
+
+    #[test]
+    fn wrong_item_type() {
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap().begin_list("list", 3, 2).unwrap();
+        assert!(matches!(
+            writer.item_i64(1),
+            Err(NbtError::ListTypeNotSame(types)) if types == vec![3, 4]
+        ));
+        assert!(matches!(writer.field_i32("x", 1), Err(NbtError::IncorrectType(10, 9))));
+        // 出错之后还能接着写
+        writer.item_i32(1).unwrap().item_i32(2).unwrap().end().unwrap().end().unwrap();
+        let value = NbtValue::Compound(
+            vec![("list".to_string(), NbtValue::List(NbtList::Int(vec![1, 2])))].into(),
+        );
+        assert_eq!(writer.finish().unwrap(), value.to_binary::<Java>().unwrap());
+    }
+
+    #[test]
+    fn wrong_item_count() {
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap().begin_compound("a").unwrap();
+        writer.begin_list("list", 8, 1).unwrap();
+        writer.item_string("a").unwrap();
+        // 写多了
+        assert!(matches!(
+            writer.item_string("b"),
+            Err(NbtError::InvalidIndex(path, 1)) if path == "a.list"
+        ));
+
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap().begin_list("list", 9, 1).unwrap();
+        writer.begin_list_item(8, 2).unwrap().item_string("a").unwrap();
+        // 写少了, 不会结束
+        assert!(matches!(
+            writer.end(),
+            Err(NbtError::InvalidIndex(path, 1)) if path == "list[0]"
+        ));
+        assert_eq!(writer.depth(), 3);
+        writer.item_string("b").unwrap().end().unwrap();
+        assert_eq!(writer.depth(), 2);
+    }
+
+    #[test]
+    fn wrong_nesting() {
+        let mut writer = NbtWriter::<Java>::new();
+        // 没有根节点
+        assert!(matches!(writer.field_i32("x", 1), Err(NbtError::IncorrectType(10, 0))));
+        assert!(matches!(writer.item_i32(1), Err(NbtError::IncorrectType(9, 0))));
+        assert!(matches!(writer.end(), Err(NbtError::IncorrectType(10, 0))));
+        assert!(matches!(writer.begin_compound("x"), Err(NbtError::IncorrectType(10, 0))));
+        writer
+            .begin_root_compound(Some(""))
+            .unwrap()
+            .begin_list("items", 10, 1)
+            .unwrap();
+        writer.begin_compound_item().unwrap();
+        // Compound 里不能写没有名字的值
+        assert!(matches!(writer.item_i32(1), Err(NbtError::IncorrectType(9, 10))));
+        writer.end().unwrap().end().unwrap().end().unwrap();
+        // 只能有一个根节点
+        assert!(matches!(
+            writer.begin_root_compound(Some("")),
+            Err(NbtError::IncorrectType(0, 10))
+        ));
+        assert!(matches!(writer.end(), Err(NbtError::IncorrectType(10, 0))));
+        assert!(matches!(writer.field_i32("x", 1), Err(NbtError::IncorrectType(10, 0))));
+
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap().begin_compound("a.b").unwrap();
+        // 根节点只能在最外层
+        assert!(matches!(
+            writer.begin_root_compound(Some("")),
+            Err(NbtError::IncorrectType(0, 10))
+        ));
+        writer.begin_list("l", 1, 0).unwrap();
+        assert!(matches!(writer.begin_root_list(1, 0), Err(NbtError::IncorrectType(0, 9))));
+        // 没有结束
+        assert!(matches!(writer.finish(), Err(NbtError::IncorrectType(0, 9))));
+        assert!(matches!(NbtWriter::<Java>::new().finish(), Err(NbtError::IncorrectType(10, 0))));
+    }
+
+    /// 写 Compound 的时候遇到 key 为 "bad" 就失败的格式
+    struct FailOnBad;
+
+    impl NbtWriteTrait for FailOnBad {
+        fn write_i16(writer: &mut Vec<u8>, data: i16) { Java::write_i16(writer, data) }
+        fn write_i32(writer: &mut Vec<u8>, data: i32) { Java::write_i32(writer, data) }
+        fn write_i64(writer: &mut Vec<u8>, data: i64) { Java::write_i64(writer, data) }
+        fn write_f32(writer: &mut Vec<u8>, data: f32) { Java::write_f32(writer, data) }
+        fn write_f64(writer: &mut Vec<u8>, data: f64) { Java::write_f64(writer, data) }
+        fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]) { Java::write_i8_array(writer, data) }
+        fn write_i32_array(writer: &mut Vec<u8>, data: &[i32]) {
+            Java::write_i32_array(writer, data)
+        }
+        fn write_i64_array(writer: &mut Vec<u8>, data: &[i64]) {
+            Java::write_i64_array(writer, data)
+        }
+        fn write_nbt_string(writer: &mut Vec<u8>, data: &str) {
+            Java::write_nbt_string(writer, data)
+        }
+        fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()> {
+            Java::write_list(writer, data)
+        }
+        fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()> {
+            for (key, value) in data {
+                if key == "bad" {
+                    return Err(NbtError::UnknownErr(key.clone()));
+                }
+                writer.push(value.tag());
+                Self::write_nbt_string(writer, key);
+                Self::write_compound(writer, &NbtCompound::new())?;
+            }
+            writer.push(0);
+            Ok(())
+        }
+        fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> { Java::root_name_rule(tag) }
+        fn i32_len(value: i32) -> usize { Java::i32_len(value) }
+        fn i64_len(value: i64) -> usize { Java::i64_len(value) }
+        fn nbt_string_len(data: &str) -> usize { Java::nbt_string_len(data) }
+    }
+
+    #[test]
+    fn rollback_on_error() {
+        let bad = NbtValue::Compound(
+            vec![
+                ("ok".to_string(), NbtValue::Compound(NbtCompound::new())),
+                ("bad".to_string(), NbtValue::Compound(NbtCompound::new())),
+            ]
+            .into(),
+        );
+        let good = NbtValue::Compound(NbtCompound::new());
+
+        let mut writer = NbtWriter::<FailOnBad>::new();
+        writer.begin_root_compound(Some("")).unwrap();
+        // tag, key 和写了一半的内容都不会留下
+        assert!(writer.field_value("x", &bad).is_err());
+        writer.field_value("x", &good).unwrap();
+        writer.begin_list("list", 10, 1).unwrap();
+        assert!(writer.item_value(&bad).is_err());
+        writer.item_value(&good).unwrap().end().unwrap().end().unwrap();
+
+        let expect = NbtValue::Compound(
+            vec![
+                ("x".to_string(), good.clone()),
+                ("list".to_string(), NbtValue::List(NbtList::Compound(vec![NbtCompound::new()]))),
+            ]
+            .into(),
+        );
+        assert_eq!(writer.finish().unwrap(), expect.to_binary::<Java>().unwrap());
+    }
+
+    #[test]
+    fn wrong_list_tag() {
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap();
+        assert!(matches!(writer.begin_list("a", 13, 0), Err(NbtError::UnknownType(13))));
+        assert!(matches!(writer.begin_list("b", 0, 1), Err(NbtError::UnknownType(0))));
+    }
+
+    #[test]
+    fn list_too_long() {
+        let too_long = i32::MAX as usize + 1;
+        let mut writer = NbtWriter::<Java>::new();
+        writer.begin_root_compound(Some("")).unwrap();
+        assert!(matches!(
+            writer.begin_list("a", 1, too_long),
+            Err(NbtError::InvalidIndex(path, index)) if path == "a" && index == i32::MAX as i64
+        ));
+        writer.begin_list("b", 9, 1).unwrap();
+        assert!(matches!(
+            writer.begin_list_item(1, too_long),
+            Err(NbtError::InvalidIndex(path, _)) if path == "b[0]"
+        ));
+        // 出错的时候什么都没写, 也没有计数
+        writer
+            .begin_list_item(0, 0)
+            .unwrap()
+            .end()
+            .unwrap()
+            .end()
+            .unwrap()
+            .end()
+            .unwrap();
+        let value = NbtValue::Compound(
+            vec![("b".to_string(), NbtValue::List(NbtList::List(vec![NbtList::End])))].into(),
+        );
+        assert_eq!(writer.finish().unwrap(), value.to_binary::<Java>().unwrap());
+
+        let mut writer = NbtWriter::<BedrockDisk>::new();
+        assert!(matches!(writer.begin_root_list(1, too_long), Err(NbtError::InvalidIndex(_, _))));
+        assert_eq!(writer.depth(), 0);
+    }
+}
+
+mod empty_list {
+    use super::*;
+    use crate::nbt_version::{
+        BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtReadTrait, NbtWriteTrait,
+    };
+
+    fn gen_value() -> NbtValue {
+        NbtValue::Compound(
+            vec![
+                ("compounds".to_string(), NbtValue::List(NbtList::Compound(vec![]))),
+                ("doubles".to_string(), NbtValue::List(NbtList::Double(vec![]))),
+                ("end".to_string(), NbtValue::List(NbtList::End)),
+                (
+                    "nested".to_string(),
+                    NbtValue::List(NbtList::List(vec![NbtList::String(vec![])])),
+                ),
+            ]
+            .into(),
+        )
+    }
+
+    fn check<F: NbtWriteTrait + NbtReadTrait>() {
+        let value = gen_value();
+        let mut buff = value.to_binary::<F>().unwrap();
+        assert_eq!(NbtValue::from_binary::<F>(&mut buff).unwrap(), value);
+    }
+
+    #[test]
+    fn keep_tag() {
+        check::<Java>();
+        check::<JavaNetAfter1_20_2>();
+        check::<BedrockDisk>();
+        check::<BedrockNetVarInt>();
+    }
+
+    #[test]
+    fn bedrock_root() {
+        let value = NbtValue::List(NbtList::Compound(vec![]));
+        let mut buff = value.to_binary::<BedrockDisk>().unwrap();
+        assert_eq!(buff, vec![9, 10, 0, 0, 0, 0]);
+        assert_eq!(NbtValue::from_binary::<BedrockDisk>(&mut buff).unwrap(), value);
+    }
+
+    #[test]
+    fn canonical_to_end() {
+        let mut value = NbtValue::List(NbtList::Compound(vec![]));
+        value.canonicalize();
+        assert_eq!(value, NbtValue::List(NbtList::End));
+        assert_eq!(
+            NbtValue::List(NbtList::Compound(vec![])).content_hash(),
+            NbtValue::List(NbtList::End).content_hash()
+        );
+    }
+}
+
+mod typed_list {
+    use super::*;
+    use crate::nbt_version::{BedrockNetVarInt, Java};
+    use crate::NbtError;
+
+    #[test]
+    fn from_values() {
+        let list = NbtList::try_from(vec![NbtValue::Int(1), NbtValue::Int(2)]).unwrap();
+        assert_eq!(list, NbtList::Int(vec![1, 2]));
+        assert_eq!(list.clone().into_values(), vec![NbtValue::Int(1), NbtValue::Int(2)]);
+        assert_eq!(NbtList::try_from(Vec::<NbtValue>::new()), Ok(NbtList::End));
+        assert_eq!(
+            NbtList::try_from(vec![NbtValue::Int(1), NbtValue::Long(2)]),
+            Err(NbtError::ListTypeNotSame(vec![3, 4]))
+        );
+    }
+
+    #[test]
+    fn push_and_get() {
+        let mut list = NbtList::End;
+        list.push(NbtValue::String("a".to_string())).unwrap();
+        list.push(NbtValue::String("b".to_string())).unwrap();
+        assert_eq!(list.element_tag(), 8);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(1), Some(NbtValue::String("b".to_string())));
+        assert_eq!(list.get(2), None);
+        assert_eq!(list.push(NbtValue::Byte(1)), Err(NbtError::IncorrectType(8, 1)));
+        assert_eq!(list.as_strings().unwrap(), ["a", "b"]);
+        assert_eq!(list.as_i32s(), Err(NbtError::IncorrectType(3, 8)));
+        list.as_strings_mut().unwrap().clear();
+        assert_eq!(list, NbtList::String(vec![]));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(NbtList::empty(10), Ok(NbtList::Compound(vec![])));
+        assert_eq!(NbtList::empty(13), Err(NbtError::UnknownType(13)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = NbtValue::Compound(
+            vec![
+                ("bytes".to_string(), NbtValue::List(NbtList::Byte(vec![1, -1]))),
+                ("shorts".to_string(), NbtValue::List(NbtList::Short(vec![1, -300]))),
+                ("ints".to_string(), NbtValue::List(NbtList::Int(vec![1, -70000]))),
+                ("longs".to_string(), NbtValue::List(NbtList::Long(vec![1, i64::MIN]))),
+                ("floats".to_string(), NbtValue::List(NbtList::Float(vec![0.5, -1.0]))),
+                ("doubles".to_string(), NbtValue::List(NbtList::Double(vec![0.25, 1e300]))),
+                (
+                    "arrays".to_string(),
+                    NbtValue::List(NbtList::IntArray(vec![vec![1, 2], vec![]])),
+                ),
+                ("long arrays".to_string(), NbtValue::List(NbtList::LongArray(vec![vec![-1]]))),
+                ("byte arrays".to_string(), NbtValue::List(NbtList::ByteArray(vec![vec![3]]))),
+            ]
+            .into(),
+        );
+        let mut buff = value.to_binary::<Java>().unwrap();
+        assert_eq!(NbtValue::from_binary::<Java>(&mut buff).unwrap(), value);
+        let mut buff = value.to_binary::<BedrockNetVarInt>().unwrap();
+        assert_eq!(NbtValue::from_binary::<BedrockNetVarInt>(&mut buff).unwrap(), value);
+    }
+}
+
+mod compound {
+    use super::*;
+    use crate::nbt_version::Java;
+
+    fn gen_compound() -> NbtCompound {
+        vec![
+            ("a".to_string(), NbtValue::Int(1)),
+            ("b".to_string(), NbtValue::Int(2)),
+            ("c".to_string(), NbtValue::Int(3)),
+        ]
+        .into()
+    }
+
+    fn check_map(mut compound: NbtCompound) {
+        assert_eq!(compound.get("b"), Some(&NbtValue::Int(2)));
+        assert_eq!(compound.get("x"), None);
+        assert!(compound.contains_key("c"));
+        // 替换不改变位置
+        assert_eq!(compound.insert("a", NbtValue::Byte(0)), Some(NbtValue::Int(1)));
+        assert_eq!(compound.insert("d", NbtValue::Int(4)), None);
+        *compound.get_mut("d").unwrap() = NbtValue::Int(5);
+        let keys = compound.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(keys, ["a", "b", "c", "d"]);
+        // 删除之后顺序不变, 查找仍然正确
+        assert_eq!(compound.remove("b"), Some(NbtValue::Int(2)));
+        assert_eq!(compound.remove("b"), None);
+        assert_eq!(compound.get("c"), Some(&NbtValue::Int(3)));
+        assert_eq!(compound.get("d"), Some(&NbtValue::Int(5)));
+        compound.retain(|k, _| k != "a");
+        assert_eq!(compound.get("a"), None);
+        assert_eq!(compound.get("c"), Some(&NbtValue::Int(3)));
+        compound.extend(vec![("c", NbtValue::Int(6)), ("e", NbtValue::Int(7))]);
+        assert_eq!(
+            compound.into_entries(),
+            vec![
+                ("c".to_string(), NbtValue::Int(6)),
+                ("d".to_string(), NbtValue::Int(5)),
+                ("e".to_string(), NbtValue::Int(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn map_api() {
+        check_map(gen_compound());
+        check_map(gen_compound().with_index());
+    }
+
+    #[test]
+    fn index_not_in_eq() {
+        assert_eq!(gen_compound(), gen_compound().with_index());
+        let mut compound = gen_compound().with_index();
+        assert!(compound.has_index());
+        compound.drop_index();
+        assert!(!compound.has_index());
+    }
+
+    #[test]
+    fn duplicate_key() {
+        // 读到的重复 key 原样保留, 查找返回第一个
+        let mut compound = NbtCompound::new();
+        compound.push("a", NbtValue::Int(1));
+        compound.push("a", NbtValue::Int(2));
+        let indexed = compound.clone().with_index();
+        assert_eq!(compound.len(), 2);
+        assert_eq!(compound.get("a"), Some(&NbtValue::Int(1)));
+        assert_eq!(indexed.get("a"), Some(&NbtValue::Int(1)));
+        let value = NbtValue::Compound(compound);
+        let mut buff = value.to_binary::<Java>().unwrap();
+        assert_eq!(NbtValue::from_binary::<Java>(&mut buff).unwrap(), value);
+    }
+
+    #[test]
+    fn iter() {
+        let compound = gen_compound();
+        let collected = compound.iter().cloned().collect::<NbtCompound>();
+        assert_eq!(collected, compound);
+        let sum = (&compound).into_iter().map(|(_, v)| v.as_i32().unwrap()).sum::<i32>();
+        assert_eq!(sum, 6);
+        let mut compound = compound;
+        compound.values_mut().for_each(|v| *v = NbtValue::Int(0));
+        assert!(compound.into_iter().all(|(_, v)| v == NbtValue::Int(0)));
+    }
+}
+
+mod document {
+    use super::*;
+    use crate::nbt_version::{BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2};
+    use crate::NbtError;
+
+    fn gen_root() -> NbtValue {
+        NbtValue::Compound(vec![("a".to_string(), NbtValue::Int(1))].into())
+    }
+
+    #[test]
+    fn java_name() {
+        let doc = NbtDocument::with_name("Level", gen_root());
+        let mut buff = doc.to_binary::<Java>().unwrap();
+        assert_eq!(&buff[..8], &[10, 0, 5, b'L', b'e', b'v', b'e', b'l']);
+        assert_eq!(NbtDocument::from_binary::<Java>(&mut buff), Ok(doc));
+        // 空名字也是名字
+        let doc = NbtDocument::with_name("", gen_root());
+        let mut buff = doc.to_binary::<Java>().unwrap();
+        assert_eq!(&buff[..3], &[10, 0, 0]);
+        assert_eq!(NbtDocument::from_binary::<Java>(&mut buff), Ok(doc));
+    }
+
+    #[test]
+    fn root_name_rules() {
+        // Required: Java 文件必须有名字
+        let doc = NbtDocument::new(gen_root());
+        assert_eq!(doc.to_binary::<Java>(), Err(NbtError::RootWithoutName));
+        assert_eq!(doc.encoded_len::<Java>(), Err(NbtError::RootWithoutName));
+        assert_eq!(doc.write_to::<Java>(&mut Vec::new()), Err(NbtError::RootWithoutName));
+        // Optional: 基岩版没有名字写空字符串
+        let mut buff = doc.to_binary::<BedrockDisk>().unwrap();
+        assert_eq!(
+            NbtDocument::from_binary::<BedrockDisk>(&mut buff),
+            Ok(NbtDocument::with_name("", gen_root()))
+        );
+        // Forbidden: 1.20.2 之后的网络格式不能有名字
+        assert_eq!(
+            NbtDocument::with_name("", gen_root()).to_binary::<JavaNetAfter1_20_2>(),
+            Err(NbtError::RootNameForbidden("".to_string()))
+        );
+        assert_eq!(doc.to_binary::<JavaNetAfter1_20_2>().unwrap()[..2], [10, 3]);
+    }
+
+    #[test]
+    fn java_net_no_name() {
+        let doc = NbtDocument::with_name("Level", gen_root());
+        assert_eq!(
+            doc.to_binary::<JavaNetAfter1_20_2>(),
+            Err(NbtError::RootNameForbidden("Level".to_string()))
+        );
+        assert_eq!(
+            doc.encoded_len::<JavaNetAfter1_20_2>(),
+            Err(NbtError::RootNameForbidden("Level".to_string()))
+        );
+        let doc = NbtDocument::new(gen_root());
+        let mut buff = doc.to_binary::<JavaNetAfter1_20_2>().unwrap();
+        assert_eq!(buff[1], 3);
+        assert_eq!(NbtDocument::from_binary::<JavaNetAfter1_20_2>(&mut buff), Ok(doc));
+    }
+
+    #[test]
+    fn bedrock_list_root() {
+        let list = NbtValue::List(NbtList::Int(vec![1]));
+        assert_eq!(
+            NbtDocument::with_name("x", list.clone()).to_binary::<BedrockDisk>(),
+            Err(NbtError::RootNameForbidden("x".to_string()))
+        );
+        let doc = NbtDocument::new(list);
+        let mut buff = doc.to_binary::<BedrockNetVarInt>().unwrap();
+        assert_eq!(NbtDocument::from_binary::<BedrockNetVarInt>(&mut buff), Ok(doc));
+    }
+
+    #[test]
+    fn value_helpers() {
+        // NbtValue 上的方法不带名字, 必须有名字的格式写空字符串
+        let root = gen_root();
+        assert_eq!(
+            root.to_binary::<Java>().unwrap(),
+            NbtDocument::with_name("", root.clone()).to_binary::<Java>().unwrap()
+        );
+        assert_eq!(root.encoded_len::<Java>().unwrap(), root.to_binary::<Java>().unwrap().len());
+        assert_eq!(
+            root.to_binary::<BedrockDisk>().unwrap(),
+            NbtDocument::new(root.clone()).to_binary::<BedrockDisk>().unwrap()
+        );
+        assert_eq!(NbtDocument::from(root.clone()).root_name, None);
+        let mut buff = Vec::new();
+        root.write_to_with_name::<Java>("Level", &mut buff).unwrap();
+        assert_eq!(buff, NbtDocument::with_name("Level", root).to_binary::<Java>().unwrap());
+    }
+}
+
+mod accessor {
+    use super::*;
+    use crate::NbtError;
+
+    #[test]
+    fn borrow() {
+        let value = NbtValue::IntArray(vec![1, 2, 3]);
+        assert_eq!(value.as_i32_array_ref().unwrap(), &[1, 2, 3]);
+        assert_eq!(value.as_i64_array_ref(), Err(NbtError::IncorrectType(12, 11)));
+        let value = NbtValue::String("shenjack".to_string());
+        assert_eq!(value.as_string_ref(), Ok("shenjack"));
+        assert_eq!(NbtValue::Byte(1).as_i8_ref(), Ok(&1));
+    }
+
+    #[test]
+    fn borrow_mut() {
+        let mut value = NbtValue::Compound(vec![("a".to_string(), NbtValue::Int(1))].into());
+        let inner = value.as_compound_mut().unwrap().get_mut("a").unwrap();
+        *inner.as_i32_mut().unwrap() += 1;
+        assert_eq!(inner.as_i64_mut(), Err(NbtError::IncorrectType(4, 3)));
+        assert_eq!(value.as_compound_ref().unwrap().get("a"), Some(&NbtValue::Int(2)));
+        let mut value = NbtValue::List(NbtList::Int(vec![1]));
+        value.as_list_mut().unwrap().push(NbtValue::Int(2)).unwrap();
+        assert_eq!(value.as_list_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn into() {
+        assert_eq!(NbtValue::LongArray(vec![-1]).into_i64_array(), Ok(vec![-1]));
+        assert_eq!(NbtValue::Double(0.5).into_f64(), Ok(0.5));
+        assert_eq!(NbtValue::Double(0.5).into_f32(), Err(NbtError::IncorrectType(5, 6)));
+        assert_eq!(
+            NbtValue::List(NbtList::End).into_compound(),
+            Err(NbtError::IncorrectType(10, 9))
+        );
+    }
+}
+
+mod path {
+    use super::*;
+    use crate::{NbtError, NbtMut, NbtRef};
+
+    fn gen_value() -> NbtValue {
+        let item = |id: &str| -> NbtCompound {
+            vec![("id".to_string(), NbtValue::String(id.to_string()))].into()
+        };
+        let player = NbtValue::Compound(
+            vec![
+                (
+                    "Inventory".to_string(),
+                    NbtValue::List(NbtList::Compound(vec![item("stone"), item("dirt")])),
+                ),
+                ("Pos".to_string(), NbtValue::List(NbtList::Double(vec![1.0, 2.0, 3.0]))),
+                ("a.b".to_string(), NbtValue::Byte(1)),
+            ]
+            .into(),
+        );
+        NbtValue::Compound(
+            vec![(
+                "Data".to_string(),
+                NbtValue::Compound(vec![("Player".to_string(), player)].into()),
+            )]
+            .into(),
+        )
+    }
+
+    #[test]
+    fn get() {
+        let value = gen_value();
+        let id = value.get_path("Data.Player.Inventory[1].id").unwrap();
+        assert_eq!(id, NbtRef::Value(&NbtValue::String("dirt".to_string())));
+        let pos = value.get_path("Data.Player.Pos[-1]").unwrap();
+        assert_eq!(pos.to_value(), NbtValue::Double(3.0));
+        assert!(matches!(pos, NbtRef::Element(_, 2)));
+        assert_eq!(value.get_path("Data.Player.\"a.b\"").unwrap().tag(), 1);
+        assert_eq!(value.get_path("").unwrap(), NbtRef::Value(&value));
+        let inventory = value.get_path("Data.Player.Inventory").unwrap().as_list().unwrap();
+        assert_eq!(inventory.len(), 2);
+    }
+
+    #[test]
+    fn get_error() {
+        let value = gen_value();
+        assert_eq!(
+            value.get_path("Data.Player.Inventory[2].id"),
+            Err(NbtError::PathNotFound("Data.Player.Inventory[2]".to_string()))
+        );
+        assert_eq!(
+            value.get_path("Data.Player.Health"),
+            Err(NbtError::PathNotFound("Data.Player.Health".to_string()))
+        );
+        assert_eq!(
+            value.get_path("Data.Player.Pos.x"),
+            Err(NbtError::PathTypeMismatch("Data.Player.Pos.x".to_string(), 10, 9))
+        );
+        assert_eq!(
+            value.get_path("Data[0]"),
+            Err(NbtError::PathTypeMismatch("Data[0]".to_string(), 9, 10))
+        );
+        for bad in ["a..b", "a.", "a[x]", "a[1", "\"a", "a[0]b", "\"a\\n\""] {
+            assert!(matches!(value.get_path(bad), Err(NbtError::InvalidPath(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut value = gen_value();
+        let mut pos = value.get_path_mut("Data.Player.Pos[0]").unwrap();
+        assert_eq!(pos.set(NbtValue::Double(-1.0)), Ok(NbtValue::Double(1.0)));
+        assert_eq!(pos.set(NbtValue::Int(0)), Err(NbtError::IncorrectType(6, 3)));
+        let NbtMut::Value(id) = value.get_path_mut("Data.Player.Inventory[0].id").unwrap() else {
+            unreachable!()
+        };
+        id.as_string_mut().unwrap().push_str("_slab");
+        assert_eq!(
+            value.get_path("Data.Player.Pos[0]").unwrap().to_value(),
+            NbtValue::Double(-1.0)
+        );
+        assert_eq!(
+            value.get_path("Data.Player.Inventory[0].id").unwrap().to_value(),
+            NbtValue::String("stone_slab".to_string())
+        );
+    }
+
+    #[test]
+    fn set() {
+        let mut value = gen_value();
+        // 自动创建中间的 Compound
+        assert_eq!(value.set_path("Data.World.Time.day", NbtValue::Long(1)), Ok(None));
+        assert_eq!(value.get_path("Data.World.Time.day").unwrap().to_value(), NbtValue::Long(1));
+        assert_eq!(
+            value.set_path("Data.World.Time.day", NbtValue::Long(2)),
+            Ok(Some(NbtValue::Long(1)))
+        );
+        assert_eq!(value.set_path("Data.Player.Inventory[-1].Count", NbtValue::Byte(64)), Ok(None));
+        assert_eq!(
+            value.set_path("Data.Player.Pos[1]", NbtValue::Double(0.0)),
+            Ok(Some(NbtValue::Double(2.0)))
+        );
+        // List 的元素不会自动创建
+        assert_eq!(
+            value.set_path("Data.Player.Pos[3]", NbtValue::Double(0.0)),
+            Err(NbtError::PathNotFound("Data.Player.Pos[3]".to_string()))
+        );
+        assert_eq!(
+            value.set_path("Data.Missing[0].x", NbtValue::Byte(0)),
+            Err(NbtError::PathNotFound("Data.Missing".to_string()))
+        );
+        assert_eq!(value.set_path("Data.Player.a.b.c", NbtValue::Byte(0)), Ok(None));
+        assert_eq!(value.get_path("Data.Player.a.b").unwrap().tag(), 10);
+        assert_eq!(
+            value.set_path("Data.Player.\"a.b\".c", NbtValue::Byte(0)),
+            Err(NbtError::PathTypeMismatch("Data.Player.\"a.b\".c".to_string(), 10, 1))
+        );
+        assert!(matches!(value.set_path("", NbtValue::Byte(0)), Err(NbtError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn remove() {
+        let mut value = gen_value();
+        assert_eq!(
+            value.remove_path("Data.Player.Inventory[0].id"),
+            Ok(NbtValue::String("stone".to_string()))
+        );
+        assert_eq!(value.remove_path("Data.Player.Pos[-1]"), Ok(NbtValue::Double(3.0)));
+        assert_eq!(value.get_path("Data.Player.Pos").unwrap().as_list().unwrap().len(), 2);
+        assert_eq!(
+            value.remove_path("Data.Player.Pos[5]"),
+            Err(NbtError::PathNotFound("Data.Player.Pos[5]".to_string()))
+        );
+        assert_eq!(
+            value.remove_path("Data.Nothing.x"),
+            Err(NbtError::PathNotFound("Data.Nothing".to_string()))
+        );
+        assert!(value.remove_path("Data").is_ok());
+        assert_eq!(value, NbtValue::Compound(NbtCompound::new()));
+    }
+
+    #[test]
+    fn list_edit() {
+        let mut list = NbtList::End;
+        list.insert(0, NbtValue::Int(2)).unwrap();
+        list.insert(0, NbtValue::Int(1)).unwrap();
+        assert_eq!(list.insert(0, NbtValue::Byte(1)), Err(NbtError::IncorrectType(3, 1)));
+        assert_eq!(list.set(1, NbtValue::Int(3)), Ok(NbtValue::Int(2)));
+        assert_eq!(list.remove(0), NbtValue::Int(1));
+        assert_eq!(list.remove(0), NbtValue::Int(3));
+        // 删空了也保留类型
+        assert_eq!(list, NbtList::Int(vec![]));
+    }
+}
+
+mod nbt_path {
+    use super::*;
+    use crate::{NbtError, NbtPath, NbtRef, NbtResult};
+
+    fn snbt(src: &str) -> NbtValue { NbtValue::from_snbt(src).unwrap() }
+
+    fn path(src: &str) -> NbtPath { src.parse().unwrap() }
+
+    fn gen_player() -> NbtValue {
+        snbt(
+            r#"{
+                Health: 20.0f,
+                UUID: [I; 1, 2, 3, 4],
+                Tags: ["a", "b"],
+                Data: {Flag: 1b, x: 5},
+                Inventory: [
+                    {Slot: 0b, id: "minecraft:diamond", Count: 1b},
+                    {Slot: 1b, id: "minecraft:stone", Count: 64b},
+                    {Slot: 2b, id: "minecraft:diamond", Count: 3b}
+                ],
+                "quoted key": {x: 1}
+            }"#,
+        )
+    }
+
+    fn get_values(p: &str, value: &NbtValue) -> NbtResult<Vec<NbtValue>> {
+        path(p).get(value).map(|v| v.iter().map(NbtRef::to_value).collect())
+    }
+
+    #[test]
+    fn parse() {
+        for ok in [
+            "a",
+            "a.b",
+            "a[0]",
+            "a[-1][2]",
+            "a[]",
+            "a[{b:1}]",
+            "a{b:1}.c",
+            "{a:1}",
+            "{a:1}.b",
+            "\"a b\".c",
+            "'a\\'b'",
+            "a.b:c",
+        ] {
+            assert_eq!(path(ok).to_string(), ok);
+        }
+        for bad in ["", "a..b", "a.{b:1}", "a[x]", "a[0", "a b", "a[{b:}]", "\"a", "a[0]b"] {
+            assert!(matches!(NbtPath::parse(bad), Err(NbtError::InvalidPath(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn get() {
+        let player = gen_player();
+        assert_eq!(
+            get_values("Inventory[{id:\"minecraft:diamond\"}].Count", &player),
+            Ok(vec![NbtValue::Byte(1), NbtValue::Byte(3)])
+        );
+        assert_eq!(path("Inventory[]").count(&player), 3);
+        assert_eq!(get_values("Inventory[-1].Slot", &player), Ok(vec![NbtValue::Byte(2)]));
+        assert_eq!(get_values("UUID[1]", &player), Ok(vec![NbtValue::Int(2)]));
+        assert_eq!(path("UUID[]").count(&player), 4);
+        assert_eq!(get_values("Data{Flag:1b}.x", &player), Ok(vec![NbtValue::Int(5)]));
+        assert_eq!(get_values("'quoted key'.x", &player), Ok(vec![NbtValue::Int(1)]));
+        assert_eq!(path("{Health:20.0f}").count(&player), 1);
+        // List 只要包含就算匹配
+        assert_eq!(path("{Tags:[\"b\"]}").count(&player), 1);
+        assert_eq!(path("{Tags:[\"c\"]}").count(&player), 0);
+        assert_eq!(path("{Tags:[]}").count(&player), 0);
+        // 报错的路径到第一个没有匹配的节点为止
+        assert_eq!(
+            path("Inventory[5].Count").get(&player),
+            Err(NbtError::PathNotFound("Inventory[5]".to_string()))
+        );
+        assert_eq!(
+            path("Data{Flag:0b}.x").get(&player),
+            Err(NbtError::PathNotFound("Data{Flag:0b}".to_string()))
+        );
+        assert_eq!(path("Health.x").count(&player), 0);
+    }
+
+    #[test]
+    fn set() {
+        let mut player = gen_player();
+        let diamond_count = path("Inventory[{id:\"minecraft:diamond\"}].Count");
+        assert_eq!(diamond_count.set(&mut player, &NbtValue::Byte(64)), Ok(2));
+        // 没有变化
+        assert_eq!(diamond_count.set(&mut player, &NbtValue::Byte(64)), Ok(0));
+        assert_eq!(path("Inventory[].Count").set(&mut player, &NbtValue::Byte(64)), Ok(0));
+        // 中间缺少的节点会创建出来
+        assert_eq!(path("a.b.c").set(&mut player, &NbtValue::Int(1)), Ok(1));
+        assert_eq!(get_values("a.b.c", &player), Ok(vec![NbtValue::Int(1)]));
+        assert_eq!(path("list[]").set(&mut player, &NbtValue::Int(1)), Ok(1));
+        assert_eq!(get_values("list", &player), Ok(vec![snbt("[1]")]));
+        // 和游戏一样, 空 List 不管过滤条件直接放进去
+        let mut items = snbt("{Items: [], Other: []}");
+        assert_eq!(path("Items[{id:\"a\"}]").set(&mut items, &snbt("{id: b}")), Ok(1));
+        assert_eq!(path("Other[{id:\"a\"}]").set(&mut items, &NbtValue::Int(1)), Ok(1));
+        assert_eq!(items, snbt("{Items: [{id: b}], Other: [1]}"));
+        assert_eq!(path("Items[{id:\"a\"}]").set(&mut items, &snbt("{id: c}")), Ok(0));
+        // 放不进去就不算变化
+        let mut arrays = snbt("{a: [I;]}");
+        assert_eq!(path("a[]").set(&mut arrays, &snbt("x")), Ok(0));
+        assert_eq!(arrays, snbt("{a: [I;]}"));
+        // 没有匹配的元素时用过滤条件创建一个
+        let slot = path("Inventory[{Slot:5b}].Count");
+        assert_eq!(slot.set(&mut player, &NbtValue::Byte(7)), Ok(1));
+        assert_eq!(get_values("Inventory[-1]", &player), Ok(vec![snbt("{Slot:5b,Count:7b}")]));
+        // List 元素类型不对就不会改
+        assert_eq!(path("Tags[0]").set(&mut player, &NbtValue::Int(1)), Ok(0));
+        assert_eq!(path("Tags[0]").set(&mut player, &snbt("\"c\"")), Ok(1));
+        // 数组可以放任何数字
+        assert_eq!(path("UUID[]").set(&mut player, &NbtValue::Double(-1.5)), Ok(4));
+        assert_eq!(get_values("UUID", &player), Ok(vec![snbt("[I;-2,-2,-2,-2]")]));
+        // 根节点不能替换
+        assert_eq!(path("{}").set(&mut player, &NbtValue::Int(1)), Ok(0));
+        assert_eq!(
+            path("Inventory[9].Count").set(&mut player, &NbtValue::Byte(1)),
+            Err(NbtError::PathNotFound("Inventory[9]".to_string()))
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let mut player = gen_player();
+        let tags = path("Tags");
+        assert_eq!(tags.append(&mut player, &[snbt("\"z\"")]), Ok(1));
+        assert_eq!(tags.prepend(&mut player, &[snbt("\"0\"")]), Ok(1));
+        assert_eq!(tags.insert(&mut player, 1, &[snbt("\"x\""), snbt("\"y\"")]), Ok(1));
+        assert_eq!(get_values("Tags", &player), Ok(vec![snbt("[\"0\",x,y,a,b,z]")]));
+        // 类型不对
+        assert_eq!(tags.append(&mut player, &[NbtValue::Int(1)]), Ok(0));
+        assert_eq!(
+            tags.insert(&mut player, 7, &[snbt("\"x\"")]),
+            Err(NbtError::InvalidIndex("Tags".to_string(), 7))
+        );
+        assert_eq!(
+            tags.insert(&mut player, -8, &[snbt("\"x\"")]),
+            Err(NbtError::InvalidIndex("Tags".to_string(), -1))
+        );
+        // 用不到下标的时候不会报错
+        assert_eq!(tags.insert(&mut player, 7, &[NbtValue::Int(1)]), Ok(0));
+        assert_eq!(tags.insert(&mut player, 7, &[]), Ok(0));
+        assert_eq!(path("UUID").insert(&mut player, -2, &[NbtValue::Byte(9)]), Ok(1));
+        assert_eq!(get_values("UUID", &player), Ok(vec![snbt("[I;1,2,3,9,4]")]));
+        // 不存在的 List 会创建出来
+        assert_eq!(path("New.List").append(&mut player, &[NbtValue::Long(1)]), Ok(1));
+        assert_eq!(get_values("New.List", &player), Ok(vec![snbt("[1L]")]));
+
+        // 出错的时候前面的目标也不会改
+        let before = snbt("{a: [{l: [1]}, {l: [1, 2, 3]}, {l: 1}], b: [{l: [1, 2]}, {l: [1]}]}");
+        let mut value = before.clone();
+        assert_eq!(
+            path("a[].l").append(&mut value, &[NbtValue::Int(4)]),
+            Err(NbtError::ExpectedList("a[].l".to_string()))
+        );
+        assert_eq!(
+            path("b[].l").insert(&mut value, 2, &[NbtValue::Int(4)]),
+            Err(NbtError::InvalidIndex("b[].l".to_string(), 2))
+        );
+        assert_eq!(
+            path("c").append(&mut snbt("{c: {}}"), &[]),
+            Err(NbtError::ExpectedList("c".to_string()))
+        );
+        assert_eq!(value, before);
+        assert_eq!(path("b[].l").insert(&mut value, 1, &[NbtValue::Int(4)]), Ok(2));
+        assert_eq!(get_values("b[].l", &value), Ok(vec![snbt("[1,4,2]"), snbt("[1,4]")]));
+    }
+
+    #[test]
+    fn remove() {
+        let mut player = gen_player();
+        assert_eq!(path("Inventory[{id:\"minecraft:diamond\"}]").remove(&mut player), Ok(2));
+        assert_eq!(path("Inventory[].id").remove(&mut player), Ok(1));
+        assert_eq!(get_values("Inventory", &player), Ok(vec![snbt("[{Slot:1b,Count:64b}]")]));
+        assert_eq!(path("UUID[0]").remove(&mut player), Ok(1));
+        assert_eq!(path("UUID[]").remove(&mut player), Ok(3));
+        assert_eq!(path("Data{Flag:0b}").remove(&mut player), Ok(0));
+        assert_eq!(path("Data{Flag:1b}").remove(&mut player), Ok(1));
+        assert_eq!(path("Data").remove(&mut player), Ok(0));
+        assert_eq!(
+            path("Missing.x").remove(&mut player),
+            Err(NbtError::PathNotFound("Missing".to_string()))
+        );
+        // 删空了的 List 没有元素类型
+        assert_eq!(path("Tags[]").remove(&mut player), Ok(2));
+        assert_eq!(get_values("Tags", &player), Ok(vec![NbtValue::List(NbtList::End)]));
+    }
+
+    #[test]
+    fn snbt_fixture() {
+        assert_eq!(
+            snbt("{a:1b,b:2s,c:3,d:4L,e:5.0f,f:6.5,g:7d,h:true,i:abc,j:'q\"',k:[B;1b]}"),
+            NbtValue::Compound(
+                vec![
+                    ("a".to_string(), NbtValue::Byte(1)),
+                    ("b".to_string(), NbtValue::Short(2)),
+                    ("c".to_string(), NbtValue::Int(3)),
+                    ("d".to_string(), NbtValue::Long(4)),
+                    ("e".to_string(), NbtValue::Float(5.0)),
+                    ("f".to_string(), NbtValue::Double(6.5)),
+                    ("g".to_string(), NbtValue::Double(7.0)),
+                    ("h".to_string(), NbtValue::Byte(1)),
+                    ("i".to_string(), NbtValue::String("abc".to_string())),
+                    ("j".to_string(), NbtValue::String("q\"".to_string())),
+                    ("k".to_string(), NbtValue::ByteArray(vec![1])),
+                ]
+                .into()
+            )
+        );
+    }
+}
+
+mod snbt {
+    use super::*;
+    use crate::NbtError;
+
+    fn parse(src: &str) -> NbtValue { NbtValue::from_snbt(src).unwrap() }
+
+    fn error_at(src: &str) -> (usize, usize) {
+        match NbtValue::from_snbt(src) {
+            Err(NbtError::InvalidSnbt(line, column, _)) => (line, column),
+            other => panic!("{:?} -> {:?}", src, other),
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        for (src, value) in [
+            ("1b", NbtValue::Byte(1)),
+            ("-128B", NbtValue::Byte(-128)),
+            ("2s", NbtValue::Short(2)),
+            ("3", NbtValue::Int(3)),
+            ("+3i", NbtValue::Int(3)),
+            ("4L", NbtValue::Long(4)),
+            ("-9223372036854775808l", NbtValue::Long(i64::MIN)),
+            ("1.5f", NbtValue::Float(1.5)),
+            ("1f", NbtValue::Float(1.0)),
+            ("1.5", NbtValue::Double(1.5)),
+            ("2d", NbtValue::Double(2.0)),
+            (".5", NbtValue::Double(0.5)),
+            ("1.", NbtValue::Double(1.0)),
+            ("1e3", NbtValue::Double(1000.0)),
+            ("-2.5E-1f", NbtValue::Float(-0.25)),
+            ("1_000_000", NbtValue::Int(1_000_000)),
+            ("1_000.000_5", NbtValue::Double(1000.0005)),
+            ("0x10", NbtValue::Int(16)),
+            ("0xFFFFFFFF", NbtValue::Int(-1)),
+            ("0x7fffs", NbtValue::Short(i16::MAX)),
+            ("0x1b", NbtValue::Int(0x1b)),
+            ("0x1ub", NbtValue::Byte(1)),
+            ("-0x10", NbtValue::Int(-16)),
+            ("0b101", NbtValue::Int(5)),
+            ("0b1111_1111b", NbtValue::Byte(-1)),
+            ("0b", NbtValue::Byte(0)),
+            ("255ub", NbtValue::Byte(-1)),
+            ("127sb", NbtValue::Byte(127)),
+            ("65535us", NbtValue::Short(-1)),
+            ("4294967295ui", NbtValue::Int(-1)),
+            ("18446744073709551615ul", NbtValue::Long(-1)),
+            ("true", NbtValue::Byte(1)),
+            ("false", NbtValue::Byte(0)),
+        ] {
+            assert_eq!(parse(src), value, "{}", src);
+        }
+        // 看起来不像数字的就是字符串
+        for src in ["01", "1_", "_1", "1__", "0x", "1u", "1e", "1.2.3", "True", "1ubs", "-"] {
+            assert_eq!(parse(src), NbtValue::String(src.to_string()), "{}", src);
+        }
+        for src in [
+            "128b",
+            "-129b",
+            "256ub",
+            "-1ub",
+            "0x7fff_ffffs",
+            "2147483648",
+            "1e999",
+            "3.5e38f",
+        ] {
+            assert_eq!(error_at(src), (1, 1), "{}", src);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        for (src, value) in [
+            (r#""a b""#, "a b"),
+            (r#"'a"b'"#, "a\"b"),
+            (r#""a\"b""#, "a\"b"),
+            (r#""a\'b""#, "a'b"),
+            (r#"'\\'"#, "\\"),
+            (r#""\b\s\t\n\f\r""#, "\u{8} \t\n\u{c}\r"),
+            (r#""\x41中\U0001F600""#, "A中😀"),
+            ("a-b_c.d+e", "a-b_c.d+e"),
+        ] {
+            assert_eq!(parse(src), NbtValue::String(value.to_string()), "{}", src);
+        }
+        assert_eq!(error_at(r#""ab\q""#), (1, 4));
+        assert_eq!(error_at(r#""\x4""#), (1, 2));
+        assert_eq!(error_at(r#""\uD800""#), (1, 2));
+        assert_eq!(error_at("\"abc"), (1, 5));
+    }
+
+    #[test]
+    fn collections() {
+        assert_eq!(
+            parse(
+                r#"{ a : 1b, "b c": [I; 1, 2], 'd': [B;], e: [L; 1, 2l], f: [[], [1s]], g: {} }"#
+            ),
+            NbtValue::Compound(
+                vec![
+                    ("a".to_string(), NbtValue::Byte(1)),
+                    ("b c".to_string(), NbtValue::IntArray(vec![1, 2])),
+                    ("d".to_string(), NbtValue::ByteArray(vec![])),
+                    ("e".to_string(), NbtValue::LongArray(vec![1, 2])),
+                    (
+                        "f".to_string(),
+                        NbtValue::List(NbtList::List(vec![NbtList::End, NbtList::Short(vec![1])])),
+                    ),
+                    ("g".to_string(), NbtValue::Compound(NbtCompound::new())),
+                ]
+                .into()
+            )
+        );
+        // 结尾可以多一个逗号
+        assert_eq!(parse("[1,2,]"), NbtValue::List(NbtList::Int(vec![1, 2])));
+        assert_eq!(parse("[B; 0xFF, 1b, true]"), NbtValue::ByteArray(vec![-1, 1, 1]));
+        assert_eq!(parse("{a:1,a:2}"), parse("{a:2}"));
+        assert_eq!(
+            parse("{'':1}"),
+            NbtValue::Compound(vec![(String::new(), NbtValue::Int(1))].into())
+        );
+        assert_eq!("[]".parse::<NbtValue>(), Ok(NbtValue::List(NbtList::End)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error_at(""), (1, 1));
+        assert_eq!(error_at("{a:1} x"), (1, 7));
+        assert_eq!(error_at("{a:1"), (1, 5));
+        assert_eq!(error_at("{:1}"), (1, 2));
+        assert_eq!(error_at("{a 1}"), (1, 4));
+        assert_eq!(error_at("[1, 2b]"), (1, 5));
+        assert_eq!(error_at("[I; 1, 2L]"), (1, 8));
+        assert_eq!(error_at("[B; 256]"), (1, 5));
+        assert_eq!(error_at("[X; 1]"), (1, 2));
+        assert_eq!(error_at("[I; 1.5]"), (1, 5));
+        assert_eq!(error_at("{\n  a: 1,\n  b: [1, \"x\"]\n}"), (3, 10));
+        assert_eq!(error_at("{\n  \"中文\": 128b\n}"), (2, 9));
+    }
+}
+
+mod snbt_print {
+    use super::*;
+    use crate::SnbtStyle;
+
+    fn gen_value() -> NbtValue {
+        NbtValue::Compound(
+            vec![
+                ("byte".to_string(), NbtValue::Byte(i8::MIN)),
+                ("short".to_string(), NbtValue::Short(i16::MAX)),
+                ("int".to_string(), NbtValue::Int(-1)),
+                ("long".to_string(), NbtValue::Long(i64::MIN)),
+                ("float".to_string(), NbtValue::Float(1e-30)),
+                ("double".to_string(), NbtValue::Double(-0.0)),
+                ("big".to_string(), NbtValue::Float(f32::MAX)),
+                ("bytes".to_string(), NbtValue::ByteArray(vec![-1, 0, 1])),
+                ("ints".to_string(), NbtValue::IntArray(vec![])),
+                ("longs".to_string(), NbtValue::LongArray(vec![i64::MAX])),
+                ("".to_string(), NbtValue::String("".to_string())),
+                ("a b".to_string(), NbtValue::String("1b".to_string())),
+                ("中文".to_string(), NbtValue::String("true".to_string())),
+                ("quote".to_string(), NbtValue::String("'\"\\".to_string())),
+                (
+                    "control".to_string(),
+                    NbtValue::String("\u{0}\u{8}\t\n\u{c}\r\u{7f}\u{2028}".to_string()),
+                ),
+                ("number".to_string(), NbtValue::String("-x".to_string())),
+                (
+                    "lists".to_string(),
+                    NbtValue::List(NbtList::List(vec![
+                        NbtList::End,
+                        NbtList::Byte(vec![1]),
+                        NbtList::Short(vec![1]),
+                        NbtList::Long(vec![1]),
+                        NbtList::Float(vec![1.5]),
+                        NbtList::Double(vec![1.5]),
+                        NbtList::String(vec!["a".to_string(), "b c".to_string()]),
+                        NbtList::ByteArray(vec![vec![1]]),
+                        NbtList::IntArray(vec![vec![1, 2]]),
+                        NbtList::LongArray(vec![vec![]]),
+                        NbtList::Compound(vec![NbtCompound::new()]),
+                    ])),
+                ),
+            ]
+            .into(),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = gen_value();
+        for indent in [None, Some(0), Some(2)] {
+            for always_quote in [false, true] {
+                for single_quote in [false, true] {
+                    let style = SnbtStyle {
+                        indent,
+                        sort_keys: false,
+                        always_quote,
+                        single_quote,
+                    };
+                    let text = value.to_snbt_with(&style);
+                    assert_eq!(NbtValue::from_snbt(&text), Ok(value.clone()), "{}", text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compact() {
+        let value = NbtValue::from_snbt(
+            "{b: 1b, s: 2s, i: 3, l: 4L, f: 5f, d: 6.5d, a: [B; 1b], t: [a, 'b c'], c: {}, e: []}",
+        )
+        .unwrap();
+        let expect = "{b:1b,s:2s,i:3,l:4L,f:5.0f,d:6.5d,a:[B;1b],t:[a,\"b c\"],c:{},e:[]}";
+        assert_eq!(value.to_snbt(), expect);
+        assert_eq!(value.to_string(), expect);
+        assert_eq!(format!("{}", NbtValue::LongArray(vec![1, 2])), "[L;1L,2L]");
+    }
+
+    #[test]
+    fn pretty() {
+        let value =
+            NbtValue::from_snbt("{b: 1b, c: {x: [I; 1, 2], y: []}, t: [{}, {z: 1}]}").unwrap();
+        let expect = r#"{
+    b: 1b,
+    c: {
+        x: [I; 1, 2],
+        y: []
+    },
+    t: [
+        {},
+        {
+            z: 1
+        }
+    ]
+}"#;
+        assert_eq!(value.to_snbt_with(&SnbtStyle::pretty()), expect);
+        assert_eq!(format!("{:#}", value), expect);
+        let style = SnbtStyle {
+            indent: Some(1),
+            ..SnbtStyle::default()
+        };
+        assert_eq!(
+            NbtValue::from_snbt("{a:[1]}").unwrap().to_snbt_with(&style),
+            "{\n a: [\n  1\n ]\n}"
+        );
+    }
+
+    #[test]
+    fn sort_keys() {
+        let value = NbtValue::from_snbt("{b: 1, a: {d: 1, c: 2}, C: 3}").unwrap();
+        let style = SnbtStyle {
+            sort_keys: true,
+            ..SnbtStyle::default()
+        };
+        assert_eq!(value.to_snbt_with(&style), "{C:3,a:{c:2,d:1},b:1}");
+        assert_eq!(value.to_snbt(), "{b:1,a:{d:1,c:2},C:3}");
+    }
+
+    #[test]
+    fn quote() {
+        let string =
+            |s: &str, style: &SnbtStyle| NbtValue::String(s.to_string()).to_snbt_with(style);
+        let minimal = SnbtStyle::default();
+        let always = SnbtStyle {
+            always_quote: true,
+            ..SnbtStyle::default()
+        };
+        let single = SnbtStyle {
+            single_quote: true,
+            ..SnbtStyle::default()
+        };
+        assert_eq!(string("abc", &minimal), "abc");
+        assert_eq!(string("abc", &always), "\"abc\"");
+        assert_eq!(string("a b", &single), "'a b'");
+        // 会被当成别的东西的要加引号
+        for s in ["", "1", "1b", "-a", "+a", ".5", "true", "false", "a:b", "中"] {
+            assert_eq!(string(s, &minimal), format!("\"{}\"", s));
+        }
+        // 尽量不用转义
+        assert_eq!(string("a\"b", &minimal), "'a\"b'");
+        assert_eq!(string("a'b", &single), "\"a'b\"");
+        assert_eq!(string("a'\"b", &minimal), "\"a'\\\"b\"");
+        assert_eq!(string("a'\"b", &single), "'a\\'\"b'");
+        assert_eq!(string("\\\n\u{1}\u{85}", &minimal), "\"\\\\\\n\\x01\\x85\"");
+        // key 只要字符合法就不用引号
+        let value = NbtValue::from_snbt("{1: 0, 'a b': 0, true: 0}").unwrap();
+        assert_eq!(value.to_snbt(), "{1:0,\"a b\":0,true:0}");
+        assert_eq!(value.to_snbt_with(&always), "{\"1\":0,\"a b\":0,\"true\":0}");
+    }
+
+    #[test]
+    fn non_finite() {
+        use crate::NbtError;
+
+        let mut value = NbtValue::from_snbt("{a: 1f, b: {c: [0d, 1d]}, d: [{e: 1f}]}").unwrap();
+        assert_eq!(value.try_to_snbt(), Ok(value.to_snbt()));
+
+        value.set_path("b.c[1]", NbtValue::Double(f64::NEG_INFINITY)).unwrap();
+        value.set_path("d[0].e", NbtValue::Float(f32::NAN)).unwrap();
+        assert_eq!(value.try_to_snbt(), Err(NbtError::NonFiniteFloat("b.c[1]".to_string())));
+        assert_eq!(
+            NbtValue::Float(f32::INFINITY).try_to_snbt_with(&SnbtStyle::pretty()),
+            Err(NbtError::NonFiniteFloat(String::new()))
+        );
+
+        // 不检查的话输出的文字会被读成 String, 在数字的 List 里就整个解析不了
+        assert_eq!(value.to_snbt(), "{a:1.0f,b:{c:[0.0d,-infd]},d:[{e:NaNf}]}");
+        assert!(NbtValue::from_snbt(&value.to_snbt()).is_err());
+        let parsed = NbtValue::from_snbt(&NbtValue::Float(f32::NAN).to_snbt()).unwrap();
+        assert_eq!(parsed, NbtValue::String("NaNf".to_string()));
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use super::*;
+    use crate::NbtError;
+    use serde_json::json;
+
+    fn gen_value() -> NbtValue {
+        let mut value = NbtValue::from_snbt(
+            r#"{
+                byte: -128b, short: 32767s, int: -2147483648, long: 9223372036854775807L,
+                float: 0.1f, double: -0.0d, string: "a/~\"",
+                bytes: [B; 1b, -1b], ints: [I;], longs: [L; -9223372036854775808L, 9007199254740993L],
+                empty: [],
+                lists: [[], [1b], [[I; 1]], [{a: 1}]],
+            }"#,
+        )
+        .unwrap();
+        let compound = value.as_compound_mut().unwrap();
+        compound.insert("empty_ints", NbtValue::List(NbtList::Int(vec![])));
+        compound.push("byte", NbtValue::Byte(1));
+        compound.insert("inf", NbtValue::Float(f32::NEG_INFINITY));
+        compound.insert("max", NbtValue::Double(f64::MAX));
+        value
+    }
+
+    #[test]
+    fn typed_round_trip() {
+        let value = gen_value();
+        assert_eq!(value.as_compound_ref().unwrap().len(), 16);
+        let json = value.to_typed_json();
+        assert_eq!(NbtValue::from_typed_json(&json), Ok(value.clone()));
+        let text = json.to_string();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(NbtValue::from_typed_json(&parsed), Ok(value));
+
+        // 写成文字再读回来, 最后一位也不能变
+        for v in [1.0715660391465826e-75, 0.1 + 0.2, f64::MIN_POSITIVE, 5e-324, f64::MAX] {
+            let text = NbtValue::Double(v).to_typed_json().to_string();
+            let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(NbtValue::from_typed_json(&parsed), Ok(NbtValue::Double(v)), "{}", text);
+        }
+
+        let nan = NbtValue::from_typed_json(&NbtValue::Double(f64::NAN).to_typed_json()).unwrap();
+        assert!(nan.as_f64().unwrap().is_nan());
+        let nan = NbtValue::from_typed_json(&NbtValue::Float(f32::NAN).to_typed_json()).unwrap();
+        assert!(nan.as_f32().unwrap().is_nan());
+    }
+
+    #[test]
+    fn typed_format() {
+        assert_eq!(NbtValue::Long(1).to_typed_json(), json!({"type": "long", "value": "1"}));
+        assert_eq!(NbtValue::Float(0.1).to_typed_json(), json!({"type": "float", "value": 0.1}));
+        assert_eq!(
+            NbtValue::Double(f64::INFINITY).to_typed_json(),
+            json!({"type": "double", "value": "Infinity"})
+        );
+        assert_eq!(
+            NbtValue::List(NbtList::Compound(vec![])).to_typed_json(),
+            json!({"type": "list", "element": "compound", "value": []})
+        );
+        assert_eq!(
+            NbtValue::List(NbtList::Short(vec![1])).to_typed_json(),
+            json!({"type": "list", "element": "short", "value": [{"type": "short", "value": 1}]})
+        );
+        // long 也可以直接写数字
+        assert_eq!(
+            NbtValue::from_typed_json(&json!({"type": "long_array", "value": [1, "2"]})),
+            Ok(NbtValue::LongArray(vec![1, 2]))
+        );
+    }
+
+    #[test]
+    fn typed_error() {
+        let check = |json: serde_json::Value, pointer: &str| match NbtValue::from_typed_json(&json)
+        {
+            Err(NbtError::InvalidJson(p, _)) => assert_eq!(p, pointer, "{}", json),
+            other => panic!("{} -> {:?}", json, other),
+        };
+        check(json!(1), "");
+        check(json!({"value": 1}), "/type");
+        check(json!({"type": "bool", "value": 1}), "/type");
+        check(json!({"type": "end", "value": 1}), "/type");
+        check(json!({"type": "int"}), "");
+        check(json!({"type": "byte", "value": 128}), "/value");
+        check(json!({"type": "long", "value": "1.5"}), "/value");
+        check(json!({"type": "float", "value": "nan"}), "/value");
+        check(json!({"type": "int_array", "value": [1, 2.5]}), "/value/1");
+        check(json!({"type": "list", "value": []}), "/element");
+        check(
+            json!({"type": "list", "element": "end", "value": [{"type": "int", "value": 1}]}),
+            "/value",
+        );
+        check(
+            json!({"type": "compound", "value": [{"name": "a/b", "type": "list", "element": "int",
+                "value": [{"type": "int", "value": "1"}]}]}),
+            "/value/0/value/0/value",
+        );
+        check(json!({"type": "compound", "value": [{"type": "int", "value": 1}]}), "/value/0");
+    }
+
+    #[test]
+    fn plain() {
+        let value = NbtValue::from_snbt(
+            "{a: 1b, b: 2s, c: 3L, d: 0.1f, e: [B; 1b], f: [L; 1L], g: [[1], []], h: {i: 'x'}}",
+        )
+        .unwrap();
+        let expect = json!({
+            "a": 1, "b": 2, "c": 3, "d": 0.1, "e": [1], "f": [1], "g": [[1], []], "h": {"i": "x"}
+        });
+        assert_eq!(value.to_json(), expect);
+        assert_eq!(NbtValue::Double(f64::NAN).to_json(), serde_json::Value::Null);
+
+        assert_eq!(
+            NbtValue::from_json(&expect),
+            NbtValue::from_snbt(
+                "{a: 1, b: 2, c: 3, d: 0.1d, e: [1], f: [1], g: [[1], []], h: {i: x}}"
+            )
+        );
+        assert_eq!(
+            NbtValue::from_json(&json!([1, 4294967296i64, 18446744073709551615u64])),
+            Ok(NbtValue::List(NbtList::Double(vec![1.0, 4294967296.0, 18446744073709551615.0])))
+        );
+        assert_eq!(
+            NbtValue::from_json(&json!([1, 4294967296i64])),
+            Ok(NbtValue::List(NbtList::Long(vec![1, 4294967296])))
+        );
+        assert_eq!(
+            NbtValue::from_json(&json!([1.5, 2])),
+            Ok(NbtValue::List(NbtList::Double(vec![1.5, 2.0])))
+        );
+        assert_eq!(
+            NbtValue::from_json(&json!({"a": [1, "x"]})),
+            Err(NbtError::InvalidJson(
+                "/a/1".to_string(),
+                "string 不能放进 int 的 List".to_string()
+            ))
+        );
+        assert_eq!(
+            NbtValue::from_json(&json!([true, 1])),
+            Err(NbtError::InvalidJson("/1".to_string(), "int 不能放进 byte 的 List".to_string()))
+        );
+        assert!(
+            matches!(NbtValue::from_json(&json!({"a": null})), Err(NbtError::InvalidJson(p, _)) if p == "/a")
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_binary {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::nbt_version::{
+        BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtReadTrait, NbtWriteTrait,
+    };
+    use crate::{from_bytes, from_value, to_bytes, to_bytes_with_name, to_value, NbtError};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Survival,
+        Spectator(i32),
+        Pos(i32, i32),
+        Named { name: String },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        alive: bool,
+        level: u8,
+        xp: u32,
+        seed: u64,
+        health: f32,
+        pos: (f64, f64, f64),
+        items: Vec<Item>,
+        scores: BTreeMap<String, i16>,
+        modes: Vec<Mode>,
+        spawn: Option<i64>,
+        nick: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: String,
+        count: i8,
+    }
+
+    fn player() -> Player {
+        Player {
+            name: "Steve".to_string(),
+            alive: true,
+            level: 200,
+            xp: u32::MAX,
+            seed: 1 << 63,
+            health: 20.0,
+            pos: (0.5, 64.0, -0.5),
+            items: vec![
+                Item {
+                    id: "minecraft:stone".to_string(),
+                    count: 64,
+                },
+                Item {
+                    id: "minecraft:dirt".to_string(),
+                    count: 1,
+                },
+            ],
+            scores: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), -1)]),
+            modes: vec![
+                Mode::Spectator(3),
+                Mode::Pos(1, 2),
+                Mode::Named {
+                    name: "x".to_string(),
+                },
+            ],
+            spawn: Some(-1),
+            nick: None,
+        }
+    }
+
+    fn round_trip<F: NbtReadTrait + NbtWriteTrait>() {
+        let value = player();
+        let mut bytes = to_bytes::<F, _>(&value).unwrap();
+        let back: Player = from_bytes::<F, _>(&mut bytes).unwrap();
+        assert_eq!(back, value);
+    }
+
+    #[test]
+    fn round_trip_all_formats() {
+        round_trip::<Java>();
+        round_trip::<JavaNetAfter1_20_2>();
+        round_trip::<BedrockDisk>();
+        round_trip::<BedrockNetVarInt>();
+    }
+
+    fn player_value() -> NbtValue {
+        NbtValue::from_snbt(
+            "{name: Steve, alive: 1b, level: -56b, xp: -1, seed: -9223372036854775808L, \
+             health: 20f, pos: [0.5d, 64d, -0.5d], \
+             items: [{id: 'minecraft:stone', count: 64b}, {id: 'minecraft:dirt', count: 1b}], \
+             scores: {a: 1s, b: -1s}, \
+             modes: [{Spectator: 3}, {Pos: [1, 2]}, {Named: {name: x}}], spawn: -1L}",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn same_bytes_as_value() {
+        let value = player_value();
+        assert_eq!(to_bytes::<Java, _>(&player()).unwrap(), value.to_binary::<Java>().unwrap());
+        assert_eq!(
+            to_bytes::<BedrockNetVarInt, _>(&player()).unwrap(),
+            value.to_binary::<BedrockNetVarInt>().unwrap()
+        );
+        let mut doc = NbtDocument::with_name("player", value);
+        assert_eq!(
+            to_bytes_with_name::<Java, _>("player", &player()).unwrap(),
+            doc.to_binary::<Java>().unwrap()
+        );
+        doc.root_name = None;
+        assert_eq!(
+            to_bytes::<JavaNetAfter1_20_2, _>(&player()).unwrap(),
+            doc.to_binary::<JavaNetAfter1_20_2>().unwrap()
+        );
+    }
+
+    /// 事先不知道长度的 List
+    struct Unsized(Vec<i32>);
+
+    impl Serialize for Unsized {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    #[test]
+    fn unknown_length_and_list_root() {
+        #[derive(Serialize)]
+        struct Wrapper {
+            list: Unsized,
+        }
+
+        fn check<F: NbtWriteTrait>() {
+            // 100 个元素的长度在变长编码里比占位长
+            for len in [0, 3, 100] {
+                let data: Vec<i32> = (0..len).collect();
+                let value = to_value(&Wrapper {
+                    list: Unsized(data.clone()),
+                })
+                .unwrap();
+                assert_eq!(
+                    to_bytes::<F, _>(&Wrapper {
+                        list: Unsized(data.clone())
+                    })
+                    .unwrap(),
+                    value.to_binary::<F>().unwrap()
+                );
+                assert_eq!(
+                    to_bytes_with_name::<F, _>("root", &value).ok(),
+                    NbtDocument::with_name("root", value).to_binary::<F>().ok()
+                );
+            }
+        }
+        check::<Java>();
+        check::<JavaNetAfter1_20_2>();
+        check::<BedrockDisk>();
+        check::<BedrockNetVarInt>();
+
+        // 基岩版的 List 根节点没有名字, 头部和 Compound 不一样
+        let list = Unsized((0..100).collect());
+        let value = to_value(&list).unwrap();
+        assert_eq!(
+            to_bytes::<BedrockDisk, _>(&list).unwrap(),
+            value.to_binary::<BedrockDisk>().unwrap()
+        );
+        assert_eq!(
+            to_bytes::<BedrockNetVarInt, _>(&list).unwrap(),
+            value.to_binary::<BedrockNetVarInt>().unwrap()
+        );
+        assert_eq!(
+            to_bytes_with_name::<BedrockDisk, _>("root", &list),
+            Err(NbtError::RootNameForbidden("root".to_string()))
+        );
+    }
+
+    #[test]
+    fn unit_variant_and_arrays() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Data {
+            mode: Mode,
+            ints: Vec<i32>,
+            bytes: Vec<u8>,
+            empty: Vec<i64>,
+        }
+
+        let mut bytes = NbtValue::from_snbt(
+            "{mode: Survival, ints: [I; 1, 2], bytes: [B; 1b, -1b], empty: [L;]}",
+        )
+        .unwrap()
+        .to_binary::<Java>()
+        .unwrap();
+        let data: Data = from_bytes::<Java, _>(&mut bytes).unwrap();
+        assert_eq!(
+            data,
+            Data {
+                mode: Mode::Survival,
+                ints: vec![1, 2],
+                bytes: vec![1, 255],
+                empty: vec![]
+            }
+        );
+
+        let mut bytes = to_bytes::<Java, _>(&data).unwrap();
+        assert_eq!(
+            NbtValue::from_binary::<Java>(&mut bytes).unwrap(),
+            NbtValue::from_snbt("{mode: Survival, ints: [1, 2], bytes: [1b, -1b], empty: []}")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn integer_keys() {
+        let map = BTreeMap::from([(1, "a".to_string()), (-2, "b".to_string())]);
+        let mut bytes = to_bytes::<Java, _>(&map).unwrap();
+        assert_eq!(
+            NbtValue::from_binary::<Java>(&mut bytes.clone()).unwrap(),
+            NbtValue::from_snbt("{'-2': b, '1': a}").unwrap()
+        );
+        assert_eq!(from_bytes::<Java, BTreeMap<i32, String>>(&mut bytes).unwrap(), map);
+    }
+
+    #[test]
+    fn value() {
+        let value = player_value();
+        assert_eq!(to_value(&player()).unwrap(), value);
+        assert_eq!(from_value::<Player>(value).unwrap(), player());
+
+        assert_eq!(to_value(&1u8).unwrap(), NbtValue::Byte(1));
+        assert_eq!(to_value(&vec![1i64]).unwrap(), NbtValue::List(NbtList::Long(vec![1])));
+        assert_eq!(from_value::<u32>(NbtValue::Int(-1)).unwrap(), u32::MAX);
+        assert!(to_value(&None::<i32>).is_err());
+        assert!(from_value::<String>(NbtValue::Int(1)).is_err());
+    }
+
+    #[test]
+    fn value_long_string() {
+        // 二进制里字符串长度是 u16, to_value / from_value 不能被它限制
+        let long = "a".repeat(70_000);
+        let mut map = BTreeMap::new();
+        map.insert(long.clone(), long.clone());
+
+        let value = to_value(&map).unwrap();
+        let mut expect = NbtCompound::new();
+        expect.push(long.clone(), NbtValue::String(long.clone()));
+        assert_eq!(value, NbtValue::Compound(expect));
+        assert_eq!(from_value::<BTreeMap<String, String>>(value).unwrap(), map);
+
+        assert_eq!(to_value(&long).unwrap(), NbtValue::String(long.clone()));
+        assert_eq!(from_value::<String>(NbtValue::String(long.clone())).unwrap(), long);
+    }
+
+    #[test]
+    fn value_keeps_types() {
+        let value = NbtValue::from_snbt(
+            "{a: 1b, b: 2s, c: 3, d: 4L, e: 5f, f: 6d, g: [B; 1b], h: s, i: [I; 1], j: [L; 1L], \
+             k: [], l: [[]], m: [[I; 1], [I;]], n: [{}], a: dup}",
+        )
+        .unwrap();
+        let mut empty_typed = NbtCompound::new();
+        empty_typed.push("ints", NbtValue::List(NbtList::Int(vec![])));
+        empty_typed.push("lists", NbtValue::List(NbtList::List(vec![NbtList::Compound(vec![])])));
+        let empty_typed = NbtValue::Compound(empty_typed);
+
+        for value in [&value, &empty_typed] {
+            let mut bytes = to_bytes::<Java, _>(value).unwrap();
+            assert_eq!(bytes, value.to_binary::<Java>().unwrap());
+            assert_eq!(&from_bytes::<Java, NbtValue>(&mut bytes).unwrap(), value);
+            assert_eq!(&to_value(value).unwrap(), value);
+            assert_eq!(&from_value::<NbtValue>(value.clone()).unwrap(), value);
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Wrapper {
+            id: String,
+            extra: NbtValue,
+            tag: NbtCompound,
+        }
+        let wrapper = Wrapper {
+            id: "x".to_string(),
+            extra: value.clone(),
+            tag: NbtCompound::new(),
+        };
+        let mut bytes = to_bytes::<BedrockNetVarInt, _>(&wrapper).unwrap();
+        assert_eq!(from_bytes::<BedrockNetVarInt, Wrapper>(&mut bytes).unwrap(), wrapper);
+        let mut expect = NbtCompound::new();
+        expect.push("id", NbtValue::String("x".to_string()));
+        expect.push("extra", value);
+        expect.push("tag", NbtValue::Compound(NbtCompound::new()));
+        assert_eq!(to_value(&wrapper).unwrap(), NbtValue::Compound(expect));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn self_describing() {
+        use serde_json::json;
+
+        let value = NbtValue::from_snbt("{a: 1b, b: [I; 1], c: [], d: [1L]}").unwrap();
+        assert_eq!(
+            serde_json::to_value(&value).unwrap(),
+            json!({"Compound": {
+                "a": {"Byte": 1},
+                "b": {"IntArray": [1]},
+                "c": {"List": {"End": []}},
+                "d": {"List": {"Long": [1]}},
+            }})
+        );
+        let value = NbtValue::from_snbt(
+            "{z: 1b, a: 2s, z: [[I; 1]], e: [[]], f: [{}], g: 1f, h: 'x', i: [L; 1L, 2L]}",
+        )
+        .unwrap();
+        let text = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<NbtValue>(&text).unwrap(), value);
+        assert!(serde_json::from_str::<NbtValue>(r#"{"End": 0}"#).is_err());
+        assert!(serde_json::from_str::<NbtList>(r#"{"End": [1]}"#).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(to_bytes::<Java, _>(&1i32), Err(NbtError::WrongRootType(3)));
+        assert!(to_bytes::<BedrockDisk, _>(&vec![1i32]).is_ok());
+
+        #[derive(Serialize)]
+        struct Mixed {
+            list: (i32, String),
+        }
+        assert_eq!(
+            to_bytes::<Java, _>(&Mixed {
+                list: (1, "a".to_string())
+            }),
+            Err(NbtError::ListTypeNotSame(vec![3, 8]))
+        );
+
+        #[derive(Serialize)]
+        struct Holes {
+            list: Vec<Option<i32>>,
+        }
+        assert!(to_bytes::<Java, _>(&Holes {
+            list: vec![Some(1), None]
+        })
+        .is_err());
+
+        let mut bytes = NbtValue::from_snbt("{count: 1}").unwrap().to_binary::<Java>().unwrap();
+        assert!(from_bytes::<Java, Item>(&mut bytes).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_helpers {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::nbt_version::Java;
+    use crate::serde::{ByteArray, IntArray, LongArray};
+    use crate::{from_bytes, from_value, to_bytes, to_value};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Arrays {
+        bytes: ByteArray,
+        ints: IntArray,
+        longs: LongArray,
+        #[serde(with = "crate::serde::byte_array")]
+        raw: Vec<u8>,
+        #[serde(with = "crate::serde::int_array")]
+        pos: [i32; 3],
+        #[serde(with = "crate::serde::long_array")]
+        empty: Vec<u64>,
+        #[serde(with = "crate::serde::uuid")]
+        uuid: [u8; 16],
+        list: Vec<i32>,
+    }
+
+    fn arrays() -> Arrays {
+        Arrays {
+            bytes: ByteArray(vec![1, -1]),
+            ints: vec![1, 2].into(),
+            longs: LongArray(vec![i64::MIN]),
+            raw: vec![0, 255],
+            pos: [1, -64, 3],
+            empty: vec![],
+            uuid: [0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 2, 0x80, 0, 0, 0],
+            list: vec![1],
+        }
+    }
+
+    #[test]
+    fn arrays_round_trip() {
+        let value = NbtValue::from_snbt(
+            "{bytes: [B; 1b, -1b], ints: [I; 1, 2], longs: [L; -9223372036854775808L], \
+             raw: [B; 0b, -1b], pos: [I; 1, -64, 3], empty: [L;], \
+             uuid: [I; 1, -1, 2, -2147483648], list: [1]}",
+        )
+        .unwrap();
+        assert_eq!(to_value(&arrays()).unwrap(), value);
+        assert_eq!(from_value::<Arrays>(value).unwrap(), arrays());
+
+        let mut bytes = to_bytes::<Java, _>(&arrays()).unwrap();
+        assert_eq!(from_bytes::<Java, Arrays>(&mut bytes).unwrap(), arrays());
+
+        // List 也可以读成数组
+        let value = NbtValue::from_snbt(
+            "{bytes: [1b], ints: [1], longs: [], raw: [], pos: [1, 2, 3], empty: [], \
+             uuid: [1, 2, 3, 4], list: [I; 1]}",
+        )
+        .unwrap();
+        assert_eq!(from_value::<Arrays>(value).unwrap().ints, IntArray(vec![1]));
+    }
+
+    #[test]
+    fn array_errors() {
+        #[derive(Serialize)]
+        struct Wide {
+            #[serde(with = "crate::serde::int_array")]
+            ints: Vec<i64>,
+        }
+        assert!(to_value(&Wide {
+            ints: vec![1 << 32]
+        })
+        .is_err());
+        assert_eq!(
+            to_value(&Wide {
+                ints: vec![u32::MAX as i64]
+            })
+            .unwrap(),
+            NbtValue::from_snbt("{ints: [I; -1]}").unwrap()
+        );
+
+        #[derive(Serialize)]
+        struct NotInts {
+            #[serde(with = "crate::serde::byte_array")]
+            bytes: Vec<f32>,
+        }
+        assert!(to_value(&NotInts { bytes: vec![1.0] }).is_err());
+
+        #[derive(Deserialize, Debug)]
+        struct Uuid {
+            #[serde(with = "crate::serde::uuid")]
+            #[allow(dead_code)]
+            uuid: [u8; 16],
+        }
+        assert!(from_value::<Uuid>(NbtValue::from_snbt("{uuid: [I; 1, 2]}").unwrap()).is_err());
+    }
+
+    #[test]
+    fn game_conventions() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum GameType {
+            Survival,
+            Creative,
+            Adventure,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "camelCase")]
+        struct Player {
+            on_ground: bool,
+            player_game_type: GameType,
+        }
+
+        let player = Player {
+            on_ground: true,
+            player_game_type: GameType::Adventure,
+        };
+        assert_eq!(
+            to_value(&player).unwrap(),
+            NbtValue::from_snbt("{onGround: 1b, playerGameType: adventure}").unwrap()
+        );
+        assert_eq!(
+            from_value::<Player>(NbtValue::from_snbt("{onGround: 2, playerGameType: 1}").unwrap())
+                .unwrap(),
+            Player {
+                on_ground: true,
+                player_game_type: GameType::Creative,
+            }
+        );
+        assert_eq!(
+            from_value::<Player>(
+                NbtValue::from_snbt("{onGround: 0s, playerGameType: 0b}").unwrap()
+            )
+            .unwrap(),
+            Player {
+                on_ground: false,
+                player_game_type: GameType::Survival,
+            }
+        );
+        assert!(from_value::<Player>(
+            NbtValue::from_snbt("{onGround: 0b, playerGameType: 3}").unwrap()
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn self_describing() {
+        use serde_json::json;
+
+        assert_eq!(
+            serde_json::to_value(arrays()).unwrap(),
+            json!({
+                "bytes": [1, -1], "ints": [1, 2], "longs": [i64::MIN], "raw": [0, 255],
+                "pos": [1, -64, 3], "empty": [], "uuid": [1, -1, 2, i32::MIN], "list": [1]
+            })
+        );
+        let text = serde_json::to_string(&arrays()).unwrap();
+        assert_eq!(serde_json::from_str::<Arrays>(&text).unwrap(), arrays());
+    }
+}
+
+mod convert {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::convert::{byte_array, int_array, uuid};
+    use crate::{FromNbt, NbtError, ToNbt};
+
+    #[test]
+    fn scalars() {
+        assert_eq!(255u8.to_nbt(), Ok(NbtValue::Byte(-1)));
+        assert_eq!(u8::from_nbt(NbtValue::Byte(-1)), Ok(255));
+        assert_eq!(true.to_nbt(), Ok(NbtValue::Byte(1)));
+        assert_eq!(bool::from_nbt(NbtValue::Int(2)), Ok(true));
+        assert_eq!(bool::from_nbt(NbtValue::Double(0.0)), Ok(false));
+        assert_eq!("a".to_nbt(), Ok(NbtValue::String("a".to_string())));
+        assert_eq!(i32::from_nbt(NbtValue::Short(1)), Err(NbtError::IncorrectType(3, 2)));
+        assert_eq!(
+            bool::from_nbt(NbtValue::String("1".to_string())),
+            Err(NbtError::IncorrectType(1, 8))
+        );
+    }
+
+    #[test]
+    fn collections() {
+        assert_eq!(vec![1i16, 2].to_nbt(), Ok(NbtValue::List(NbtList::Short(vec![1, 2]))));
+        assert_eq!(Vec::<i16>::new().to_nbt(), Ok(NbtValue::List(NbtList::End)));
+        assert_eq!(
+            vec![NbtValue::Int(1), NbtValue::Int(2), NbtValue::Byte(1)].to_nbt(),
+            Err(NbtError::PathTypeMismatch("[2]".to_string(), 3, 1))
+        );
+        assert_eq!(
+            vec![vec![NbtValue::Int(1)], vec![NbtValue::Int(1), NbtValue::Byte(1)]].to_nbt(),
+            Err(NbtError::PathTypeMismatch("[1][1]".to_string(), 3, 1))
+        );
+        assert_eq!(Vec::<i32>::from_nbt(NbtValue::IntArray(vec![1])), Ok(vec![1]));
+        assert_eq!(Vec::<i32>::from_nbt(NbtValue::from_snbt("[1, 2]").unwrap()), Ok(vec![1, 2]));
+        assert_eq!(
+            Vec::<Vec<i32>>::from_nbt(NbtValue::from_snbt("[[1], [2b]]").unwrap()),
+            Err(NbtError::PathTypeMismatch("[1][0]".to_string(), 3, 1))
+        );
+
+        let map = BTreeMap::from([("b".to_string(), 1i64), ("a".to_string(), 2)]);
+        let value = map.to_nbt().unwrap();
+        assert_eq!(value, NbtValue::from_snbt("{a: 2L, b: 1L}").unwrap());
+        assert_eq!(BTreeMap::from_nbt(value.clone()), Ok(map));
+        let map: HashMap<String, i64> = HashMap::from_nbt(value).unwrap();
+        assert_eq!(map["a"], 2);
+        assert_eq!(
+            HashMap::<String, i64>::from_nbt(NbtValue::from_snbt("{'a.b': 1}").unwrap()),
+            Err(NbtError::PathTypeMismatch("\"a.b\"".to_string(), 4, 3))
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(int_array::to_nbt(&[1, 2]), Ok(NbtValue::IntArray(vec![1, 2])));
+        assert_eq!(int_array::from_nbt(NbtValue::from_snbt("[1, 2]").unwrap()), Ok(vec![1, 2]));
+        assert_eq!(byte_array::from_nbt(NbtValue::Int(1)), Err(NbtError::IncorrectType(7, 3)));
+
+        let bytes = [0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 2, 0x80, 0, 0, 0];
+        let value = uuid::to_nbt(&bytes).unwrap();
+        assert_eq!(value, NbtValue::IntArray(vec![1, -1, 2, i32::MIN]));
+        assert_eq!(uuid::from_nbt(value), Ok(bytes));
+        assert!(uuid::from_nbt(NbtValue::IntArray(vec![1])).is_err());
+    }
+}
+
+mod from_into {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::NbtError;
+
+    #[test]
+    fn scalars() {
+        assert_eq!(NbtValue::from(1i8), NbtValue::Byte(1));
+        assert_eq!(NbtValue::from(u16::MAX), NbtValue::Short(-1));
+        assert_eq!(NbtValue::from(true), NbtValue::Byte(1));
+        assert_eq!(NbtValue::from(1.5f32), NbtValue::Float(1.5));
+        assert_eq!(NbtValue::from("a"), NbtValue::String("a".to_string()));
+        assert_eq!(i64::try_from(NbtValue::Long(-1)), Ok(-1));
+        assert_eq!(u32::try_from(NbtValue::Int(-1)), Ok(u32::MAX));
+        assert_eq!(i32::try_from(NbtValue::Long(1)), Err(NbtError::IncorrectType(3, 4)));
+        assert_eq!(bool::try_from(NbtValue::Short(2)), Ok(true));
+        assert_eq!(String::try_from(NbtValue::from("a")), Ok("a".to_string()));
+        let value: NbtValue = 1.0f64.into();
+        assert_eq!(f64::try_from(value), Ok(1.0));
+    }
+
+    #[test]
+    fn arrays_and_lists() {
+        assert_eq!(NbtValue::from(vec![1i8]), NbtValue::ByteArray(vec![1]));
+        assert_eq!(NbtValue::from(vec![1i32]), NbtValue::IntArray(vec![1]));
+        assert_eq!(NbtValue::from(vec![1i64]), NbtValue::LongArray(vec![1]));
+        assert_eq!(NbtValue::from(vec![1i16]), NbtValue::List(NbtList::Short(vec![1])));
+        assert_eq!(
+            NbtValue::from(vec!["a".to_string()]),
+            NbtValue::List(NbtList::String(vec!["a".to_string()]))
+        );
+        assert_eq!(
+            NbtValue::from(vec![vec![1i32]]),
+            NbtValue::List(NbtList::IntArray(vec![vec![1]]))
+        );
+        assert_eq!(Vec::<i32>::try_from(NbtValue::IntArray(vec![2])), Ok(vec![2]));
+        assert_eq!(
+            Vec::<i32>::try_from(NbtValue::List(NbtList::Int(vec![2]))),
+            Err(NbtError::IncorrectType(11, 9))
+        );
+        assert_eq!(Vec::<f64>::try_from(NbtValue::List(NbtList::End)), Ok(vec![]));
+        assert_eq!(
+            Vec::<f64>::try_from(NbtValue::List(NbtList::Float(vec![1.0]))),
+            Err(NbtError::IncorrectType(6, 5))
+        );
+
+        // 无符号数和有符号的一样, 按补码存
+        assert_eq!(NbtValue::from(vec![255u8]), NbtValue::ByteArray(vec![-1]));
+        assert_eq!(NbtValue::from(vec![u16::MAX]), NbtValue::List(NbtList::Short(vec![-1])));
+        assert_eq!(NbtValue::from(vec![u32::MAX]), NbtValue::IntArray(vec![-1]));
+        assert_eq!(NbtValue::from(vec![u64::MAX]), NbtValue::LongArray(vec![-1]));
+        assert_eq!(Vec::<u8>::try_from(NbtValue::ByteArray(vec![-1])), Ok(vec![255]));
+        assert_eq!(Vec::<u16>::try_from(NbtValue::List(NbtList::End)), Ok(vec![]));
+        assert_eq!(Vec::<u32>::try_from(NbtValue::IntArray(vec![-1])), Ok(vec![u32::MAX]));
+        assert_eq!(Vec::<u64>::try_from(NbtValue::LongArray(vec![-1])), Ok(vec![u64::MAX]));
+
+        assert_eq!(NbtValue::from(vec![true, false]), NbtValue::List(NbtList::Byte(vec![1, 0])));
+        assert_eq!(
+            Vec::<bool>::try_from(NbtValue::List(NbtList::Byte(vec![2, 0]))),
+            Ok(vec![true, false])
+        );
+        assert_eq!(
+            Vec::<bool>::try_from(NbtValue::List(NbtList::Int(vec![1]))),
+            Err(NbtError::IncorrectType(1, 3))
+        );
+        assert_eq!(NbtValue::from(vec!["a", "b"]), NbtValue::from_snbt("[a, b]").unwrap());
+
+        let values = vec![NbtValue::Int(1), NbtValue::Int(2)];
+        let value = NbtValue::try_from(values.clone()).unwrap();
+        assert_eq!(value, NbtValue::List(NbtList::Int(vec![1, 2])));
+        assert_eq!(Vec::<NbtValue>::try_from(value), Ok(values));
+        assert_eq!(
+            NbtValue::try_from(vec![NbtValue::Int(1), NbtValue::Byte(1)]),
+            Err(NbtError::ListTypeNotSame(vec![3, 1]))
+        );
+    }
+
+    #[test]
+    fn maps() {
+        let map = BTreeMap::from([("b", 1i16), ("a", 2)]);
+        let value = NbtValue::from(map);
+        assert_eq!(value, NbtValue::from_snbt("{a: 2s, b: 1s}").unwrap());
+        let map = BTreeMap::<String, i16>::try_from(value.clone()).unwrap();
+        assert_eq!(map["a"], 2);
+        let map = HashMap::<String, i16>::try_from(value).unwrap();
+        assert_eq!(map["b"], 1);
+        assert_eq!(
+            HashMap::<String, String>::try_from(NbtValue::from_snbt("{a: 'x', b: 1}").unwrap()),
+            Err(NbtError::PathTypeMismatch("b".to_string(), 8, 3))
+        );
+        let nested = HashMap::from([("inner".to_string(), HashMap::from([("x", 1i8)]))]);
+        assert_eq!(NbtValue::from(nested), NbtValue::from_snbt("{inner: {x: 1b}}").unwrap());
+
+        let list = NbtValue::from(vec![BTreeMap::from([("a", 1)]), BTreeMap::new()]);
+        assert_eq!(list, NbtValue::from_snbt("[{a: 1}, {}]").unwrap());
+        let maps = Vec::<BTreeMap<String, i32>>::try_from(list.clone()).unwrap();
+        assert_eq!(maps[0]["a"], 1);
+        assert_eq!(Vec::<HashMap<String, i32>>::try_from(list).unwrap()[1].len(), 0);
+        assert_eq!(
+            NbtValue::from(vec![HashMap::from([("x", "y")])]),
+            NbtValue::from_snbt("[{x: y}]").unwrap()
+        );
+        assert_eq!(
+            Vec::<BTreeMap<String, i32>>::try_from(
+                NbtValue::from_snbt("[{a: 1}, {a: 1b}]").unwrap()
+            ),
+            Err(NbtError::PathTypeMismatch("[1].a".to_string(), 3, 1))
+        );
+        assert_eq!(
+            Vec::<HashMap<String, i32>>::try_from(NbtValue::from_snbt("[1]").unwrap()),
+            Err(NbtError::IncorrectType(10, 3))
+        );
+    }
+}
+
+mod nbt_macro {
+    use super::*;
+    use crate::nbt;
+
+    #[test]
+    fn literals() {
+        assert_eq!(nbt!(1), NbtValue::Int(1));
+        assert_eq!(nbt!(-1b), NbtValue::Byte(-1));
+        assert_eq!(nbt!(255u8), NbtValue::Byte(-1));
+        assert_eq!(nbt!(2S), NbtValue::Short(2));
+        assert_eq!(nbt!(3L), NbtValue::Long(3));
+        assert_eq!(nbt!(0x10i64), NbtValue::Long(16));
+        assert_eq!(nbt!(0x1f32), NbtValue::Int(0x1f32));
+        assert_eq!(nbt!(1.5), NbtValue::Double(1.5));
+        assert_eq!(nbt!(1.5f), NbtValue::Float(1.5));
+        assert_eq!(nbt!(1e3f32), NbtValue::Float(1000.0));
+        assert_eq!(nbt!(1_000d), NbtValue::Double(1000.0));
+        assert_eq!(nbt!(true), NbtValue::Byte(1));
+        assert_eq!(nbt!("a\n\"\u{4e2d}\x41"), NbtValue::String("a\n\"中A".to_string()));
+        assert_eq!(nbt!(r#"a"b"#), NbtValue::String("a\"b".to_string()));
+        assert_eq!(nbt!('c'), NbtValue::String("c".to_string()));
+    }
+
+    #[test]
+    fn expressions() {
+        let x = 5i16;
+        let name = String::from("n");
+        assert_eq!(nbt!(x), NbtValue::Short(5));
+        assert_eq!(nbt!(-x), NbtValue::Short(-5));
+        assert_eq!(nbt!(x as i64 * 2), NbtValue::Long(10));
+        assert_eq!(
+            nbt!({ a: x + 1, "b": name.clone(), c: vec![1i32, 2] }),
+            NbtValue::from_snbt("{a: 6s, b: n, c: [I; 1, 2]}").unwrap()
+        );
+        assert_eq!(nbt!([x, -x, 1s]), NbtValue::List(NbtList::Short(vec![5, -5, 1])));
+        assert_eq!(nbt!([B; x as i8, 2]), NbtValue::ByteArray(vec![5, 2]));
+    }
+
+    #[test]
+    fn nested() {
+        let value = nbt! {
+            "minecraft:id": "stone",
+            Count: 64b,
+            tag: {
+                display: { Name: r#"{"text":"a"}"#, Lore: [] },
+                Enchantments: [{ id: "sharpness", lvl: 5s }, { id: "unbreaking", lvl: 3s },],
+                list: [[1, 2], [3b], []],
+                empty: {},
+            },
+            arrays: [[B; 1, -2b], [B;]],
+        };
+        assert_eq!(
+            value,
+            NbtValue::from_snbt(
+                r#"{"minecraft:id": stone, Count: 64b, tag: {display: {Name: '{"text":"a"}', Lore: []},
+                Enchantments: [{id: sharpness, lvl: 5s}, {id: unbreaking, lvl: 3s}],
+                list: [[1, 2], [3b], []], empty: {}}, arrays: [[B; 1b, -2b], [B;]]}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(nbt!(), NbtValue::Compound(NbtCompound::new()));
+        assert_eq!(nbt!({}), NbtValue::Compound(NbtCompound::new()));
+        assert_eq!(nbt!({ a: 1, a: 2 }), NbtValue::from_snbt("{a: 2}").unwrap());
+        assert_eq!(nbt!([L;]), NbtValue::LongArray(vec![]));
+    }
+
+    #[test]
+    #[should_panic(expected = "nbt!: 1 类型的值不能放进元素类型为 3 的 List")]
+    fn mixed_list() {
+        nbt!([1, 2b]);
+    }
+
+    #[test]
+    #[should_panic(expected = "nbt!: 4 类型的值不能放进 [I;")]
+    fn wrong_array_element() {
+        nbt!([I; 1, 2L]);
+    }
+
+    #[test]
+    #[should_panic(expected = "nbt!: 无效的字面量 300b")]
+    fn out_of_range() {
+        nbt!(300b);
+    }
+}
+
+mod merge {
+    use super::*;
+    use crate::{ListMerge, MergeOptions};
+
+    fn snbt(text: &str) -> NbtValue { NbtValue::from_snbt(text).unwrap() }
+
+    #[test]
+    fn like_data_merge() {
+        let mut value = snbt("{a: 1, b: {c: 1, d: [1, 2]}, e: {f: 1b}, list: [{x: 1}]}");
+        value.merge(snbt("{a: 2s, b: {d: [3], g: 'x'}, e: 1, list: [{y: 1}], h: {}}"));
+        assert_eq!(value, snbt("{a: 2s, b: {c: 1, d: [3], g: x}, e: 1, list: [{y: 1}], h: {}}"));
+
+        // 不是 Compound 的时候直接替换
+        let mut value = snbt("{a: 1}");
+        value.merge(snbt("[1]"));
+        assert_eq!(value, snbt("[1]"));
+        let mut value = snbt("1");
+        value.merge(snbt("{a: 1}"));
+        assert_eq!(value, snbt("{a: 1}"));
+
+        let mut compound = snbt("{a: {b: 1}}").into_compound().unwrap();
+        compound.merge(snbt("{a: {c: 2}}").into_compound().unwrap());
+        assert_eq!(NbtValue::Compound(compound), snbt("{a: {b: 1, c: 2}}"));
+    }
+
+    #[test]
+    fn append_and_index() {
+        let append = MergeOptions {
+            lists: ListMerge::Append,
+            ..MergeOptions::default()
+        };
+        let mut value = snbt("{a: [1, 2], b: [1b], c: [], d: [1]}");
+        value.merge_with(snbt("{a: [3], b: [1], c: [x], d: []}"), &append);
+        // 元素类型不一样的时候替换
+        assert_eq!(value, snbt("{a: [1, 2, 3], b: [1], c: [x], d: [1]}"));
+
+        let by_index = MergeOptions {
+            lists: ListMerge::ByIndex,
+            ..MergeOptions::default()
+        };
+        let mut value = snbt("[{a: 1, b: 1}, {a: 2}]");
+        value.merge_with(snbt("[{b: 2}, {b: 3}, {b: 4}]"), &by_index);
+        assert_eq!(value, snbt("[{a: 1, b: 2}, {a: 2, b: 3}, {b: 4}]"));
+        let mut value = snbt("[[1, 2], [3]]");
+        value.merge_with(snbt("[[4]]"), &by_index);
+        assert_eq!(value, snbt("[[4, 2], [3]]"));
+    }
+
+    #[test]
+    fn by_key() {
+        let options = MergeOptions::default()
+            .with_list("Inventory", ListMerge::ByKey("Slot".to_string()))
+            .with_list("Enchantments", ListMerge::ByKey("id".to_string()));
+        let mut player = snbt(
+            "{Inventory: [{Slot: 0b, id: sword, tag: {Enchantments: [{id: sharpness, lvl: 1s}]}}, \
+             {Slot: 1b, id: dirt}], Tags: [a]}",
+        );
+        player.merge_with(
+            snbt(
+                "{Inventory: [{Slot: 0b, tag: {Enchantments: [{id: sharpness, lvl: 5s}, \
+                 {id: mending, lvl: 1s}]}}, {Slot: 2b, id: sand}, {id: stone}], Tags: [b]}",
+            ),
+            &options,
+        );
+        assert_eq!(
+            player,
+            snbt(
+                "{Inventory: [{Slot: 0b, id: sword, tag: {Enchantments: [{id: sharpness, lvl: 5s}, \
+                 {id: mending, lvl: 1s}]}}, {Slot: 1b, id: dirt}, {Slot: 2b, id: sand}, {id: stone}], \
+                 Tags: [b]}"
+            )
+        );
+
+        // 不是 Compound 的元素找不到 key, 加到后面
+        let mut value = snbt("{Inventory: [1, 2]}");
+        value.merge_with(snbt("{Inventory: [2]}"), &options);
+        assert_eq!(value, snbt("{Inventory: [1, 2, 2]}"));
+    }
+}
+
+mod diff {
+    use super::*;
+    use crate::{DiffChange, DiffEntry, DiffOptions, ListAlign, NbtPatch};
+
+    fn snbt(text: &str) -> NbtValue { NbtValue::from_snbt(text).unwrap() }
+
+    fn entry(path: &str, change: DiffChange) -> DiffEntry {
+        DiffEntry {
+            path: path.to_string(),
+            change,
+        }
+    }
+
+    #[test]
+    fn compounds() {
+        let old = snbt("{a: 1, b: {c: 1, d: 'x'}, 'e.f': 1b, g: [I; 1]}");
+        let new = snbt("{a: 1, b: {c: 2, d: 1}, g: [I; 1, 2], h: {}}");
+        assert_eq!(
+            old.diff(&new).changes,
+            vec![
+                entry(
+                    "b.c",
+                    DiffChange::Changed {
+                        old: snbt("1"),
+                        new: snbt("2")
+                    }
+                ),
+                entry(
+                    "b.d",
+                    DiffChange::TypeChanged {
+                        old: snbt("x"),
+                        new: snbt("1")
+                    }
+                ),
+                entry("\"e.f\"", DiffChange::Removed(snbt("1b"))),
+                entry(
+                    "g",
+                    DiffChange::Changed {
+                        old: snbt("[I; 1]"),
+                        new: snbt("[I; 1, 2]")
+                    }
+                ),
+                entry("h", DiffChange::Added(snbt("{}"))),
+            ]
+        );
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(
+            snbt("1").diff(&snbt("{}")).changes,
+            vec![entry(
+                "",
+                DiffChange::TypeChanged {
+                    old: snbt("1"),
+                    new: snbt("{}")
+                }
+            )]
+        );
+        // 空 List 不管元素类型
+        assert!(NbtValue::List(NbtList::Short(vec![])).diff(&snbt("[]")).is_empty());
+    }
+
+    #[test]
+    fn lists() {
+        let old = snbt("{l: [{a: 1}, {a: 2}, {a: 3}]}");
+        let new = snbt("{l: [{a: 1}, {a: 5}]}");
+        assert_eq!(
+            old.diff(&new).changes,
+            vec![
+                entry(
+                    "l[1].a",
+                    DiffChange::Changed {
+                        old: snbt("2"),
+                        new: snbt("5")
+                    }
+                ),
+                entry("l[2]", DiffChange::Removed(snbt("{a: 3}"))),
+            ]
+        );
+        // 元素类型变了的 List 整个替换
+        assert_eq!(
+            snbt("[1b]").diff(&snbt("[1s, 2s]")).changes,
+            vec![entry(
+                "",
+                DiffChange::Changed {
+                    old: snbt("[1b]"),
+                    new: snbt("[1s, 2s]")
+                }
+            )]
+        );
+
+        let options =
+            DiffOptions::default().with_list("Inventory", ListAlign::ByKey("Slot".into()));
+        let old = snbt(
+            "{Inventory: [{Slot: 0b, id: a}, {Slot: 1b, id: b}, {id: loose}, {Slot: 3b, id: c}]}",
+        );
+        let new =
+            snbt("{Inventory: [{Slot: 3b, id: c}, {id: loose}, {Slot: 1b, id: d}, {Slot: 4b}]}");
+        assert_eq!(
+            old.diff_with(&new, &options).changes,
+            vec![
+                entry("Inventory[0]", DiffChange::Removed(snbt("{Slot: 0b, id: a}"))),
+                entry(
+                    "Inventory[0]",
+                    DiffChange::Moved {
+                        from: "Inventory[2]".to_string()
+                    }
+                ),
+                entry(
+                    "Inventory[1]",
+                    DiffChange::Moved {
+                        from: "Inventory[2]".to_string()
+                    }
+                ),
+                entry(
+                    "Inventory[2].id",
+                    DiffChange::Changed {
+                        old: snbt("b"),
+                        new: snbt("d")
+                    }
+                ),
+                entry("Inventory[3]", DiffChange::Added(snbt("{Slot: 4b}"))),
+            ]
+        );
+        // 没有指定的 List 还是按下标
+        let old = snbt("{Other: [{Slot: 0b}, {Slot: 1b}]}");
+        let new = snbt("{Other: [{Slot: 1b}]}");
+        assert_eq!(old.diff_with(&new, &options).len(), 2);
+    }
+
+    #[test]
+    fn floats() {
+        let mut old = snbt("{a: 1.0f, b: [0.1d, 0.2d], c: 0d}");
+        let mut new = snbt("{a: 1.00001f, b: [0.1000001d, 0.3d], c: 0d}");
+        old.set_path("c", NbtValue::Double(f64::NAN)).unwrap();
+        new.set_path("c", NbtValue::Double(f64::NAN)).unwrap();
+        assert_eq!(old.diff(&new).len(), 3);
+        let options = DiffOptions {
+            epsilon: Some(1e-3),
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            old.diff_with(&new, &options).changes,
+            vec![entry(
+                "b[1]",
+                DiffChange::Changed {
+                    old: snbt("0.2d"),
+                    new: snbt("0.3d")
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn element_type_changed() {
+        let old = snbt("{L: [1, 2], M: [], N: [[1], [a]]}");
+        let new = snbt("{L: [a, b], M: [1], N: [[1], [2]]}");
+        let diff = old.diff(&new);
+        assert_eq!(diff.to_string(), "~ L: [1,2] -> [a,b]\n+ M[0]: 1\n~ N[1]: [a] -> [2]\n");
+        let mut value = old.clone();
+        value.apply_patch(&NbtPatch::from(&diff)).unwrap();
+        assert_eq!(value, new);
+
+        // 按 key 对齐的 List 也一样
+        let options = DiffOptions::default().with_list("L", ListAlign::ByKey("id".into()));
+        let old = snbt("{L: [{id: 1}]}");
+        let new = snbt("{L: [a]}");
+        assert_eq!(
+            old.diff_with(&new, &options).changes,
+            vec![entry(
+                "L",
+                DiffChange::Changed {
+                    old: snbt("[{id: 1}]"),
+                    new: snbt("[a]")
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let diff = snbt("{a: 1, b: 'x', c: [1]}").diff(&snbt("{a: 2, b: 1b, d: {}}"));
+        assert_eq!(
+            diff.to_string(),
+            "~ a: 1 -> 2\n~ b: x -> 1b (类型 8 -> 1)\n- c: [1]\n+ d: {}\n"
+        );
+        assert_eq!(diff.to_colored_string().lines().next(), Some("\x1b[33m~ a: 1 -> 2\x1b[0m"));
+        assert_eq!(snbt("1").diff(&snbt("2")).to_string(), "~ <root>: 1 -> 2\n");
+    }
+}
+
+mod patch {
+    use super::*;
+    use crate::{DiffOptions, FromNbt, ListAlign, NbtError, NbtPatch, PatchOp, ToNbt};
+
+    fn snbt(text: &str) -> NbtValue { NbtValue::from_snbt(text).unwrap() }
+
+    /// 打补丁, 再打撤销补丁, 结果应该和原来的内容一样
+    fn round_trip(before: &str, patch: &NbtPatch, after: &str) {
+        let before = snbt(before);
+        let undo = before.invert_patch(patch).unwrap();
+        let mut value = before.clone();
+        value.apply_patch(patch).unwrap();
+        assert_eq!(value, snbt(after));
+        value.apply_patch(&undo).unwrap();
+        assert_eq!(value.to_canonical(), before.to_canonical());
+    }
+
+    fn add(path: &str, value: &str) -> PatchOp {
+        PatchOp::Add {
+            path: path.to_string(),
+            value: snbt(value),
+        }
+    }
+
+    #[test]
+    fn operations() {
+        let patch = NbtPatch::from(vec![
+            add("a", "1b"),
+            add("b.c", "{}"),
+            add("l[0]", "0"),
+            add("l[-1]", "9"),
+            add("arr[1]", "5L"),
+            PatchOp::Remove {
+                path: "x".to_string(),
+            },
+            PatchOp::Replace {
+                path: "l[1]".to_string(),
+                value: snbt("7"),
+            },
+            PatchOp::Copy {
+                from: "b".to_string(),
+                path: "d".to_string(),
+            },
+            PatchOp::Move {
+                from: "l[0]".to_string(),
+                path: "l[2]".to_string(),
+            },
+            PatchOp::Test {
+                path: "l".to_string(),
+                value: snbt("[7, 2, 0, 9]"),
+            },
+        ]);
+        round_trip(
+            "{a: 0, b: {}, l: [1, 2], arr: [L; 1L, 2L], x: 'gone'}",
+            &patch,
+            "{a: 1b, b: {c: {}}, l: [7, 2, 0, 9], arr: [L; 1L, 5L, 2L], d: {c: {}}}",
+        );
+
+        // 覆盖已有 key 的 move, 和替换根节点
+        let patch = NbtPatch::from(vec![PatchOp::Move {
+            from: "a".to_string(),
+            path: "b".to_string(),
+        }]);
+        round_trip("{a: 1, b: 2}", &patch, "{b: 1}");
+        let patch = NbtPatch::from(vec![PatchOp::Move {
+            from: "a".to_string(),
+            path: "".to_string(),
+        }]);
+        round_trip("{a: {x: 1}, b: 2}", &patch, "{x: 1}");
+        round_trip("{a: 1}", &NbtPatch::from(vec![add("", "[1]")]), "[1]");
+    }
+
+    #[test]
+    fn errors() {
+        let mut value = snbt("{a: 1, l: [1]}");
+        let failed = |index, err| Err(NbtError::PatchFailed(index, Box::new(err)));
+        let test = |path: &str, v: &str| PatchOp::Test {
+            path: path.to_string(),
+            value: snbt(v),
+        };
+
+        // 失败的时候不会改动原来的值
+        let patch = NbtPatch::from(vec![add("b", "1"), test("a", "1b")]);
+        assert_eq!(
+            value.apply_patch(&patch),
+            failed(1, NbtError::PatchTestFailed("a".to_string()))
+        );
+        assert_eq!(value, snbt("{a: 1, l: [1]}"));
+        assert_eq!(
+            value.apply_patch(&NbtPatch::from(vec![add("x.y", "1")])),
+            failed(0, NbtError::PathNotFound("x".to_string()))
+        );
+        assert_eq!(
+            value.apply_patch(&NbtPatch::from(vec![add("l[0]", "1b")])),
+            failed(0, NbtError::PathTypeMismatch("l[0]".to_string(), 3, 1))
+        );
+        assert_eq!(
+            value.apply_patch(&NbtPatch::from(vec![add("l[2]", "1")])),
+            failed(0, NbtError::PathNotFound("l[2]".to_string()))
+        );
+        let remove = NbtPatch::from(vec![PatchOp::Remove {
+            path: "b".to_string(),
+        }]);
+        assert_eq!(value.apply_patch(&remove), failed(0, NbtError::PathNotFound("b".to_string())));
+        assert_eq!(
+            value.invert_patch(&remove).err(),
+            failed(0, NbtError::PathNotFound("b".to_string())).err()
+        );
+
+        // 浮点数按位比较
+        let mut value = snbt("{d: 0d, f: 0f}");
+        value.set_path("d", NbtValue::Double(f64::NAN)).unwrap();
+        let nan = NbtPatch::from(vec![PatchOp::Test {
+            path: "d".to_string(),
+            value: NbtValue::Double(f64::NAN),
+        }]);
+        assert_eq!(value.apply_patch(&nan), Ok(()));
+        let negative_zero = NbtPatch::from(vec![PatchOp::Test {
+            path: "f".to_string(),
+            value: NbtValue::Float(-0.0),
+        }]);
+        assert_eq!(
+            value.apply_patch(&negative_zero),
+            failed(0, NbtError::PatchTestFailed("f".to_string()))
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let text = "[{op: add, path: 'a[0]', value: 1b}, {op: remove, path: b}, \
+                    {op: replace, path: c, value: {}}, {op: move, from: d, path: e}, \
+                    {op: copy, from: f, path: g}, {op: test, path: h, value: [I; 1]}]";
+        let patch = NbtPatch::from_snbt(text).unwrap();
+        assert_eq!(patch.len(), 6);
+        assert_eq!(patch.ops[0], add("a[0]", "1b"));
+        assert_eq!(patch.to_nbt().unwrap(), snbt(text));
+        assert_eq!(NbtPatch::from_snbt(&patch.to_snbt()), Ok(patch));
+
+        assert_eq!(
+            NbtPatch::from_snbt("[{op: add, value: 1}]"),
+            Err(NbtError::PathNotFound("[0].path".to_string()))
+        );
+        assert_eq!(
+            NbtPatch::from_snbt("[{op: remove, path: 1}]"),
+            Err(NbtError::PathTypeMismatch("[0].path".to_string(), 8, 3))
+        );
+        assert!(PatchOp::from_nbt(snbt("{op: frobnicate, path: a}")).is_err());
+    }
+
+    #[test]
+    fn from_diff() {
+        let cases = [
+            ("{a: 1, b: [1, 2, 3], c: {d: 1}}", "{a: 1s, b: [1], c: {e: 2}, f: [I; 1]}"),
+            ("[{a: 1}, {a: 2}]", "[{a: 2}, {a: 2}, {a: 3}, {}]"),
+            ("{x: 1}", "[]"),
+        ];
+        for (old, new) in cases {
+            let (old, new) = (snbt(old), snbt(new));
+            let mut value = old.clone();
+            value.apply_patch(&NbtPatch::from(old.diff(&new))).unwrap();
+            assert_eq!(value.to_canonical(), new.to_canonical());
+        }
+
+        let options = DiffOptions::default().with_list("Items", ListAlign::ByKey("Slot".into()));
+        let old = snbt("{Items: [{Slot: 0b}, {Slot: 1b, n: 1}, {Slot: 2b}, {Slot: 3b}]}");
+        let new = snbt("{Items: [{Slot: 1b, n: 2}, {Slot: 4b}, {Slot: 3b}, {Slot: 5b}]}");
+        let mut value = old.clone();
+        value.apply_patch(&NbtPatch::from(old.diff_with(&new, &options))).unwrap();
+        assert_eq!(value, new);
+
+        // 顺序变了的元素要先移过去再改
+        let cases = [
+            (
+                "{Items: [{Slot: 0b, a: 1}, {Slot: 1b, a: 1}]}",
+                "{Items: [{Slot: 1b, a: 2}, {Slot: 0b, a: 1}]}",
+            ),
+            (
+                "{Items: [{Slot: 0b}, {Slot: 1b}, {Slot: 2b, a: 1}, {Slot: 3b}, {x: 1}]}",
+                "{Items: [{Slot: 3b}, {x: 1}, {Slot: 9b}, {Slot: 2b, a: 2}, {Slot: 0b}]}",
+            ),
+        ];
+        for (old, new) in cases {
+            let (old, new) = (snbt(old), snbt(new));
+            let diff = old.diff_with(&new, &options);
+            let mut value = old.clone();
+            value.apply_patch(&NbtPatch::from(&diff)).unwrap();
+            assert_eq!(value, new, "{}", diff);
+        }
+        assert_eq!(
+            snbt("{Items: [{Slot: 0b, a: 1}, {Slot: 1b, a: 1}]}")
+                .diff_with(&snbt("{Items: [{Slot: 1b, a: 2}, {Slot: 0b, a: 1}]}"), &options)
+                .to_string(),
+            "> Items[0]: <- Items[1]\n~ Items[0].a: 1 -> 2\n"
+        );
+    }
+
+    #[test]
+    fn from_diff_mixed() {
+        let options = DiffOptions::default().with_list("Items", ListAlign::ByKey("Slot".into()));
+        let cases = [
+            // 元素类型变了, 包括嵌套的 List 和根节点
+            ("{L: [1, 2], M: [[1b], [2b]], N: []}", "{L: [a, b, c], M: [[1s]], N: [1d]}"),
+            ("[1, 2]", "[{a: 1}]"),
+            // 按 key 移动, 删除, 新增和元素类型变化一起出现
+            (
+                "{Items: [{Slot: 0b, tag: [1]}, {Slot: 1b}, {Slot: 2b, tag: [a]}], Tags: [x, y]}",
+                "{Items: [{Slot: 2b, tag: [1f]}, {Slot: 3b}, {Slot: 0b, tag: [1, 2]}], Tags: [1b]}",
+            ),
+            ("{Items: [{Slot: 0b}, {Slot: 1b}]}", "{Items: [a, b]}"),
+            ("{Items: [{Slot: 0b}, {Slot: 1b}, {Slot: 2b}]}", "{Items: [{Slot: 2b}]}"),
+        ];
+        for (old, new) in cases {
+            let (old, new) = (snbt(old), snbt(new));
+            for diff in [old.diff(&new), old.diff_with(&new, &options)] {
+                let mut value = old.clone();
+                value.apply_patch(&NbtPatch::from(&diff)).unwrap();
+                assert_eq!(value, new, "{}", diff);
+            }
+        }
+    }
+}
+
+mod ord {
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{BTreeSet, HashMap};
+    use std::hash::{Hash, Hasher};
+
+    use super::*;
+    use crate::{NbtKey, NbtUnorderedKey};
+
+    fn snbt(text: &str) -> NbtValue { NbtValue::from_snbt(text).unwrap() }
+
+    fn hash(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn samples() -> Vec<NbtValue> {
+        let mut values = [
+            "1b",
+            "2b",
+            "1s",
+            "1",
+            "-1",
+            "1L",
+            "1.5f",
+            "1.5d",
+            "[B; 1b]",
+            "[B; 1b, 2b]",
+            "''",
+            "a",
+            "b",
+            "[]",
+            "[1b]",
+            "[1]",
+            "[1, 2]",
+            "[2]",
+            "[[1], [2]]",
+            "[{a: 1}]",
+            "[{a: 2}]",
+            "{}",
+            "{a: 1}",
+            "{a: 1, b: 2}",
+            "{b: 2, a: 1}",
+            "{a: [1.0f, 2.0f]}",
+            "[I; 1]",
+            "[L; 1L]",
+        ]
+        .iter()
+        .map(|s| snbt(s))
+        .collect::<Vec<_>>();
+        values.push(NbtValue::List(NbtList::Compound(vec![])));
+        values
+    }
+
+    #[test]
+    fn agrees_with_partial_eq() {
+        let values = samples();
+        for a in &values {
+            for b in &values {
+                let ordering = a.total_cmp(b);
+                assert_eq!(ordering == Ordering::Equal, a == b, "{} {}", a, b);
+                assert_eq!(b.total_cmp(a), ordering.reverse());
+                assert_eq!(NbtKey(a.clone()) == NbtKey(b.clone()), a == b);
+                if a == b {
+                    assert_eq!(hash(&NbtKey(a.clone())), hash(&NbtKey(b.clone())));
+                }
+            }
+        }
+        // 先按类型排序
+        let set = values.into_iter().map(NbtKey).collect::<BTreeSet<_>>();
+        let tags = set.iter().map(|v| v.tag()).collect::<Vec<_>>();
+        assert!(tags.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(set.first().map(|v| v.0.clone()), Some(NbtValue::Byte(1)));
+        assert_eq!(set.len(), 29);
+    }
+
+    #[test]
+    fn floats() {
+        let nan = NbtValue::Double(f64::NAN);
+        assert_ne!(nan, nan);
+        assert_eq!(NbtKey(nan.clone()), NbtKey(nan.clone()));
+        assert_eq!(hash(&NbtKey(nan.clone())), hash(&NbtKey(nan.clone())));
+        assert_ne!(
+            NbtKey(nan.clone()),
+            NbtKey(NbtValue::Double(f64::from_bits(0x7ff8_0000_0000_0001)))
+        );
+        // 按位比较, 0.0 和 -0.0 不一样
+        assert_eq!(NbtValue::Float(0.0), NbtValue::Float(-0.0));
+        assert_eq!(NbtValue::Float(-0.0).total_cmp(&NbtValue::Float(0.0)), Ordering::Less);
+        let list = NbtValue::List(NbtList::Float(vec![f32::NAN, 1.0]));
+        assert_eq!(NbtKey(list.clone()), NbtKey(list.clone()));
+        assert_eq!(
+            list.total_cmp(&NbtValue::List(NbtList::Float(vec![f32::NAN]))),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn unordered() {
+        let a = snbt("{id: stone, tag: {x: 1, y: [{b: 1, a: 2}]}}");
+        let b = snbt("{tag: {y: [{a: 2, b: 1}], x: 1}, id: stone}");
+        assert_ne!(a, b);
+        assert_ne!(NbtKey(a.clone()), NbtKey(b.clone()));
+        assert_eq!(a.total_cmp_unordered(&b), Ordering::Equal);
+        assert_eq!(NbtUnorderedKey(a.clone()), NbtUnorderedKey(b.clone()));
+        assert_eq!(hash(&NbtUnorderedKey(a.clone())), hash(&NbtUnorderedKey(b.clone())));
+
+        // List 里元素的顺序还是要管
+        let c = snbt("{id: stone, tag: {x: 1, y: [{a: 1, b: 2}]}}");
+        assert_ne!(NbtUnorderedKey(a.clone()), NbtUnorderedKey(c));
+        assert_ne!(NbtUnorderedKey(snbt("[1, 2]")), NbtUnorderedKey(snbt("[2, 1]")));
+
+        let mut counts = HashMap::new();
+        for value in [a, b, snbt("{id: dirt}")] {
+            *counts.entry(NbtUnorderedKey::from(value)).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&NbtUnorderedKey(snbt("{id: dirt}"))], 1);
+    }
+}
+
+mod schema {
+    use super::*;
+    use crate::{NbtError, NbtSchema, SchemaNumber, SchemaViolation, ViolationKind};
+
+    fn snbt(text: &str) -> NbtValue { NbtValue::from_snbt(text).unwrap() }
+
+    fn violations(schema: &NbtSchema, value: &str) -> Vec<(String, ViolationKind)> {
+        schema.validate(&snbt(value)).into_iter().map(|v| (v.path, v.kind)).collect()
+    }
+
+    fn at(path: &str, kind: ViolationKind) -> (String, ViolationKind) { (path.to_string(), kind) }
+
+    #[test]
+    fn validate() {
+        let schema = NbtSchema::compound()
+            .field("id", NbtSchema::string().one_of(["stone", "dirt"]))
+            .field("Count", NbtSchema::byte().range(1, 64))
+            .optional("Damage", NbtSchema::any().min(0))
+            .optional("Colors", NbtSchema::int_array().max(0xffffff))
+            .optional("Lore", NbtSchema::list(NbtSchema::string()))
+            .optional("tag", NbtSchema::compound().open());
+        assert_eq!(violations(&schema, "{id: stone, Count: 1b}"), []);
+        assert_eq!(
+            violations(&schema, "{id: dirt, Count: 64b, Damage: 3s, tag: {x: 1}, Lore: []}"),
+            []
+        );
+        assert_eq!(
+            violations(
+                &schema,
+                "{id: sand, Count: 0b, Damage: -0.5d, Colors: [I; 1, 16777216, -1, 16777217], \
+                 Lore: [a, b], 'bad.key': 1, tag: 1}"
+            ),
+            [
+                at("id", ViolationKind::NotAllowed("sand".to_string())),
+                at(
+                    "Count",
+                    ViolationKind::OutOfRange {
+                        value: SchemaNumber::Int(0),
+                        min: Some(SchemaNumber::Int(1)),
+                        max: Some(SchemaNumber::Int(64))
+                    }
+                ),
+                at(
+                    "Damage",
+                    ViolationKind::OutOfRange {
+                        value: SchemaNumber::Float(-0.5),
+                        min: Some(SchemaNumber::Int(0)),
+                        max: None
+                    }
+                ),
+                at(
+                    "Colors[1]",
+                    ViolationKind::OutOfRange {
+                        value: SchemaNumber::Int(16777216),
+                        min: None,
+                        max: Some(SchemaNumber::Int(16777215))
+                    }
+                ),
+                at(
+                    "Colors[3]",
+                    ViolationKind::OutOfRange {
+                        value: SchemaNumber::Int(16777217),
+                        min: None,
+                        max: Some(SchemaNumber::Int(16777215))
+                    }
+                ),
+                at(
+                    "tag",
+                    ViolationKind::TypeMismatch {
+                        expected: 10,
+                        found: 3
+                    }
+                ),
+                at("\"bad.key\"", ViolationKind::Unexpected),
+            ]
+        );
+        assert_eq!(
+            violations(&schema, "{Lore: [[a]]}"),
+            [
+                at("id", ViolationKind::Missing),
+                at("Count", ViolationKind::Missing),
+                at(
+                    "Lore[0]",
+                    ViolationKind::TypeMismatch {
+                        expected: 8,
+                        found: 9
+                    }
+                ),
+            ]
+        );
+        // 根节点类型不对就不往下检查
+        assert_eq!(
+            violations(&schema, "[{id: stone}]"),
+            [at(
+                "",
+                ViolationKind::TypeMismatch {
+                    expected: 10,
+                    found: 9
+                }
+            )]
+        );
+        assert!(!NbtSchema::double().max(1.0).is_valid(&NbtValue::Double(f64::NAN)));
+        assert!(NbtSchema::any().is_valid(&snbt("{a: [1, 2]}")));
+        // 没有要求类型的时候不管多余的 key
+        let schema = NbtSchema::any().field("a", NbtSchema::int());
+        assert!(schema.is_valid(&snbt("{a: 1, b: 2}")));
+        assert!(schema.is_valid(&snbt("[1]")));
+        assert!(!NbtSchema::compound().is_valid(&snbt("{b: 2}")));
+    }
+
+    #[test]
+    fn display() {
+        let show = |path: &str, kind| {
+            SchemaViolation {
+                path: path.to_string(),
+                kind,
+            }
+            .to_string()
+        };
+        assert_eq!(show("", ViolationKind::Missing), "<root>: 缺少必需的 key");
+        assert_eq!(
+            show("a[0]", ViolationKind::NotAllowed("x".to_string())),
+            "a[0]: \"x\" 不是允许的值"
+        );
+        assert_eq!(
+            show(
+                "a",
+                ViolationKind::OutOfRange {
+                    value: SchemaNumber::Float(1.5),
+                    min: None,
+                    max: Some(SchemaNumber::Int(1))
+                }
+            ),
+            "a: 1.5 超出范围 ..=1"
+        );
+    }
+
+    #[test]
+    fn long_range() {
+        // 2^53 以上的 Long 转成 f64 会丢精度, 整数边界要按整数比较
+        let schema = NbtSchema::long().max(i64::MAX - 1);
+        assert!(schema.is_valid(&NbtValue::Long(i64::MAX - 1)));
+        assert!(!schema.is_valid(&NbtValue::Long(i64::MAX)));
+        let schema = NbtSchema::long_array().range(-(1_i64 << 53), 1_i64 << 53);
+        assert!(schema.is_valid(&NbtValue::LongArray(vec![-(1 << 53), 1 << 53])));
+        assert_eq!(
+            schema.validate(&NbtValue::LongArray(vec![(1 << 53) + 1, -(1 << 53) - 1])).len(),
+            2
+        );
+        // 整数和浮点数边界之间也是精确比较
+        let schema = NbtSchema::long().max(9007199254740992.0);
+        assert!(schema.is_valid(&NbtValue::Long(1 << 53)));
+        assert!(!schema.is_valid(&NbtValue::Long((1 << 53) + 1)));
+        assert!(NbtSchema::long().max(9.3e18).is_valid(&NbtValue::Long(i64::MAX)));
+        assert!(NbtSchema::long().min(-9.3e18).is_valid(&NbtValue::Long(i64::MIN)));
+        assert!(!NbtSchema::int().max(0.5).is_valid(&NbtValue::Int(1)));
+        assert!(NbtSchema::int().min(-0.5).is_valid(&NbtValue::Int(0)));
+        assert!(!NbtSchema::int().min(-0.5).is_valid(&NbtValue::Int(-1)));
+        // 写出去再读回来, 放不下 Int 的边界写成 Long
+        let schema = NbtSchema::long().range(i64::MIN, i64::MAX);
+        assert_eq!(schema.to_snbt(), format!("{{type:long,min:{}L,max:{}L}}", i64::MIN, i64::MAX));
+        assert_eq!(NbtSchema::from_snbt(&schema.to_snbt()), Ok(schema));
+    }
+
+    #[test]
+    fn load() {
+        let schema = NbtSchema::from_snbt(
+            "{type: compound, open: true, fields: {\
+             id: string, \
+             Count: {type: byte, min: 1b, max: 64}, \
+             Pos: {type: list, elements: {type: double, min: -3e7, max: 3e7}, optional: 1b}, \
+             Mode: {values: [a, b]}, \
+             Any: {optional: true}}}",
+        )
+        .unwrap();
+        let built = NbtSchema::compound()
+            .open()
+            .field("id", NbtSchema::string())
+            .field("Count", NbtSchema::byte().range(1, 64))
+            .optional("Pos", NbtSchema::list(NbtSchema::double().range(-3e7, 3e7)))
+            .field("Mode", NbtSchema::any().one_of(["a", "b"]))
+            .optional("Any", NbtSchema::any());
+        assert_eq!(schema, built);
+        assert_eq!(NbtSchema::from_snbt(&built.to_snbt()), Ok(built));
+        assert_eq!(NbtSchema::int().to_snbt(), "int");
+        assert_eq!(NbtSchema::from_snbt("any"), Ok(NbtSchema::any()));
+
+        assert_eq!(
+            NbtSchema::from_snbt("{type: integer}"),
+            Err(NbtError::UnknownErr("未知的类型名: integer".to_string()))
+        );
+        assert_eq!(
+            NbtSchema::from_snbt("{type: int, mni: 1}"),
+            Err(NbtError::UnknownErr("schema 里未知的 key: mni".to_string()))
+        );
+        assert_eq!(
+            NbtSchema::from_snbt("{fields: {a: {min: x}}}"),
+            Err(NbtError::PathTypeMismatch("fields.a.min".to_string(), 6, 8))
+        );
+        assert_eq!(
+            NbtSchema::from_snbt("{fields: {a: {optional: yes}}}"),
+            Err(NbtError::PathTypeMismatch("fields.a.optional".to_string(), 1, 8))
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_json() {
+        let json = serde_json::json!({
+            "type": "compound",
+            "fields": {
+                "Health": {"type": "float", "min": 0, "max": 20.5},
+                "Tags": {"type": "list", "elements": "string", "optional": true}
+            }
+        });
+        let schema = NbtSchema::from_json(&json).unwrap();
+        assert_eq!(
+            schema,
+            NbtSchema::compound()
+                .field("Health", NbtSchema::float().range(0.0, 20.5))
+                .optional("Tags", NbtSchema::list(NbtSchema::string()))
+        );
+        assert!(schema.is_valid(&snbt("{Health: 20f, Tags: [a]}")));
+    }
+}
+
+#[cfg(feature = "derive")]
+mod derive {
+    use super::*;
+    use crate::{FromNbt, NbtError, ToNbt};
+
+    #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+    #[nbt(rename_all = "PascalCase")]
+    struct Item {
+        #[nbt(rename = "id")]
+        id: String,
+        count: i8,
+        tag: Option<NbtCompound>,
+    }
+
+    fn default_air() -> i16 { 300 }
+
+    #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+    #[nbt(rename_all = "camelCase")]
+    struct Player {
+        player_game_type: i32,
+        #[nbt(with = "crate::convert::uuid")]
+        uuid: [u8; 16],
+        #[nbt(default = "default_air")]
+        air: i16,
+        #[nbt(default)]
+        on_ground: bool,
+        inventory: Vec<Item>,
+        #[nbt(long_array)]
+        seeds: Vec<i64>,
+        #[nbt(byte_array)]
+        flags: Option<Vec<i8>>,
+        #[nbt(skip)]
+        cache: Vec<String>,
+        r#type: String,
+        #[nbt(flatten)]
+        pos: Pos,
+        #[nbt(flatten)]
+        extra: NbtCompound,
+    }
+
+    #[derive(ToNbt, FromNbt, Debug, PartialEq, Default)]
+    struct Pos {
+        x: f64,
+        y: f64,
+    }
+
+    fn player() -> Player {
+        Player {
+            player_game_type: 1,
+            uuid: [0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4],
+            air: 300,
+            on_ground: false,
+            inventory: vec![Item {
+                id: "minecraft:stone".to_string(),
+                count: 64,
+                tag: None,
+            }],
+            seeds: vec![1],
+            flags: None,
+            cache: vec![],
+            r#type: "player".to_string(),
+            pos: Pos { x: 0.5, y: 64.0 },
+            extra: NbtCompound::new(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = NbtValue::from_snbt(
+            "{playerGameType: 1, uuid: [I; 1, 2, 3, 4], air: 300s, onGround: 0b, \
+             inventory: [{id: 'minecraft:stone', Count: 64b}], seeds: [L; 1L], type: player, \
+             x: 0.5d, y: 64.0d}",
+        )
+        .unwrap();
+        assert_eq!(player().to_nbt(), Ok(value.clone()));
+        assert_eq!(Player::from_nbt(value), Ok(player()));
+    }
+
+    #[test]
+    fn defaults_and_extra() {
+        let value = NbtValue::from_snbt(
+            "{playerGameType: 0, uuid: [I; 1, 2, 3, 4], inventory: [], seeds: [], type: player, \
+             flags: [B; 1b], x: 1d, y: 2d, Motion: [0d, 0d, 0d], Health: 20f}",
+        )
+        .unwrap();
+        let player = Player::from_nbt(value).unwrap();
+        assert_eq!(player.air, 300);
+        assert!(!player.on_ground);
+        assert_eq!(player.flags, Some(vec![1]));
+        assert_eq!(player.pos, Pos { x: 1.0, y: 2.0 });
+        assert_eq!(
+            NbtValue::Compound(player.extra.clone()),
+            NbtValue::from_snbt("{Motion: [0d, 0d, 0d], Health: 20f}").unwrap()
+        );
+        let value = player.to_nbt().unwrap();
+        assert_eq!(
+            value.get_path("Health").map(|v| v.to_value()).ok().as_ref(),
+            Some(&NbtValue::Float(20.0))
+        );
+        assert_eq!(
+            value.get_path("flags").map(|v| v.to_value()).ok().as_ref(),
+            Some(&NbtValue::ByteArray(vec![1]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Item::from_nbt(NbtValue::from_snbt("{Count: 1b}").unwrap()),
+            Err(NbtError::PathNotFound("id".to_string()))
+        );
+        assert_eq!(
+            Item::from_nbt(NbtValue::from_snbt("{id: a, Count: 1}").unwrap()),
+            Err(NbtError::PathTypeMismatch("Count".to_string(), 1, 3))
+        );
+        assert_eq!(Item::from_nbt(NbtValue::Int(1)), Err(NbtError::IncorrectType(10, 3)));
+
+        let mut value = player().to_nbt().unwrap();
+        value.set_path("inventory[0].Count", NbtValue::String("x".to_string())).unwrap();
+        assert_eq!(
+            Player::from_nbt(value),
+            Err(NbtError::PathTypeMismatch("inventory[0].Count".to_string(), 1, 8))
+        );
+        let mut value = player().to_nbt().unwrap();
+        value.set_path("seeds", NbtValue::IntArray(vec![])).unwrap();
+        assert_eq!(
+            Player::from_nbt(value),
+            Err(NbtError::PathTypeMismatch("seeds".to_string(), 12, 11))
+        );
+        let mut value = player().to_nbt().unwrap();
+        value.set_path("x", NbtValue::Float(0.0)).unwrap();
+        assert_eq!(Player::from_nbt(value), Err(NbtError::PathTypeMismatch("x".to_string(), 6, 5)));
+    }
+
+    #[test]
+    fn generics() {
+        #[derive(ToNbt, FromNbt, Debug, PartialEq)]
+        struct Wrapper<T> {
+            value: T,
+            values: Vec<T>,
+        }
+
+        let wrapper = Wrapper {
+            value: 1i16,
+            values: vec![2, 3],
+        };
+        let value = wrapper.to_nbt().unwrap();
+        assert_eq!(value, NbtValue::from_snbt("{value: 1s, values: [2s, 3s]}").unwrap());
+        assert_eq!(Wrapper::from_nbt(value), Ok(wrapper));
+    }
+}
@@ -1,15 +1,72 @@
-use crate::nbt_version::{BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtWriteTrait};
-use crate::{NbtError, NbtResult, NbtValue};
+use crate::convert::path_key;
+use crate::nbt_version::{
+    BedrockDisk, BedrockNetVarInt, Java, JavaNetAfter1_20_2, NbtWriteTrait, RootName,
+};
+use crate::{NbtCompound, NbtError, NbtList, NbtResult, NbtTypeId, NbtValue};
+
+/// 直接写入 i8 数组
+///
+/// i8 和 u8 内存布局完全一致, 直接整块复制
+#[inline]
+pub fn extend_i8_array(writer: &mut Vec<u8>, data: &[i8]) {
+    let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len()) };
+    writer.extend_from_slice(bytes);
+}
+
+macro_rules! extend_array {
+    ($name:ident, $ty:ty, $size:literal, $to_bytes:ident, $endian:literal) => {
+        #[doc = concat!("直接写入 ", stringify!($ty), " 数组 (", $endian, " endian)")]
+        ///
+        /// 只扩容一次, 不产生临时的 Vec
+        ///
+        /// 字节序和本机一致时直接整块复制, 否则整块转换字节序
+        #[inline]
+        pub fn $name(writer: &mut Vec<u8>, data: &[$ty]) {
+            if cfg!(target_endian = $endian) {
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * $size)
+                };
+                writer.extend_from_slice(bytes);
+            } else {
+                writer.reserve(data.len() * $size);
+                let start = writer.len();
+                unsafe {
+                    // 直接写进预留的空间, 省掉一次清零
+                    let dst = writer.as_mut_ptr().add(start) as *mut [u8; $size];
+                    for (i, x) in data.iter().enumerate() {
+                        dst.add(i).write_unaligned(x.$to_bytes());
+                    }
+                    writer.set_len(start + data.len() * $size);
+                }
+            }
+        }
+    };
+}
+
+extend_array!(extend_be_i32_array, i32, 4, to_be_bytes, "big");
+extend_array!(extend_le_i32_array, i32, 4, to_le_bytes, "little");
+extend_array!(extend_be_i64_array, i64, 8, to_be_bytes, "big");
+extend_array!(extend_le_i64_array, i64, 8, to_le_bytes, "little");
 
 /// 最简单的一集
 impl NbtWriteTrait for Java {
+    #[inline]
+    fn write_i16(writer: &mut Vec<u8>, data: i16) { writer.extend_from_slice(&data.to_be_bytes()); }
+    #[inline]
+    fn write_i32(writer: &mut Vec<u8>, data: i32) { writer.extend_from_slice(&data.to_be_bytes()); }
+    #[inline]
+    fn write_i64(writer: &mut Vec<u8>, data: i64) { writer.extend_from_slice(&data.to_be_bytes()); }
+    #[inline]
+    fn write_f32(writer: &mut Vec<u8>, data: f32) { writer.extend_from_slice(&data.to_be_bytes()); }
+    #[inline]
+    fn write_f64(writer: &mut Vec<u8>, data: f64) { writer.extend_from_slice(&data.to_be_bytes()); }
     #[inline]
     fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]) {
         // 写好 tag 了, 直接写入信息
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_be_bytes());
         // 写入数据
-        writer.extend_from_slice(data.iter().map(|x| *x as u8).collect::<Vec<u8>>().as_slice());
+        extend_i8_array(writer, data);
     }
     #[inline]
     fn write_i32_array(writer: &mut Vec<u8>, data: &[i32]) {
@@ -17,9 +74,7 @@ impl NbtWriteTrait for Java {
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_be_bytes());
         // 写入数据
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_be_bytes()).collect::<Vec<[u8; 4]>>().concat(),
-        );
+        extend_be_i32_array(writer, data);
     }
     #[inline]
     fn write_i64_array(writer: &mut Vec<u8>, data: &[i64]) {
@@ -27,9 +82,7 @@ impl NbtWriteTrait for Java {
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_be_bytes());
         // 写入数据
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_be_bytes()).collect::<Vec<[u8; 8]>>().concat(),
-        );
+        extend_be_i64_array(writer, data);
     }
     #[inline]
     fn write_nbt_string(writer: &mut Vec<u8>, data: &str) {
@@ -39,50 +92,39 @@ impl NbtWriteTrait for Java {
         writer.extend_from_slice(data.as_bytes());
     }
     #[inline]
-    fn write_list(writer: &mut Vec<u8>, data: &[NbtValue]) -> NbtResult<()> {
-        if data.is_empty() {
-            // 写入一个空的 tag
-            writer.extend_from_slice(&0i8.to_be_bytes());
-            return Ok(());
-        }
-        // 遍历检查一遍所有的 tag 是否一致
-        let tag = data.first().unwrap().tag();
-        if !data.iter().all(|x| x.tag() == tag) {
-            return Err(NbtError::ListTypeNotSame(data.iter().map(|x| x.tag()).collect()));
-        }
+    fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()> {
         // 写入 tag
-        writer.push(tag);
+        writer.push(data.element_tag());
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_be_bytes());
-        // 写入数据
-        for i in data {
-            match i {
-                NbtValue::Byte(x) => writer.push(*x as u8),
-                NbtValue::Short(x) => writer.extend_from_slice(&x.to_be_bytes()),
-                NbtValue::Int(x) => writer.extend_from_slice(&x.to_be_bytes()),
-                NbtValue::Long(x) => writer.extend_from_slice(&x.to_be_bytes()),
-                NbtValue::Float(x) => writer.extend_from_slice(&x.to_be_bytes()),
-                NbtValue::Double(x) => writer.extend_from_slice(&x.to_be_bytes()),
-                NbtValue::ByteArray(x) => Self::write_i8_array(writer, x),
-                NbtValue::IntArray(x) => Self::write_i32_array(writer, x),
-                NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
-                NbtValue::String(x) => Self::write_nbt_string(writer, x),
-                NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
+        // 写入数据, 类型由 NbtList 保证一致, 不用再检查
+        match data {
+            NbtList::End => (),
+            NbtList::Byte(x) => extend_i8_array(writer, x),
+            NbtList::Short(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_be_bytes())),
+            NbtList::Int(x) => extend_be_i32_array(writer, x),
+            NbtList::Long(x) => extend_be_i64_array(writer, x),
+            NbtList::Float(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_be_bytes())),
+            NbtList::Double(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_be_bytes())),
+            NbtList::ByteArray(x) => x.iter().for_each(|v| Self::write_i8_array(writer, v)),
+            NbtList::String(x) => x.iter().for_each(|v| Self::write_nbt_string(writer, v)),
+            NbtList::List(x) => {
+                for v in x {
+                    Self::write_list(writer, v)?;
                 }
             }
+            NbtList::Compound(x) => {
+                for v in x {
+                    Self::write_compound(writer, v)?;
+                }
+            }
+            NbtList::IntArray(x) => x.iter().for_each(|v| Self::write_i32_array(writer, v)),
+            NbtList::LongArray(x) => x.iter().for_each(|v| Self::write_i64_array(writer, v)),
         }
         Ok(())
     }
     #[inline]
-    fn write_compound(
-        writer: &mut Vec<u8>,
-        name: Option<&String>,
-        data: &[(String, NbtValue)],
-    ) -> NbtResult<()> {
-        // 写入自己的名字
-        Self::write_nbt_string(writer, name.unwrap_or(&"".to_string()));
+    fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()> {
         for (key, value) in data {
             // 写入 tag
             writer.push(value.tag());
@@ -101,38 +143,39 @@ impl NbtWriteTrait for Java {
                 NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
                 NbtValue::String(x) => Self::write_nbt_string(writer, x),
                 NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
-                }
+                NbtValue::Compound(data) => Self::write_compound(writer, data)?,
             }
         }
         // 写入结束 tag
         writer.push(0);
         Ok(())
     }
-    fn write_to(value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        match value {
-            NbtValue::Compound(name, data) => {
-                buff.push(value.tag());
-                Self::write_compound(buff, name.as_ref(), data)?
-            }
-            x => return Err(NbtError::WrongRootType(x.tag())),
+    fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> {
+        match tag {
+            // 只能是 Compound, 文件里总是有名字 (可以是空字符串)
+            10 => Ok(RootName::Required),
+            x => Err(NbtError::WrongRootType(x)),
         }
-        Ok(())
-    }
-    fn write_to_with_name(name: &str, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        buff.push(value.tag());
-        // 写入 key
-        Self::write_nbt_string(buff, name);
-        // 写入 value
-        Self::write_to(value, buff)?;
-        Ok(())
     }
+    #[inline]
+    fn i32_len(_value: i32) -> usize { 4 }
+    #[inline]
+    fn i64_len(_value: i64) -> usize { 8 }
+    #[inline]
+    fn nbt_string_len(data: &str) -> usize { 2 + data.len() }
 }
 
 impl NbtWriteTrait for JavaNetAfter1_20_2 {
+    #[inline]
+    fn write_i16(writer: &mut Vec<u8>, data: i16) { Java::write_i16(writer, data); }
+    #[inline]
+    fn write_i32(writer: &mut Vec<u8>, data: i32) { Java::write_i32(writer, data); }
+    #[inline]
+    fn write_i64(writer: &mut Vec<u8>, data: i64) { Java::write_i64(writer, data); }
+    #[inline]
+    fn write_f32(writer: &mut Vec<u8>, data: f32) { Java::write_f32(writer, data); }
+    #[inline]
+    fn write_f64(writer: &mut Vec<u8>, data: f64) { Java::write_f64(writer, data); }
     #[inline]
     fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]) { Java::write_i8_array(writer, data); }
     #[inline]
@@ -142,93 +185,60 @@ impl NbtWriteTrait for JavaNetAfter1_20_2 {
     #[inline]
     fn write_nbt_string(writer: &mut Vec<u8>, data: &str) { Java::write_nbt_string(writer, data); }
     #[inline]
-    fn write_list(writer: &mut Vec<u8>, data: &[NbtValue]) -> NbtResult<()> {
+    fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()> {
         Java::write_list(writer, data)
     }
     #[inline]
-    fn write_compound(
-        writer: &mut Vec<u8>,
-        name: Option<&String>,
-        data: &[(String, NbtValue)],
-    ) -> NbtResult<()> {
-        Java::write_compound(writer, name, data)
-    }
-    #[inline]
-    fn write_to(value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        match value {
-            NbtValue::Compound(_, data) => {
-                // 忽略名字
-                buff.push(value.tag());
-                for (key, value) in data {
-                    // 写入 tag
-                    buff.push(value.tag());
-                    // 写入 key
-                    Self::write_nbt_string(buff, key);
-                    // 写入 value
-                    match value {
-                        NbtValue::Byte(x) => buff.push(*x as u8),
-                        NbtValue::Short(x) => buff.extend_from_slice(&x.to_be_bytes()),
-                        NbtValue::Int(x) => buff.extend_from_slice(&x.to_be_bytes()),
-                        NbtValue::Long(x) => buff.extend_from_slice(&x.to_be_bytes()),
-                        NbtValue::Float(x) => buff.extend_from_slice(&x.to_be_bytes()),
-                        NbtValue::Double(x) => buff.extend_from_slice(&x.to_be_bytes()),
-                        NbtValue::ByteArray(x) => Self::write_i8_array(buff, x),
-                        NbtValue::IntArray(x) => Self::write_i32_array(buff, x),
-                        NbtValue::LongArray(x) => Self::write_i64_array(buff, x),
-                        NbtValue::String(x) => Self::write_nbt_string(buff, x),
-                        NbtValue::List(x) => Self::write_list(buff, x)?,
-                        NbtValue::Compound(name, data) => {
-                            Self::write_compound(buff, name.as_ref(), data)?
-                        }
-                    }
-                }
-                // 写入结束 tag
-                buff.push(0);
-                Ok(())
-            }
-            x => Err(NbtError::WrongRootType(x.tag())),
+    fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()> {
+        Java::write_compound(writer, data)
+    }
+    fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> {
+        match tag {
+            // 只能是 Compound, 而且没有名字
+            10 => Ok(RootName::Forbidden),
+            x => Err(NbtError::WrongRootType(x)),
         }
     }
     #[inline]
-    fn write_to_with_name(_name: &str, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // drop name
-        JavaNetAfter1_20_2::write_to(value, buff)
-    }
+    fn i32_len(value: i32) -> usize { Java::i32_len(value) }
     #[inline]
-    fn to_bytes(value: &NbtValue) -> NbtResult<Vec<u8>> {
-        let mut buff = Vec::new();
-        JavaNetAfter1_20_2::write_to(value, &mut buff)?;
-        Ok(buff)
-    }
+    fn i64_len(value: i64) -> usize { Java::i64_len(value) }
+    #[inline]
+    fn nbt_string_len(data: &str) -> usize { Java::nbt_string_len(data) }
 }
 
 /// 比较痛苦的一集
 impl NbtWriteTrait for BedrockDisk {
+    #[inline]
+    fn write_i16(writer: &mut Vec<u8>, data: i16) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_i32(writer: &mut Vec<u8>, data: i32) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_i64(writer: &mut Vec<u8>, data: i64) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_f32(writer: &mut Vec<u8>, data: f32) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_f64(writer: &mut Vec<u8>, data: f64) { writer.extend_from_slice(&data.to_le_bytes()); }
     #[inline]
     fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]) {
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_le_bytes());
         // 写入数据
-        writer.extend_from_slice(data.iter().map(|x| *x as u8).collect::<Vec<u8>>().as_slice());
+        extend_i8_array(writer, data);
     }
     #[inline]
     fn write_i32_array(writer: &mut Vec<u8>, data: &[i32]) {
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_le_bytes());
         // 写入数据
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_le_bytes()).collect::<Vec<[u8; 4]>>().concat(),
-        );
+        extend_le_i32_array(writer, data);
     }
     #[inline]
     fn write_i64_array(writer: &mut Vec<u8>, data: &[i64]) {
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_le_bytes());
         // 写入数据
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_le_bytes()).collect::<Vec<[u8; 8]>>().concat(),
-        );
+        extend_le_i64_array(writer, data);
     }
     #[inline]
     fn write_nbt_string(writer: &mut Vec<u8>, data: &str) {
@@ -238,50 +248,39 @@ impl NbtWriteTrait for BedrockDisk {
         writer.extend_from_slice(data.as_bytes());
     }
     #[inline]
-    fn write_list(writer: &mut Vec<u8>, data: &[NbtValue]) -> NbtResult<()> {
-        if data.is_empty() {
-            // 写入一个空的 tag
-            writer.extend_from_slice(&0i8.to_le_bytes());
-            return Ok(());
-        }
-        // 遍历检查一遍所有的 tag 是否一致
-        let tag = data.first().unwrap().tag();
-        if !data.iter().all(|x| x.tag() == tag) {
-            return Err(NbtError::ListTypeNotSame(data.iter().map(|x| x.tag()).collect()));
-        }
+    fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()> {
         // 写入 tag
-        writer.push(tag);
+        writer.push(data.element_tag());
         // 写入长度
         writer.extend_from_slice(&(data.len() as i32).to_le_bytes());
-        // 写入数据
-        for i in data {
-            match i {
-                NbtValue::Byte(x) => writer.push(*x as u8),
-                NbtValue::Short(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Int(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Long(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Float(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Double(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::ByteArray(x) => Self::write_i8_array(writer, x),
-                NbtValue::IntArray(x) => Self::write_i32_array(writer, x),
-                NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
-                NbtValue::String(x) => Self::write_nbt_string(writer, x),
-                NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
+        // 写入数据, 类型由 NbtList 保证一致, 不用再检查
+        match data {
+            NbtList::End => (),
+            NbtList::Byte(x) => extend_i8_array(writer, x),
+            NbtList::Short(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::Int(x) => extend_le_i32_array(writer, x),
+            NbtList::Long(x) => extend_le_i64_array(writer, x),
+            NbtList::Float(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::Double(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::ByteArray(x) => x.iter().for_each(|v| Self::write_i8_array(writer, v)),
+            NbtList::String(x) => x.iter().for_each(|v| Self::write_nbt_string(writer, v)),
+            NbtList::List(x) => {
+                for v in x {
+                    Self::write_list(writer, v)?;
+                }
+            }
+            NbtList::Compound(x) => {
+                for v in x {
+                    Self::write_compound(writer, v)?;
                 }
             }
+            NbtList::IntArray(x) => x.iter().for_each(|v| Self::write_i32_array(writer, v)),
+            NbtList::LongArray(x) => x.iter().for_each(|v| Self::write_i64_array(writer, v)),
         }
         Ok(())
     }
     #[inline]
-    fn write_compound(
-        writer: &mut Vec<u8>,
-        name: Option<&String>,
-        data: &[(String, NbtValue)],
-    ) -> NbtResult<()> {
-        // 写入自己的名字
-        Self::write_nbt_string(writer, name.unwrap_or(&"".to_string()));
+    fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()> {
         for (key, value) in data {
             // 写入 tag
             writer.push(value.tag());
@@ -300,9 +299,7 @@ impl NbtWriteTrait for BedrockDisk {
                 NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
                 NbtValue::String(x) => Self::write_nbt_string(writer, x),
                 NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
-                }
+                NbtValue::Compound(data) => Self::write_compound(writer, data)?,
             }
         }
         // 写入结束 tag
@@ -310,176 +307,179 @@ impl NbtWriteTrait for BedrockDisk {
         Ok(())
     }
 
-    fn write_to(value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        match value {
-            NbtValue::Compound(name, data) => {
-                buff.push(value.tag());
-                Self::write_compound(buff, name.as_ref(), data)?
-            }
-            NbtValue::List(data) => {
-                buff.push(value.tag());
-                Self::write_list(buff, data)?
-            }
-            x => return Err(NbtError::WrongRootType(x.tag())),
-        }
-        Ok(())
-    }
-
-    fn write_to_with_name(name: &str, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        buff.push(value.tag());
-        // 写入 key
-        Self::write_nbt_string(buff, name);
-        // 写入 value
-        Self::write_to(value, buff)?;
-        Ok(())
-    }
+    fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> { bedrock_root_name_rule(tag) }
+    #[inline]
+    fn i32_len(_value: i32) -> usize { 4 }
+    #[inline]
+    fn i64_len(_value: i64) -> usize { 8 }
+    #[inline]
+    fn nbt_string_len(data: &str) -> usize { 2 + data.len() }
+}
 
-    fn to_bytes(value: &NbtValue) -> NbtResult<Vec<u8>> {
-        let mut buff = Vec::new();
-        BedrockDisk::write_to(value, &mut buff)?;
-        Ok(buff)
+/// 基岩版的两种格式根节点规则一样
+///
+/// 有名字的 Compound 或者没有名字的 List
+fn bedrock_root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> {
+    match tag {
+        10 => Ok(RootName::Optional),
+        9 => Ok(RootName::Forbidden),
+        x => Err(NbtError::WrongRootType(x)),
     }
 }
 
-pub fn var_i32_to_bytes(value: i32) -> Vec<u8> {
-    let mut buff = Vec::new();
-    let mut value = value;
+/// 直接写入一个 var i32, 不额外分配
+///
+/// 按 u32 处理, 负数也能正常结束
+#[inline]
+pub fn write_var_i32(writer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
     loop {
         let mut temp = (value & 0b01111111) as u8;
         value >>= 7;
         if value != 0 {
             temp |= 0b10000000;
         }
-        buff.push(temp);
+        writer.push(temp);
         if value == 0 {
             break;
         }
     }
-    buff
 }
 
-pub fn var_i64_to_bytes(value: i64) -> Vec<u8> {
-    let mut buff = Vec::new();
-    let mut value = value;
+/// 直接写入一个 var i64, 不额外分配
+///
+/// 按 u64 处理, 负数也能正常结束
+#[inline]
+pub fn write_var_i64(writer: &mut Vec<u8>, value: i64) {
+    let mut value = value as u64;
     loop {
         let mut temp = (value & 0b01111111) as u8;
         value >>= 7;
         if value != 0 {
             temp |= 0b10000000;
         }
-        buff.push(temp);
+        writer.push(temp);
         if value == 0 {
             break;
         }
     }
+}
+
+#[inline]
+pub fn write_zigzag_var_i32(writer: &mut Vec<u8>, value: i32) {
+    write_var_i32(writer, (value << 1) ^ (value >> 31))
+}
+
+#[inline]
+pub fn write_zigzag_var_i64(writer: &mut Vec<u8>, value: i64) {
+    write_var_i64(writer, (value << 1) ^ (value >> 63))
+}
+
+pub fn var_i32_to_bytes(value: i32) -> Vec<u8> {
+    let mut buff = Vec::with_capacity(5);
+    write_var_i32(&mut buff, value);
     buff
 }
 
-pub fn zigzag_var_i32_to_bytes(value: i32) -> Vec<u8> {
-    let mut buff = Vec::new();
-    let mut value = (value << 1) ^ (value >> 31);
-    loop {
-        let mut temp = (value & 0b01111111) as u8;
-        value >>= 7;
-        if value != 0 {
-            temp |= 0b10000000;
-        }
-        buff.push(temp);
-        if value == 0 {
-            break;
-        }
-    }
+pub fn var_i64_to_bytes(value: i64) -> Vec<u8> {
+    let mut buff = Vec::with_capacity(10);
+    write_var_i64(&mut buff, value);
     buff
 }
 
+pub fn zigzag_var_i32_to_bytes(value: i32) -> Vec<u8> {
+    var_i32_to_bytes((value << 1) ^ (value >> 31))
+}
+
 pub fn zigzag_var_i64_to_bytes(value: i64) -> Vec<u8> {
-    let mut buff = Vec::new();
-    let mut value = (value << 1) ^ (value >> 63);
-    loop {
-        let mut temp = (value & 0b01111111) as u8;
-        value >>= 7;
-        if value != 0 {
-            temp |= 0b10000000;
-        }
-        buff.push(temp);
-        if value == 0 {
-            break;
-        }
-    }
-    buff
+    var_i64_to_bytes((value << 1) ^ (value >> 63))
+}
+
+/// var i32 编码后的长度 (1..=5)
+#[inline]
+pub fn var_i32_len(value: i32) -> usize {
+    // 每 7 bit 一个字节, 0 也要占一个字节
+    let bits = 32 - (value as u32 | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// var i64 编码后的长度 (1..=10)
+#[inline]
+pub fn var_i64_len(value: i64) -> usize {
+    let bits = 64 - (value as u64 | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
 }
 
+#[inline]
+pub fn zigzag_var_i32_len(value: i32) -> usize { var_i32_len((value << 1) ^ (value >> 31)) }
+
+#[inline]
+pub fn zigzag_var_i64_len(value: i64) -> usize { var_i64_len((value << 1) ^ (value >> 63)) }
+
 /// 最痛苦的一集
 impl NbtWriteTrait for BedrockNetVarInt {
+    #[inline]
+    fn write_i16(writer: &mut Vec<u8>, data: i16) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_i32(writer: &mut Vec<u8>, data: i32) { write_zigzag_var_i32(writer, data); }
+    #[inline]
+    fn write_i64(writer: &mut Vec<u8>, data: i64) { write_zigzag_var_i64(writer, data); }
+    #[inline]
+    fn write_f32(writer: &mut Vec<u8>, data: f32) { writer.extend_from_slice(&data.to_le_bytes()); }
+    #[inline]
+    fn write_f64(writer: &mut Vec<u8>, data: f64) { writer.extend_from_slice(&data.to_le_bytes()); }
     fn write_i8_array(writer: &mut Vec<u8>, data: &[i8]) {
         // zigzag var i32
-        writer.extend_from_slice(&zigzag_var_i32_to_bytes(data.len() as i32));
-        writer.extend_from_slice(data.iter().map(|x| *x as u8).collect::<Vec<u8>>().as_slice());
+        write_zigzag_var_i32(writer, data.len() as i32);
+        extend_i8_array(writer, data);
     }
     fn write_i32_array(writer: &mut Vec<u8>, data: &[i32]) {
         // zigzag var i32
-        writer.extend_from_slice(&zigzag_var_i32_to_bytes(data.len() as i32));
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_le_bytes()).collect::<Vec<[u8; 4]>>().concat(),
-        );
+        write_zigzag_var_i32(writer, data.len() as i32);
+        extend_le_i32_array(writer, data);
     }
     fn write_i64_array(writer: &mut Vec<u8>, data: &[i64]) {
         // zigzag var i32
-        writer.extend_from_slice(&zigzag_var_i32_to_bytes(data.len() as i32));
-        writer.extend_from_slice(
-            &data.iter().map(|x| x.to_le_bytes()).collect::<Vec<[u8; 8]>>().concat(),
-        );
+        write_zigzag_var_i32(writer, data.len() as i32);
+        extend_le_i64_array(writer, data);
     }
     fn write_nbt_string(writer: &mut Vec<u8>, data: &str) {
-        // zigzag var i32
-        writer.extend_from_slice(&zigzag_var_i32_to_bytes(data.len() as i32));
+        // 字符串长度是普通的 var i32, 不是 zigzag
+        write_var_i32(writer, data.len() as i32);
         writer.extend_from_slice(data.as_bytes());
     }
-    fn write_list(writer: &mut Vec<u8>, data: &[NbtValue]) -> NbtResult<()> {
-        if data.is_empty() {
-            // 写入一个空的 tag
-            writer.extend_from_slice(&0i8.to_le_bytes());
-            return Ok(());
-        }
-        // 遍历检查一遍所有的 tag 是否一致
-        let tag = data.first().unwrap().tag();
-        if !data.iter().all(|x| x.tag() == tag) {
-            return Err(NbtError::ListTypeNotSame(data.iter().map(|x| x.tag()).collect()));
-        }
+    fn write_list(writer: &mut Vec<u8>, data: &NbtList) -> NbtResult<()> {
         // 写入 tag
-        writer.push(tag);
-        // zigzag var i32
-        writer.extend_from_slice(&zigzag_var_i32_to_bytes(data.len() as i32));
-        // 写入数据
-        for i in data {
-            match i {
-                NbtValue::Byte(x) => writer.push(*x as u8),
-                NbtValue::Short(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Int(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Long(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Float(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Double(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::ByteArray(x) => Self::write_i8_array(writer, x),
-                NbtValue::IntArray(x) => Self::write_i32_array(writer, x),
-                NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
-                NbtValue::String(x) => Self::write_nbt_string(writer, x),
-                NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
+        writer.push(data.element_tag());
+        // 写入长度
+        write_zigzag_var_i32(writer, data.len() as i32);
+        // 写入数据, 类型由 NbtList 保证一致, 不用再检查
+        match data {
+            NbtList::End => (),
+            NbtList::Byte(x) => extend_i8_array(writer, x),
+            NbtList::Short(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::Int(x) => x.iter().for_each(|v| write_zigzag_var_i32(writer, *v)),
+            NbtList::Long(x) => x.iter().for_each(|v| write_zigzag_var_i64(writer, *v)),
+            NbtList::Float(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::Double(x) => x.iter().for_each(|v| writer.extend_from_slice(&v.to_le_bytes())),
+            NbtList::ByteArray(x) => x.iter().for_each(|v| Self::write_i8_array(writer, v)),
+            NbtList::String(x) => x.iter().for_each(|v| Self::write_nbt_string(writer, v)),
+            NbtList::List(x) => {
+                for v in x {
+                    Self::write_list(writer, v)?;
+                }
+            }
+            NbtList::Compound(x) => {
+                for v in x {
+                    Self::write_compound(writer, v)?;
                 }
             }
+            NbtList::IntArray(x) => x.iter().for_each(|v| Self::write_i32_array(writer, v)),
+            NbtList::LongArray(x) => x.iter().for_each(|v| Self::write_i64_array(writer, v)),
         }
         Ok(())
     }
-    fn write_compound(
-        writer: &mut Vec<u8>,
-        name: Option<&String>,
-        data: &[(String, NbtValue)],
-    ) -> NbtResult<()> {
-        // 写入自己的名字
-        Self::write_nbt_string(writer, name.unwrap_or(&"".to_string()));
+    fn write_compound(writer: &mut Vec<u8>, data: &NbtCompound) -> NbtResult<()> {
         for (key, value) in data {
             // 写入 tag
             writer.push(value.tag());
@@ -489,8 +489,8 @@ impl NbtWriteTrait for BedrockNetVarInt {
             match value {
                 NbtValue::Byte(x) => writer.push(*x as u8),
                 NbtValue::Short(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Int(x) => writer.extend_from_slice(&x.to_le_bytes()),
-                NbtValue::Long(x) => writer.extend_from_slice(&x.to_le_bytes()),
+                NbtValue::Int(x) => write_zigzag_var_i32(writer, *x),
+                NbtValue::Long(x) => write_zigzag_var_i64(writer, *x),
                 NbtValue::Float(x) => writer.extend_from_slice(&x.to_le_bytes()),
                 NbtValue::Double(x) => writer.extend_from_slice(&x.to_le_bytes()),
                 NbtValue::ByteArray(x) => Self::write_i8_array(writer, x),
@@ -498,9 +498,7 @@ impl NbtWriteTrait for BedrockNetVarInt {
                 NbtValue::LongArray(x) => Self::write_i64_array(writer, x),
                 NbtValue::String(x) => Self::write_nbt_string(writer, x),
                 NbtValue::List(x) => Self::write_list(writer, x)?,
-                NbtValue::Compound(name, data) => {
-                    Self::write_compound(writer, name.as_ref(), data)?
-                }
+                NbtValue::Compound(data) => Self::write_compound(writer, data)?,
             }
         }
         // 写入结束 tag
@@ -508,33 +506,376 @@ impl NbtWriteTrait for BedrockNetVarInt {
         Ok(())
     }
 
-    fn write_to(value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        match value {
-            NbtValue::Compound(name, data) => {
-                buff.push(value.tag());
-                Self::write_compound(buff, name.as_ref(), data)?
-            }
-            NbtValue::List(data) => {
-                buff.push(value.tag());
-                Self::write_list(buff, data)?
-            }
-            x => return Err(NbtError::WrongRootType(x.tag())),
+    fn root_name_rule(tag: NbtTypeId) -> NbtResult<RootName> { bedrock_root_name_rule(tag) }
+    #[inline]
+    fn i32_len(value: i32) -> usize { zigzag_var_i32_len(value) }
+    #[inline]
+    fn i64_len(value: i64) -> usize { zigzag_var_i64_len(value) }
+    #[inline]
+    fn nbt_string_len(data: &str) -> usize { var_i32_len(data.len() as i32) + data.len() }
+}
+
+/// 写入一个值的数据部分 (不含 tag 和名字)
+fn write_payload<W: NbtWriteTrait>(buff: &mut Vec<u8>, value: &NbtValue) -> NbtResult<()> {
+    match value {
+        NbtValue::Byte(x) => buff.push(*x as u8),
+        NbtValue::Short(x) => W::write_i16(buff, *x),
+        NbtValue::Int(x) => W::write_i32(buff, *x),
+        NbtValue::Long(x) => W::write_i64(buff, *x),
+        NbtValue::Float(x) => W::write_f32(buff, *x),
+        NbtValue::Double(x) => W::write_f64(buff, *x),
+        NbtValue::ByteArray(x) => W::write_i8_array(buff, x),
+        NbtValue::IntArray(x) => W::write_i32_array(buff, x),
+        NbtValue::LongArray(x) => W::write_i64_array(buff, x),
+        NbtValue::String(x) => W::write_nbt_string(buff, x),
+        NbtValue::List(x) => W::write_list(buff, x)?,
+        NbtValue::Compound(x) => W::write_compound(buff, x)?,
+    }
+    Ok(())
+}
+
+/// 当前正在写的容器, `path` 是它在根节点里的路径
+enum Frame {
+    Compound {
+        path: String,
+    },
+    List {
+        path: String,
+        /// 元素类型
+        tag: NbtTypeId,
+        /// 声明的长度
+        len: usize,
+        /// 已经写入的个数
+        written: usize,
+    },
+}
+
+impl Frame {
+    fn tag(&self) -> NbtTypeId {
+        match self {
+            Frame::Compound { .. } => 10,
+            Frame::List { .. } => 9,
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            Frame::Compound { path } | Frame::List { path, .. } => path,
+        }
+    }
+}
+
+/// 流式写入 NBT 数据
+///
+/// 不需要先构造一棵 `NbtValue`, 直接写入字节
+///
+/// 嵌套关系 和 List 的元素类型/个数 会在运行时检查,
+/// 出错的调用不会在 buffer 里留下写了一半的数据, 改正之后可以接着写
+///
+/// - 在不对的地方调用 (比如 List 里用 `field_xxx`, 根节点外面写值) 返回
+///   `IncorrectType(需要的容器类型, 当前的容器类型)`, 根节点外面的类型是 0
+/// - List 的元素类型不对返回 `ListTypeNotSame`
+/// - List 的元素个数不对返回 `InvalidIndex(List 的路径, 第一个有问题的下标)`:
+///   写多了是声明的长度, `end` 的时候没写满是还没写的那一个, 长度超过 `i32::MAX` 是 `i32::MAX`
+///
+/// ```rust
+/// use shen_nbt5::nbt_version::Java;
+/// use shen_nbt5::writer::NbtWriter;
+///
+/// let mut writer = NbtWriter::<Java>::new();
+/// writer.begin_root_compound(Some("root")).unwrap();
+/// writer.field_i32("x", 1).unwrap();
+/// writer.begin_list("pos", 6, 2).unwrap();
+/// writer.item_f64(0.5).unwrap().item_f64(1.5).unwrap();
+/// writer.end().unwrap();
+/// writer.end().unwrap();
+/// let data = writer.finish().unwrap();
+/// ```
+pub struct NbtWriter<W: NbtWriteTrait> {
+    buff: Vec<u8>,
+    stack: Vec<Frame>,
+    /// 已经写完的根节点类型
+    root: Option<NbtTypeId>,
+    _format: std::marker::PhantomData<W>,
+}
+
+/// 同时生成 Compound 里的 `field_xxx` 和 List 里的 `item_xxx`
+macro_rules! writer_value {
+    ($field:ident, $item:ident, $ty:ty, $tag:expr, |$buff:ident, $v:ident| $body:expr) => {
+        /// 在 Compound 里写入一个值
+        pub fn $field(&mut self, key: &str, $v: $ty) -> NbtResult<&mut Self> {
+            self.field($tag, key, |$buff| {
+                $body;
+                Ok(())
+            })
+        }
+        /// 在 List 里写入一个元素
+        pub fn $item(&mut self, $v: $ty) -> NbtResult<&mut Self> {
+            self.item($tag, |$buff| {
+                $body;
+                Ok(())
+            })
+        }
+    };
+}
+
+impl<W: NbtWriteTrait> Default for NbtWriter<W> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<W: NbtWriteTrait> NbtWriter<W> {
+    pub fn new() -> Self { Self::with_capacity(0) }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buff: Vec::with_capacity(capacity),
+            stack: Vec::new(),
+            root: None,
+            _format: std::marker::PhantomData,
+        }
+    }
+
+    /// 当前嵌套深度, 0 表示在根节点外面
+    pub fn depth(&self) -> usize { self.stack.len() }
+
+    /// 当前正在写的容器, 需要的是 `expect` 类型
+    fn current(&mut self, expect: NbtTypeId) -> NbtResult<&mut Frame> {
+        self.stack.last_mut().ok_or(NbtError::IncorrectType(expect, 0))
+    }
+
+    /// 当前 Compound 里 `key` 的路径
+    fn key_path(&self, key: &str) -> String {
+        match self.stack.last().map(Frame::path) {
+            Some("") | None => path_key(key),
+            Some(path) => format!("{}.{}", path, path_key(key)),
+        }
+    }
+
+    /// 当前 List 里第 `index` 个元素的路径
+    fn index_path(&self, index: usize) -> String {
+        format!("{}[{}]", self.stack.last().map_or("", Frame::path), index)
+    }
+
+    /// 写入 Compound 里一个值的 tag 和 key
+    fn field_header(&mut self, tag: NbtTypeId, key: &str) -> NbtResult<()> {
+        if let Frame::List { .. } = self.current(10)? {
+            return Err(NbtError::IncorrectType(10, 9));
         }
+        self.buff.push(tag);
+        W::write_nbt_string(&mut self.buff, key);
         Ok(())
     }
-    fn write_to_with_name(name: &str, value: &NbtValue, buff: &mut Vec<u8>) -> NbtResult<()> {
-        // 写入 tag
-        buff.push(value.tag());
-        // 写入 key
-        Self::write_nbt_string(buff, name);
-        // 写入 value
-        Self::write_to(value, buff)?;
+
+    /// 检查 List 的元素类型和个数, 返回这个元素的下标
+    ///
+    /// 写入成功之后再用 `count_item` 计数
+    fn item_header(&mut self, tag: NbtTypeId) -> NbtResult<usize> {
+        match self.current(9)? {
+            Frame::List {
+                path,
+                tag: list_tag,
+                len,
+                written,
+            } => {
+                if *list_tag != tag {
+                    return Err(NbtError::ListTypeNotSame(vec![*list_tag, tag]));
+                }
+                if written >= len {
+                    return Err(NbtError::InvalidIndex(path.clone(), *len as i64));
+                }
+                Ok(*written)
+            }
+            Frame::Compound { .. } => Err(NbtError::IncorrectType(9, 10)),
+        }
+    }
+
+    fn count_item(&mut self) {
+        if let Some(Frame::List { written, .. }) = self.stack.last_mut() {
+            *written += 1;
+        }
+    }
+
+    /// 在 Compound 里写入一个值, 失败的时候撤回已经写入的 tag, key 和数据
+    fn field(
+        &mut self,
+        tag: NbtTypeId,
+        key: &str,
+        payload: impl FnOnce(&mut Vec<u8>) -> NbtResult<()>,
+    ) -> NbtResult<&mut Self> {
+        let start = self.buff.len();
+        self.field_header(tag, key)?;
+        if let Err(e) = payload(&mut self.buff) {
+            self.buff.truncate(start);
+            return Err(e);
+        }
+        Ok(self)
+    }
+
+    /// 在 List 里写入一个元素, 失败的时候撤回已经写入的数据, 也不计数
+    fn item(
+        &mut self,
+        tag: NbtTypeId,
+        payload: impl FnOnce(&mut Vec<u8>) -> NbtResult<()>,
+    ) -> NbtResult<&mut Self> {
+        self.item_header(tag)?;
+        let start = self.buff.len();
+        if let Err(e) = payload(&mut self.buff) {
+            self.buff.truncate(start);
+            return Err(e);
+        }
+        self.count_item();
+        Ok(self)
+    }
+
+    /// 检查 List 的元素类型和长度, 在写入任何东西之前调用
+    fn check_list(tag: NbtTypeId, len: usize, path: &str) -> NbtResult<()> {
+        // End 只能用于空 List
+        if tag > 12 || (tag == 0 && len != 0) {
+            return Err(NbtError::UnknownType(tag));
+        }
+        // 长度前缀是 i32
+        if len > i32::MAX as usize {
+            return Err(NbtError::InvalidIndex(path.to_string(), i32::MAX as i64));
+        }
         Ok(())
     }
-    fn to_bytes(value: &NbtValue) -> NbtResult<Vec<u8>> {
-        let mut buff = Vec::new();
-        BedrockNetVarInt::write_to(value, &mut buff)?;
-        Ok(buff)
+
+    /// 写入 List 的元素类型和长度
+    fn list_header(&mut self, path: String, tag: NbtTypeId, len: usize) {
+        self.buff.push(tag);
+        W::write_i32(&mut self.buff, len as i32);
+        self.stack.push(Frame::List {
+            path,
+            tag,
+            len,
+            written: 0,
+        });
+    }
+
+    /// 写入根节点的 tag 和名字
+    fn root_header(&mut self, tag: NbtTypeId, name: Option<&str>) -> NbtResult<()> {
+        if let Some(frame) = self.stack.last() {
+            return Err(NbtError::IncorrectType(0, frame.tag()));
+        }
+        if let Some(root) = self.root {
+            return Err(NbtError::IncorrectType(0, root));
+        }
+        W::write_root_header(&mut self.buff, tag, name)
+    }
+
+    /// 开始根节点 Compound
+    ///
+    /// 名字是否允许由格式决定, 见 `NbtWriteTrait::root_name_rule`
+    pub fn begin_root_compound(&mut self, name: Option<&str>) -> NbtResult<&mut Self> {
+        self.root_header(10, name)?;
+        self.stack.push(Frame::Compound {
+            path: String::new(),
+        });
+        Ok(self)
+    }
+
+    /// 开始根节点 List (仅基岩版), 需要提前给出元素类型和长度
+    pub fn begin_root_list(&mut self, tag: NbtTypeId, len: usize) -> NbtResult<&mut Self> {
+        Self::check_list(tag, len, "")?;
+        self.root_header(9, None)?;
+        self.list_header(String::new(), tag, len);
+        Ok(self)
+    }
+
+    /// 在 Compound 里开始一个 Compound
+    pub fn begin_compound(&mut self, key: &str) -> NbtResult<&mut Self> {
+        self.field_header(10, key)?;
+        let path = self.key_path(key);
+        self.stack.push(Frame::Compound { path });
+        Ok(self)
+    }
+
+    /// 在 Compound 里开始一个 List, 需要提前给出元素类型和长度
+    pub fn begin_list(&mut self, key: &str, tag: NbtTypeId, len: usize) -> NbtResult<&mut Self> {
+        let path = self.key_path(key);
+        Self::check_list(tag, len, &path)?;
+        self.field_header(9, key)?;
+        self.list_header(path, tag, len);
+        Ok(self)
+    }
+
+    /// 在 List 里开始一个 Compound
+    pub fn begin_compound_item(&mut self) -> NbtResult<&mut Self> {
+        let index = self.item_header(10)?;
+        let path = self.index_path(index);
+        self.count_item();
+        self.stack.push(Frame::Compound { path });
+        Ok(self)
+    }
+
+    /// 在 List 里开始一个 List
+    pub fn begin_list_item(&mut self, tag: NbtTypeId, len: usize) -> NbtResult<&mut Self> {
+        let index = self.item_header(9)?;
+        let path = self.index_path(index);
+        Self::check_list(tag, len, &path)?;
+        self.count_item();
+        self.list_header(path, tag, len);
+        Ok(self)
+    }
+
+    /// 结束当前的 Compound 或者 List
+    ///
+    /// List 没写满的时候不会结束, 补齐之后可以再调用
+    pub fn end(&mut self) -> NbtResult<&mut Self> {
+        let compound = match self.current(10)? {
+            Frame::Compound { .. } => true,
+            Frame::List {
+                path, len, written, ..
+            } => {
+                if written < len {
+                    return Err(NbtError::InvalidIndex(path.clone(), *written as i64));
+                }
+                false
+            }
+        };
+        if compound {
+            self.buff.push(0);
+        }
+        let frame = self.stack.pop().expect("上面检查过了");
+        if self.stack.is_empty() {
+            self.root = Some(frame.tag());
+        }
+        Ok(self)
+    }
+
+    /// 完成写入, 返回写好的数据
+    pub fn finish(self) -> NbtResult<Vec<u8>> {
+        if let Some(frame) = self.stack.last() {
+            return Err(NbtError::IncorrectType(0, frame.tag()));
+        }
+        if self.root.is_none() {
+            return Err(NbtError::IncorrectType(10, 0));
+        }
+        Ok(self.buff)
+    }
+
+    writer_value!(field_i8, item_i8, i8, 1, |buff, v| buff.push(v as u8));
+    writer_value!(field_i16, item_i16, i16, 2, |buff, v| W::write_i16(buff, v));
+    writer_value!(field_i32, item_i32, i32, 3, |buff, v| W::write_i32(buff, v));
+    writer_value!(field_i64, item_i64, i64, 4, |buff, v| W::write_i64(buff, v));
+    writer_value!(field_f32, item_f32, f32, 5, |buff, v| W::write_f32(buff, v));
+    writer_value!(field_f64, item_f64, f64, 6, |buff, v| W::write_f64(buff, v));
+    writer_value!(field_i8_array, item_i8_array, &[i8], 7, |buff, v| W::write_i8_array(buff, v));
+    writer_value!(field_string, item_string, &str, 8, |buff, v| W::write_nbt_string(buff, v));
+    writer_value!(field_i32_array, item_i32_array, &[i32], 11, |buff, v| W::write_i32_array(
+        buff, v
+    ));
+    writer_value!(field_i64_array, item_i64_array, &[i64], 12, |buff, v| W::write_i64_array(
+        buff, v
+    ));
+
+    /// 在 Compound 里写入一个现成的 `NbtValue`
+    pub fn field_value(&mut self, key: &str, value: &NbtValue) -> NbtResult<&mut Self> {
+        self.field(value.tag(), key, |buff| write_payload::<W>(buff, value))
+    }
+
+    /// 在 List 里写入一个现成的 `NbtValue`
+    pub fn item_value(&mut self, value: &NbtValue) -> NbtResult<&mut Self> {
+        self.item(value.tag(), |buff| write_payload::<W>(buff, value))
     }
 }